@@ -1,4 +1,5 @@
 pub mod model {
+  pub mod curve;
   pub mod field_elements;
   pub mod point;
   pub mod s256_field;
@@ -6,4 +7,27 @@ pub mod model {
   pub mod s256_point;
   pub mod private_key;
   pub mod constants;
+  pub mod helper;
+  pub mod schnorr;
+  pub mod taproot;
+  pub mod musig;
+  pub mod frost;
+  pub mod shamir;
+  pub mod ecdh;
+  pub mod ecies;
+  pub mod bip32;
+  pub mod mnemonic;
+  pub mod slip39;
+  pub mod keystore;
+  pub mod der;
+  pub mod pem;
+  pub mod jose;
+  pub mod ecdsa;
+  pub mod edwards;
+  pub mod ed25519;
+  pub mod montgomery;
+  pub mod extension_fields;
+  pub mod bls12_381;
+  pub mod bls;
+  pub mod hash_to_curve;
 }
\ No newline at end of file
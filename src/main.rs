@@ -16,14 +16,13 @@ fn main() {
   let mut rng = rand::thread_rng();
   
   let r1 = rng.gen_biguint(256).to_bigint();
-  let r2 = rng.gen_bigint_range(&BigInt::from(0), &N);
   
   let z = match r1.clone() {
     Some(r1_) => r1_,
     _ => panic!("no N")
   };
 
-  let pk = PrivateKey::new(r2);
+  let pk = PrivateKey::generate(&mut rng);
   let sig = pk.sign(z.clone());
   
   let z_message = "안녕하세요";
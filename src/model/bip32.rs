@@ -0,0 +1,448 @@
+//! BIP32 hierarchical deterministic keys
+//! https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+
+use std::str::FromStr;
+
+use hmac::{Hmac, Mac};
+use num_bigint::BigInt;
+use num_traits::{Euclid, Zero};
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use super::{
+  constants::{Gs, N},
+  helper::{bytes_to_int, decode_base58_checksum, encode_base58_checksum, hash160, SecretScalar},
+  private_key::PrivateKey,
+  s256_point::S256Point,
+};
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// child numbers at or above this are hardened
+pub const HARDENED: u32 = 0x8000_0000;
+
+const SERIALIZED_LENGTH: usize = 78;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Network {
+  /// xprv / xpub
+  Mainnet,
+  /// tprv / tpub
+  Testnet,
+}
+
+impl Network {
+  fn private_version(&self) -> [u8; 4] {
+    match self {
+      Network::Mainnet => [0x04, 0x88, 0xad, 0xe4],
+      Network::Testnet => [0x04, 0x35, 0x83, 0x94],
+    }
+  }
+
+  fn public_version(&self) -> [u8; 4] {
+    match self {
+      Network::Mainnet => [0x04, 0x88, 0xb2, 0x1e],
+      Network::Testnet => [0x04, 0x35, 0x87, 0xcf],
+    }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Bip32Error {
+  /// seeds are 16 to 64 bytes
+  InvalidSeedLength,
+  /// I_L is zero or not less than n; the caller should move on to the next index
+  InvalidChildKey,
+  /// hardened children need the private key
+  HardenedFromPublic,
+  /// past depth 255
+  MaxDepthExceeded,
+  /// not of the form `m/84'/0'/0'/0/5`
+  InvalidPath,
+  /// not base58check, or not 78 bytes
+  InvalidEncoding,
+  /// version bytes of neither a mainnet nor a testnet key, or of the wrong key type
+  UnknownVersion,
+  /// the key data is not a valid private key or public key
+  InvalidKeyData,
+  /// depth 0 with a non-zero parent fingerprint or child number
+  InvalidMasterKey,
+}
+
+/// child numbers from the master key, hardened ones offset by `HARDENED`
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct DerivationPath(pub Vec<u32>);
+
+impl FromStr for DerivationPath {
+  type Err = Bip32Error;
+
+  /// `m/84'/0'/0'/0/5`, hardened steps marked with `'` or `h`
+  fn from_str(path: &str) -> Result<Self, Self::Err> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+      return Err(Bip32Error::InvalidPath)
+    }
+    parts
+      .map(|part| {
+        let (digits, offset) = match part.strip_suffix('\'').or_else(|| part.strip_suffix('h')) {
+          Some(digits) => (digits, HARDENED),
+          None => (part, 0),
+        };
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+          return Err(Bip32Error::InvalidPath)
+        }
+        match digits.parse::<u32>() {
+          Ok(index) if index < HARDENED => Ok(index + offset),
+          _ => Err(Bip32Error::InvalidPath),
+        }
+      })
+      .collect::<Result<Vec<u32>, Bip32Error>>()
+      .map(DerivationPath)
+  }
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+  let mut mac = HmacSha512::new_from_slice(key)
+    .expect("HMAC can take key of any size");
+  mac.update(data);
+  let i = mac.finalize().into_bytes();
+  let (mut il, mut ir) = ([0u8; 32], [0u8; 32]);
+  il.copy_from_slice(&i[..32]);
+  ir.copy_from_slice(&i[32..]);
+  (il, ir)
+}
+
+/// I_L as a scalar, rejected if zero or not less than n
+fn parse_il(il: &[u8; 32]) -> Result<BigInt, Bip32Error> {
+  let il = bytes_to_int(il);
+  if il.is_zero() || il >= *N {
+    return Err(Bip32Error::InvalidChildKey)
+  }
+  Ok(il)
+}
+
+fn fingerprint(public_key: &S256Point) -> [u8; 4] {
+  let hash = hash160(&public_key.sec(true));
+  [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn serialize(
+  version: [u8; 4], depth: u8, parent_fingerprint: [u8; 4],
+  child_number: u32, chain_code: &[u8; 32], key_data: &[u8]
+) -> String {
+  let mut out = Vec::with_capacity(SERIALIZED_LENGTH);
+  out.extend_from_slice(&version);
+  out.push(depth);
+  out.extend_from_slice(&parent_fingerprint);
+  out.extend_from_slice(&child_number.to_be_bytes());
+  out.extend_from_slice(chain_code);
+  out.extend_from_slice(key_data);
+  encode_base58_checksum(&out)
+}
+
+/// (version, depth, parent fingerprint, child number, chain code, key data)
+type Fields = ([u8; 4], u8, [u8; 4], u32, [u8; 32], [u8; 33]);
+
+fn deserialize(s: &str) -> Result<Fields, Bip32Error> {
+  let data = decode_base58_checksum(s).ok_or(Bip32Error::InvalidEncoding)?;
+  if data.len() != SERIALIZED_LENGTH {
+    return Err(Bip32Error::InvalidEncoding)
+  }
+  let (mut version, mut parent_fingerprint, mut chain_code, mut key_data) =
+    ([0u8; 4], [0u8; 4], [0u8; 32], [0u8; 33]);
+  version.copy_from_slice(&data[..4]);
+  let depth = data[4];
+  parent_fingerprint.copy_from_slice(&data[5..9]);
+  let child_number = u32::from_be_bytes([data[9], data[10], data[11], data[12]]);
+  chain_code.copy_from_slice(&data[13..45]);
+  key_data.copy_from_slice(&data[45..]);
+
+  if depth == 0 && (parent_fingerprint != [0u8; 4] || child_number != 0) {
+    return Err(Bip32Error::InvalidMasterKey)
+  }
+  Ok((version, depth, parent_fingerprint, child_number, chain_code, key_data))
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtendedPrivateKey {
+  pub network: Network,
+  pub depth: u8,
+  pub parent_fingerprint: [u8; 4],
+  pub child_number: u32,
+  pub chain_code: [u8; 32],
+  pub private_key: PrivateKey,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ExtendedPublicKey {
+  pub network: Network,
+  pub depth: u8,
+  pub parent_fingerprint: [u8; 4],
+  pub child_number: u32,
+  pub chain_code: [u8; 32],
+  pub public_key: S256Point,
+}
+
+impl ExtendedPrivateKey {
+  /// I = HMAC-SHA512("Bitcoin seed", seed), master secret I_L and chain code I_R
+  pub fn new_master(seed: &[u8], network: Network) -> Result<Self, Bip32Error> {
+    if seed.len() < 16 || seed.len() > 64 {
+      return Err(Bip32Error::InvalidSeedLength)
+    }
+    let (il, ir) = hmac_sha512(b"Bitcoin seed", seed);
+    Ok(Self {
+      network,
+      depth: 0,
+      parent_fingerprint: [0u8; 4],
+      child_number: 0,
+      chain_code: ir,
+      private_key: PrivateKey::new(parse_il(&il)?),
+    })
+  }
+
+  /// CKDpriv
+  pub fn derive_child(&self, child_number: u32) -> Result<Self, Bip32Error> {
+    let depth = self.depth.checked_add(1).ok_or(Bip32Error::MaxDepthExceeded)?;
+    let mut data = Zeroizing::new(if child_number >= HARDENED {
+      let mut data = vec![0u8];
+      data.extend_from_slice(self.private_key.to_bytes().as_ref());
+      data
+    } else {
+      self.private_key.point.sec(true)
+    });
+    data.extend_from_slice(&child_number.to_be_bytes());
+
+    let (il, ir) = hmac_sha512(&self.chain_code, &data);
+    let sum = SecretScalar::new(parse_il(&il)? + &*self.private_key.secret());
+    let secret = Euclid::rem_euclid(&*sum, &N);
+    if secret.is_zero() {
+      return Err(Bip32Error::InvalidChildKey)
+    }
+    Ok(Self {
+      network: self.network,
+      depth,
+      parent_fingerprint: self.fingerprint(),
+      child_number,
+      chain_code: ir,
+      private_key: PrivateKey::new(secret),
+    })
+  }
+
+  pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Bip32Error> {
+    path.0.iter().try_fold(self.clone(), |key, index| key.derive_child(*index))
+  }
+
+  /// N((k, c)): the same node without the private key
+  pub fn to_public(&self) -> ExtendedPublicKey {
+    ExtendedPublicKey {
+      network: self.network,
+      depth: self.depth,
+      parent_fingerprint: self.parent_fingerprint,
+      child_number: self.child_number,
+      chain_code: self.chain_code,
+      public_key: self.private_key.point.clone(),
+    }
+  }
+
+  /// first 4 bytes of HASH160 of the compressed public key
+  pub fn fingerprint(&self) -> [u8; 4] {
+    fingerprint(&self.private_key.point)
+  }
+
+  /// xprv / tprv
+  pub fn to_base58(&self) -> String {
+    let mut key_data = Zeroizing::new(vec![0u8]);
+    key_data.extend_from_slice(self.private_key.to_bytes().as_ref());
+    serialize(
+      self.network.private_version(), self.depth, self.parent_fingerprint,
+      self.child_number, &self.chain_code, &key_data
+    )
+  }
+
+  pub fn from_base58(s: &str) -> Result<Self, Bip32Error> {
+    let (version, depth, parent_fingerprint, child_number, chain_code, key_data) = deserialize(s)?;
+    let network = if version == Network::Mainnet.private_version() {
+      Network::Mainnet
+    } else if version == Network::Testnet.private_version() {
+      Network::Testnet
+    } else {
+      return Err(Bip32Error::UnknownVersion)
+    };
+    let secret = bytes_to_int(&key_data[1..]);
+    if key_data[0] != 0 || secret.is_zero() || secret >= *N {
+      return Err(Bip32Error::InvalidKeyData)
+    }
+    Ok(Self {
+      network, depth, parent_fingerprint, child_number, chain_code,
+      private_key: PrivateKey::new(secret),
+    })
+  }
+}
+
+impl ExtendedPublicKey {
+  /// CKDpub, non-hardened children only
+  pub fn derive_child(&self, child_number: u32) -> Result<Self, Bip32Error> {
+    if child_number >= HARDENED {
+      return Err(Bip32Error::HardenedFromPublic)
+    }
+    let depth = self.depth.checked_add(1).ok_or(Bip32Error::MaxDepthExceeded)?;
+    let mut data = self.public_key.sec(true);
+    data.extend_from_slice(&child_number.to_be_bytes());
+
+    let (il, ir) = hmac_sha512(&self.chain_code, &data);
+    let public_key = Gs.rmul(parse_il(&il)?).add(&self.public_key);
+    if public_key.x.is_none() {
+      return Err(Bip32Error::InvalidChildKey)
+    }
+    Ok(Self {
+      network: self.network,
+      depth,
+      parent_fingerprint: self.fingerprint(),
+      child_number,
+      chain_code: ir,
+      public_key,
+    })
+  }
+
+  pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Bip32Error> {
+    path.0.iter().try_fold(self.clone(), |key, index| key.derive_child(*index))
+  }
+
+  pub fn fingerprint(&self) -> [u8; 4] {
+    fingerprint(&self.public_key)
+  }
+
+  /// xpub / tpub
+  pub fn to_base58(&self) -> String {
+    serialize(
+      self.network.public_version(), self.depth, self.parent_fingerprint,
+      self.child_number, &self.chain_code, &self.public_key.sec(true)
+    )
+  }
+
+  pub fn from_base58(s: &str) -> Result<Self, Bip32Error> {
+    let (version, depth, parent_fingerprint, child_number, chain_code, key_data) = deserialize(s)?;
+    let network = if version == Network::Mainnet.public_version() {
+      Network::Mainnet
+    } else if version == Network::Testnet.public_version() {
+      Network::Testnet
+    } else {
+      return Err(Bip32Error::UnknownVersion)
+    };
+    let public_key = S256Point::parse(&key_data).ok_or(Bip32Error::InvalidKeyData)?;
+    Ok(Self { network, depth, parent_fingerprint, child_number, chain_code, public_key })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::helper::decode_hex;
+
+  /// derive `path` from the master key of `seed` and check both serializations; the
+  /// trailing non-hardened steps are also derived from the public parent
+  fn check(seed: &str, path: &str, xprv: &str, xpub: &str) {
+    let master = ExtendedPrivateKey::new_master(&decode_hex(seed).unwrap(), Network::Mainnet).unwrap();
+    let path: DerivationPath = path.parse().unwrap();
+    let key = master.derive_path(&path).unwrap();
+    assert_eq!(key.to_base58(), xprv);
+    assert_eq!(key.to_public().to_base58(), xpub);
+    assert_eq!(ExtendedPrivateKey::from_base58(xprv).unwrap().to_base58(), xprv);
+    assert_eq!(ExtendedPublicKey::from_base58(xpub).unwrap().to_base58(), xpub);
+
+    let split = path.0.iter().rposition(|i| *i >= HARDENED).map_or(0, |i| i + 1);
+    let parent = master.derive_path(&DerivationPath(path.0[..split].to_vec())).unwrap().to_public();
+    assert_eq!(parent.derive_path(&DerivationPath(path.0[split..].to_vec())).unwrap().to_base58(), xpub);
+  }
+
+  const SEED_1: &str = "000102030405060708090a0b0c0d0e0f";
+  const SEED_2: &str = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a2\
+    9f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542";
+
+  #[test]
+  fn bip32_test_vector_1() {
+    check(SEED_1, "m",
+      "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+      "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+    check(SEED_1, "m/0h",
+      "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+      "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw");
+    check(SEED_1, "m/0h/1",
+      "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+      "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ");
+    check(SEED_1, "m/0h/1/2h",
+      "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM",
+      "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5");
+    check(SEED_1, "m/0h/1/2h/2",
+      "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334",
+      "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV");
+    check(SEED_1, "m/0h/1/2h/2/1000000000",
+      "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+      "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy");
+  }
+
+  #[test]
+  fn bip32_test_vector_2() {
+    check(SEED_2, "m",
+      "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+      "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB");
+    check(SEED_2, "m/0",
+      "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt",
+      "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH");
+    check(SEED_2, "m/0/2147483647h",
+      "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9",
+      "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a");
+    check(SEED_2, "m/0/2147483647h/1",
+      "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef",
+      "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon");
+    check(SEED_2, "m/0/2147483647h/1/2147483646h",
+      "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc",
+      "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL");
+    check(SEED_2, "m/0/2147483647h/1/2147483646h/2",
+      "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j",
+      "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt");
+  }
+
+  /// vectors 3 and 4 keep the leading zeros of private keys shorter than 32 bytes
+  #[test]
+  fn bip32_test_vectors_3_and_4() {
+    let seed_3 = "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be";
+    check(seed_3, "m",
+      "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6",
+      "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13");
+    check(seed_3, "m/0h",
+      "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L",
+      "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y");
+
+    let seed_4 = decode_hex("3ddd5602285899a946114506157c7997e5444528f3003f6134712147db19b678").unwrap();
+    let master = ExtendedPrivateKey::new_master(&seed_4, Network::Mainnet).unwrap();
+    assert_eq!(master.to_base58(),
+      "xprv9s21ZrQH143K48vGoLGRPxgo2JNkJ3J3fqkirQC2zVdk5Dgd5w14S7fRDyHH4dWNHUgkvsvNDCkvAwcSHNAQwhwgNMgZhLtQC63zxwhQmRv");
+    assert_eq!(master.derive_path(&"m/0h".parse().unwrap()).unwrap().to_base58(),
+      "xprv9vB7xEWwNp9kh1wQRfCCQMnZUEG21LpbR9NPCNN1dwhiZkjjeGRnaALmPXCX7SgjFTiCTT6bXes17boXtjq3xLpcDjzEuGLQBM5ohqkao9G");
+    assert_eq!(master.derive_path(&"m/0h/1h".parse().unwrap()).unwrap().to_base58(),
+      "xprv9xJocDuwtYCMNAo3Zw76WENQeAS6WGXQ55RCy7tDJ8oALr4FWkuVoHJeHVAcAqiZLE7Je3vZJHxspZdFHfnBEjHqU5hG1Jaj32dVoS6XLT1");
+  }
+
+  #[test]
+  fn parse_paths() {
+    assert_eq!("m/84'/0'/0'/0/5".parse::<DerivationPath>().unwrap().0, vec![84 + HARDENED, HARDENED, HARDENED, 0, 5]);
+    assert!("84/0".parse::<DerivationPath>().is_err());
+    assert!("m/x".parse::<DerivationPath>().is_err());
+    assert!("m/2147483648".parse::<DerivationPath>().is_err());
+  }
+
+  #[test]
+  fn rejects_bad_serializations() {
+    let master = ExtendedPrivateKey::new_master(&decode_hex(SEED_1).unwrap(), Network::Testnet).unwrap();
+    assert!(master.to_base58().starts_with("tprv"));
+    assert_eq!(ExtendedPublicKey::from_base58(&master.to_public().to_base58()).unwrap(), master.to_public());
+    assert_eq!(ExtendedPublicKey::from_base58(&master.to_base58()).unwrap_err(), Bip32Error::UnknownVersion);
+    assert_eq!(master.to_public().derive_child(HARDENED).unwrap_err(), Bip32Error::HardenedFromPublic);
+
+    let mut corrupted = master.to_base58();
+    corrupted.pop();
+    corrupted.push('x');
+    assert_eq!(ExtendedPrivateKey::from_base58(&corrupted).unwrap_err(), Bip32Error::InvalidEncoding);
+  }
+}
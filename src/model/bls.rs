@@ -0,0 +1,307 @@
+//! BLS signatures over BLS12-381, minimal-pubkey-size variant with proof of possession
+//! https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-bls-signature-05
+//!
+//! Public keys are 48-byte G1 points and signatures 96-byte G2 points, with the
+//! ciphersuite BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_ that Ethereum consensus uses.
+//! With proofs of possession checked when keys are registered, aggregate verification
+//! needs no distinct messages and `fast_aggregate_verify` can sum the public keys.
+
+use std::fmt;
+
+use hkdf::Hkdf;
+use num_traits::{Euclid, Zero};
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::{
+  bls12_381::{hash_to_g2, multi_pairing, G1Point, G2Point},
+  curve::{Bls12381, Curve},
+  helper::{bytes_to_int, int_to_bytes32, sha256},
+};
+
+/// DST of signatures in the proof-of-possession ciphersuite
+pub const DST_SIGNATURE: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// DST of proofs of possession
+pub const DST_POP: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+const KEYGEN_SALT: &[u8] = b"BLS-SIG-KEYGEN-SALT-";
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum BlsError {
+  /// a secret not in [1, r-1], key material shorter than 32 bytes,
+  /// or a public key that is the identity or outside G1
+  InvalidKey,
+  /// bad flag bits, a coordinate >= p, or no curve point with that x
+  InvalidEncoding,
+  /// a signature point outside G2
+  InvalidSignature,
+  /// nothing to aggregate
+  EmptyAggregate,
+}
+
+/// a validated public key: a G1 point of order r, never the identity (KeyValidate)
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BlsPublicKey {
+  point: G1Point,
+}
+
+impl BlsPublicKey {
+  pub fn from_bytes(bytes: &[u8; 48]) -> Result<Self, BlsError> {
+    let point = G1Point::from_compressed(bytes).ok_or(BlsError::InvalidEncoding)?;
+    if point.is_infinity() || !point.is_in_subgroup() {
+      return Err(BlsError::InvalidKey)
+    }
+    Ok(Self { point })
+  }
+
+  pub fn to_bytes(&self) -> [u8; 48] {
+    self.point.to_compressed()
+  }
+
+  pub fn point(&self) -> &G1Point {
+    &self.point
+  }
+
+  /// the sum of the keys; it is the identity when keys cancel, and then verifies nothing
+  pub fn aggregate(keys: &[BlsPublicKey]) -> Result<Self, BlsError> {
+    if keys.is_empty() {
+      return Err(BlsError::EmptyAggregate)
+    }
+    let point = keys.iter().fold(G1Point::infinity(), |acc, key| acc.add(&key.point));
+    Ok(Self { point })
+  }
+
+  pub fn verify(&self, msg: &[u8], sig: &BlsSignature) -> bool {
+    core_aggregate_verify(&[(self.point.clone(), msg)], sig, DST_SIGNATURE)
+  }
+
+  /// PopVerify: `proof` signs this key's own encoding under `DST_POP`
+  pub fn pop_verify(&self, proof: &BlsSignature) -> bool {
+    core_aggregate_verify(&[(self.point.clone(), &self.to_bytes())], proof, DST_POP)
+  }
+}
+
+/// a signature: a G2 point of order r, or the identity
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BlsSignature {
+  point: G2Point,
+}
+
+impl BlsSignature {
+  /// decoding includes the subgroup check of signature_subgroup_check
+  pub fn from_bytes(bytes: &[u8; 96]) -> Result<Self, BlsError> {
+    let point = G2Point::from_compressed(bytes).ok_or(BlsError::InvalidEncoding)?;
+    if !point.is_in_subgroup() {
+      return Err(BlsError::InvalidSignature)
+    }
+    Ok(Self { point })
+  }
+
+  pub fn to_bytes(&self) -> [u8; 96] {
+    self.point.to_compressed()
+  }
+
+  pub fn point(&self) -> &G2Point {
+    &self.point
+  }
+
+  pub fn aggregate(sigs: &[BlsSignature]) -> Result<Self, BlsError> {
+    if sigs.is_empty() {
+      return Err(BlsError::EmptyAggregate)
+    }
+    let point = sigs.iter().fold(G2Point::infinity(), |acc, sig| acc.add(&sig.point));
+    Ok(Self { point })
+  }
+}
+
+/// e(PK_1, H(m_1)) ··· e(PK_n, H(m_n)) == e(G1, sig), checked as one multi-pairing equal to 1
+fn core_aggregate_verify(terms: &[(G1Point, &[u8])], sig: &BlsSignature, dst: &[u8]) -> bool {
+  if terms.is_empty() || terms.iter().any(|(pk, _)| pk.is_infinity()) {
+    return false
+  }
+  let mut pairs: Vec<(G1Point, G2Point)> = terms.iter()
+    .map(|(pk, msg)| (pk.clone(), hash_to_g2(msg, dst)))
+    .collect();
+  pairs.push((Bls12381::generator().negate(), sig.point.clone()));
+  multi_pairing(&pairs).is_one()
+}
+
+/// AggregateVerify of the proof-of-possession scheme: each key signed its own message,
+/// messages may repeat
+pub fn aggregate_verify(keys: &[BlsPublicKey], msgs: &[&[u8]], sig: &BlsSignature) -> bool {
+  if keys.len() != msgs.len() {
+    return false
+  }
+  let terms: Vec<(G1Point, &[u8])> = keys.iter().map(|key| key.point.clone()).zip(msgs.iter().copied()).collect();
+  core_aggregate_verify(&terms, sig, DST_SIGNATURE)
+}
+
+/// FastAggregateVerify: every key signed the same message; the keys must have proven possession
+pub fn fast_aggregate_verify(keys: &[BlsPublicKey], msg: &[u8], sig: &BlsSignature) -> bool {
+  match BlsPublicKey::aggregate(keys) {
+    Ok(key) => key.verify(msg, sig),
+    Err(_) => false,
+  }
+}
+
+/// BLS secret key SK in [1, r-1], big-endian; the bytes are wiped on drop
+#[derive(Clone)]
+pub struct BlsSecretKey {
+  secret: Zeroizing<[u8; 32]>,
+  pub public_key: BlsPublicKey,
+}
+
+impl fmt::Debug for BlsSecretKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("BlsSecretKey")
+      .field("secret", &"[REDACTED]")
+      .field("public_key", &self.public_key)
+      .finish()
+  }
+}
+
+impl BlsSecretKey {
+  /// KeyGen: HKDF-SHA256 over `ikm` of at least 32 bytes, rehashing the salt until SK != 0
+  pub fn key_gen(ikm: &[u8], key_info: &[u8]) -> Result<Self, BlsError> {
+    if ikm.len() < 32 {
+      return Err(BlsError::InvalidKey)
+    }
+    let mut ikm_zero = Zeroizing::new(ikm.to_vec());
+    ikm_zero.push(0);
+    let mut info = key_info.to_vec();
+    info.extend_from_slice(&48u16.to_be_bytes());
+
+    let mut salt = KEYGEN_SALT.to_vec();
+    loop {
+      salt = sha256(&salt).to_vec();
+      let mut okm = Zeroizing::new([0u8; 48]);
+      Hkdf::<Sha256>::new(Some(&salt), &ikm_zero)
+        .expand(&info, okm.as_mut())
+        .expect("48 bytes is a valid HKDF-SHA256 output length");
+      let secret = Euclid::rem_euclid(&bytes_to_int(okm.as_ref()), Bls12381::n());
+      if !secret.is_zero() {
+        return Self::from_bytes(&Zeroizing::new(int_to_bytes32(&secret)))
+      }
+    }
+  }
+
+  /// KeyGen over 32 random bytes
+  pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+    let mut ikm = Zeroizing::new([0u8; 32]);
+    rng.fill_bytes(ikm.as_mut());
+    Self::key_gen(ikm.as_ref(), &[]).expect("32 bytes of key material")
+  }
+
+  /// rejected unless 1 <= SK < r
+  pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, BlsError> {
+    let secret = bytes_to_int(bytes);
+    if secret.is_zero() || secret >= *Bls12381::n() {
+      return Err(BlsError::InvalidKey)
+    }
+    let point = Bls12381::generator().rmul(secret);
+    Ok(Self { secret: Zeroizing::new(*bytes), public_key: BlsPublicKey { point } })
+  }
+
+  pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+    self.secret.clone()
+  }
+
+  fn core_sign(&self, msg: &[u8], dst: &[u8]) -> BlsSignature {
+    BlsSignature { point: hash_to_g2(msg, dst).rmul(bytes_to_int(self.secret.as_ref())) }
+  }
+
+  pub fn sign(&self, msg: &[u8]) -> BlsSignature {
+    self.core_sign(msg, DST_SIGNATURE)
+  }
+
+  /// PopProve: a signature on the public key's encoding under `DST_POP`
+  pub fn pop_prove(&self) -> BlsSignature {
+    self.core_sign(&self.public_key.to_bytes(), DST_POP)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use num_bigint::BigInt;
+
+  use super::*;
+  use crate::model::helper::{decode_hex, encode_hex};
+
+  fn secret_key(hex: &str) -> BlsSecretKey {
+    BlsSecretKey::from_bytes(&decode_hex(hex).unwrap().try_into().unwrap()).unwrap()
+  }
+
+  /// consensus-spec-tests bls/sign/sign_case_84d45c9c7cca6b92
+  #[test]
+  fn ethereum_sign_vector() {
+    let sk = secret_key("263dbd792f5b1be47ed85f8938c0f29586af0d3ac7b977f21c278fe1462040e3");
+    assert_eq!(
+      encode_hex(&sk.public_key.to_bytes()),
+      "a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a"
+    );
+    let msg = [0x56u8; 32];
+    let sig = sk.sign(&msg);
+    assert_eq!(
+      encode_hex(&sig.to_bytes()),
+      "882730e5d03f6b42c3abc26d3372625034e1d871b65a8a6b900a56dae22da98abbe1b68f85e49fe7652a55ec3d0591c2\
+       0767677e33e5cbb1207315c41a9ac03be39c2e7668edc043d6cb1d9fd93033caa8a1c5b0e84bedaeb6c64972503a43eb"
+    );
+    assert!(sk.public_key.verify(&msg, &sig));
+    assert!(!sk.public_key.verify(&[0u8; 32], &sig));
+    assert_eq!(BlsSignature::from_bytes(&sig.to_bytes()), Ok(sig));
+  }
+
+  /// EIP-2333 test case 0, master SK
+  #[test]
+  fn eip2333_key_gen() {
+    let seed = decode_hex(
+      "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+    ).unwrap();
+    let sk = BlsSecretKey::key_gen(&seed, &[]).unwrap();
+    assert_eq!(
+      bytes_to_int(sk.to_bytes().as_ref()),
+      "6083874454709270928345386274498605044986640685124978867557563392430687146096".parse::<BigInt>().unwrap()
+    );
+    assert_eq!(BlsSecretKey::key_gen(&[1u8; 31], &[]).err(), Some(BlsError::InvalidKey));
+  }
+
+  #[test]
+  fn proofs_of_possession_and_aggregates() {
+    let keys: Vec<BlsSecretKey> = (1..3u8).map(|i| BlsSecretKey::key_gen(&[i; 32], b"").unwrap()).collect();
+    let pks: Vec<BlsPublicKey> = keys.iter().map(|k| k.public_key.clone()).collect();
+    for k in &keys {
+      let proof = k.pop_prove();
+      assert!(k.public_key.pop_verify(&proof));
+      // a proof is not a signature on the key's encoding
+      assert!(!k.public_key.verify(&k.public_key.to_bytes(), &proof));
+    }
+
+    let agg = BlsSignature::aggregate(&keys.iter().map(|k| k.sign(b"same")).collect::<Vec<_>>()).unwrap();
+    assert!(fast_aggregate_verify(&pks, b"same", &agg));
+    assert!(!fast_aggregate_verify(&pks[..1], b"same", &agg));
+    assert!(!fast_aggregate_verify(&[], b"same", &agg));
+
+    let msgs: [&[u8]; 2] = [b"a", b"b"];
+    let agg = BlsSignature::aggregate(&keys.iter().zip(msgs).map(|(k, m)| k.sign(m)).collect::<Vec<_>>()).unwrap();
+    assert!(aggregate_verify(&pks, &msgs, &agg));
+    assert!(!aggregate_verify(&pks, &[b"a", b"c"], &agg));
+    assert_eq!(BlsSignature::aggregate(&[]), Err(BlsError::EmptyAggregate));
+  }
+
+  #[test]
+  fn invalid_encodings_are_rejected() {
+    let mut identity = [0u8; 48];
+    identity[0] = 0xc0;
+    assert_eq!(BlsPublicKey::from_bytes(&identity), Err(BlsError::InvalidKey));
+    assert_eq!(BlsSecretKey::from_bytes(&[0u8; 32]).err(), Some(BlsError::InvalidKey));
+
+    // without the compression flag
+    let mut sig = [0u8; 96];
+    sig[95] = 2;
+    assert_eq!(BlsSignature::from_bytes(&sig), Err(BlsError::InvalidEncoding));
+    // x = 2 is on E' but not in G2
+    sig[0] = 0x80;
+    assert_eq!(BlsSignature::from_bytes(&sig), Err(BlsError::InvalidSignature));
+  }
+}
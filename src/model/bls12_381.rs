@@ -0,0 +1,436 @@
+//! BLS12-381 groups and the optimal Ate pairing
+//! https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-pairing-friendly-curves-11#section-4.2.1
+//!
+//! * G1: the order-r subgroup of E(F_p): y^2 = x^3 + 4, as `AffinePoint<Bls12381>`
+//! * G2: the order-r subgroup of the sextic twist E'(F_p2): y^2 = x^3 + 4(u + 1), as
+//!   `AffinePoint<Bls12381G2>` with F_p2 coordinates
+//! * GT: the order-r subgroup of F_p12^*
+//!
+//! `pairing` maps Q to E(F_p12) with (x, y) -> (x / w^2, y / w^3), runs the Miller
+//! loop over |x| with affine lines and raises the result to (p^12 - 1) / r. It favours
+//! plain formulas over speed and is not constant time.
+
+use num_bigint::BigInt;
+use once_cell::sync::Lazy;
+
+use super::{
+  curve::{Bls12381, Curve},
+  extension_fields::{Fp, Fp12, Fp2, Fp6},
+  field_elements::{Fe, FieldOperation},
+  hash_to_curve::{hash_to_curve, SswuCurve, SswuParams},
+  helper::{bytes_to_int, hash_to_field, int_to_bytes},
+  point::{AffinePoint, WeierstrassCurve},
+};
+
+pub type G1Point = AffinePoint<Bls12381>;
+
+/// the sextic twist E'(F_p2): y^2 = x^3 + 4(u + 1), whose order-r subgroup is G2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bls12381G2;
+
+pub type G2Point = AffinePoint<Bls12381G2>;
+
+/// |x| of the curve parameter x = -0xd201000000010000
+const BLS_X: u64 = 0xd201_0000_0001_0000;
+
+/// (p^4 - p^2 + 1) / r, the hard part of the final exponentiation
+static HARD_EXPONENT: Lazy<BigInt> = Lazy::new(|| {
+  let p = Bls12381::p();
+  let p2 = p * p;
+  (&p2 * &p2 - &p2 + 1u8) / Bls12381::n()
+});
+
+/// b' = 4(u + 1)
+static TWIST_B: Lazy<Fp2> = Lazy::new(|| {
+  let four = fp(&BigInt::from(4u8));
+  Fp2::new(four.clone(), four)
+});
+
+/// #E'(F_p2) = r h', h' being the G2 cofactor
+static TWIST_ORDER: Lazy<BigInt> = Lazy::new(|| {
+  let cofactor = BigInt::parse_bytes(
+    b"5d543a95414e7f1091d50792876a202cd91de4547085abaa68a205b2e5a7ddfa628f1cb4d9e82ef21537e293a6691ae1616ec6e786f0c70cf1c38e31c7238e5",
+    16,
+  ).expect("hex");
+  Bls12381::n() * cofactor
+});
+
+/// w^-1, w^-2 and w^-3, for `untwist`
+static W_INV: Lazy<Fp12> = Lazy::new(|| Fp12::new(Fp6::zero(), Fp6::one()).inverse().expect("w != 0"));
+static W_INV2: Lazy<Fp12> = Lazy::new(|| W_INV.square());
+static W_INV3: Lazy<Fp12> = Lazy::new(|| W_INV2.mul(&W_INV));
+
+fn fp(n: &BigInt) -> Fp {
+  Fe::from_int(n)
+}
+
+/// c0 + c1 u from big-endian hex
+fn fp2_from_hex(c0: &str, c1: &str) -> Fp2 {
+  let parse = |s: &str| BigInt::parse_bytes(s.as_bytes(), 16).expect("hex");
+  Fp2::new(fp(&parse(c0)), fp(&parse(c1)))
+}
+
+impl WeierstrassCurve for Bls12381G2 {
+  type Coordinate = Fp2;
+
+  fn curve_name() -> &'static str {
+    "BLS12-381 G2"
+  }
+
+  fn curve_a() -> Fp2 {
+    Fp2::zero()
+  }
+
+  fn curve_b() -> Fp2 {
+    TWIST_B.clone()
+  }
+
+  fn group_order() -> BigInt {
+    TWIST_ORDER.clone()
+  }
+}
+
+impl AffinePoint<Bls12381G2> {
+  pub fn generator() -> Self {
+    static GENERATOR: Lazy<G2Point> = Lazy::new(|| G2Point::new(
+      Some(fp2_from_hex(
+        "024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8",
+        "13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e",
+      )),
+      Some(fp2_from_hex(
+        "0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801",
+        "0606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be",
+      )),
+    ));
+    GENERATOR.clone()
+  }
+
+  /// on E' and of order r
+  pub fn is_in_subgroup(&self) -> bool {
+    (self.is_infinity() || self.is_on_curve()) && self.rmul(Bls12381::n().clone()).is_infinity()
+  }
+}
+
+impl AffinePoint<Bls12381> {
+  /// on E and of order r
+  pub fn is_in_subgroup(&self) -> bool {
+    (self.is_infinity() || self.is_on_curve()) && self.rmul(Bls12381::n().clone()).is_infinity()
+  }
+}
+
+/// ψ(Q) = (x / w^2, y / w^3) in E(F_p12)
+fn untwist(x: &Fp2, y: &Fp2) -> (Fp12, Fp12) {
+  (Fp12::from_fp2(x).mul(&W_INV2), Fp12::from_fp2(y).mul(&W_INV3))
+}
+
+/// f_{x,Q}(P) before the final exponentiation, 1 if either point is at infinity
+pub fn miller_loop(p: &G1Point, q: &G2Point) -> Fp12 {
+  let one = Fp12::one();
+  let (xp, yp, xq, yq) = match (&p.x, &p.y, &q.x, &q.y) {
+    (Some(xp), Some(yp), Some(xq), Some(yq)) => (xp, yp, xq, yq),
+    _ => return one,
+  };
+  let (xp, yp) = (Fp12::from_fp(xp), Fp12::from_fp(yp));
+  let (xq, yq) = untwist(xq, yq);
+  let three = Fp12::from_fp(&fp(&BigInt::from(3u8)));
+
+  // the line through T with slope λ, at P; vertical lines lie in F_p6 and vanish in the final exponentiation
+  let line = |xt: &Fp12, yt: &Fp12, slope: &Fp12| yp.sub(yt).sub(&slope.mul(&xp.sub(xt)));
+
+  let (mut xt, mut yt) = (xq.clone(), yq.clone());
+  let mut f = one;
+  for i in (0..63).rev() {
+    // T + T: λ = 3 x^2 / 2y
+    let slope = three.mul(&xt.square()).mul(&yt.add(&yt).inverse().expect("T has odd order"));
+    f = f.square().mul(&line(&xt, &yt, &slope));
+    let x3 = slope.square().sub(&xt).sub(&xt);
+    yt = slope.mul(&xt.sub(&x3)).sub(&yt);
+    xt = x3;
+
+    if (BLS_X >> i) & 1 == 1 {
+      // T + Q: λ = (y_Q - y_T) / (x_Q - x_T)
+      let slope = yq.sub(&yt).mul(&xq.sub(&xt).inverse().expect("T != ±Q below r"));
+      f = f.mul(&line(&xt, &yt, &slope));
+      let x3 = slope.square().sub(&xt).sub(&xq);
+      yt = slope.mul(&xt.sub(&x3)).sub(&yt);
+      xt = x3;
+    }
+  }
+  // x < 0: f_{-|x|} = 1 / f_{|x|} up to a vertical line, and the inverse is the conjugate once
+  // the final exponentiation has made f unitary
+  f.conjugate()
+}
+
+/// f^((p^12 - 1) / r) = (f^((p^6 - 1)(p^2 + 1)))^((p^4 - p^2 + 1) / r)
+pub fn final_exponentiation(f: &Fp12) -> Fp12 {
+  let f = f.conjugate().mul(&f.inverse().expect("Miller loop output is nonzero"));
+  let f = f.frobenius().frobenius().mul(&f);
+  f.pow(&HARD_EXPONENT)
+}
+
+/// e(P, Q) in GT; implementations with an addition-chain hard part, like zkcrypto's
+/// `bls12_381`, return e(P, Q)^3 instead
+pub fn pairing(p: &G1Point, q: &G2Point) -> Fp12 {
+  final_exponentiation(&miller_loop(p, q))
+}
+
+/// the product of e(P_i, Q_i), with one final exponentiation for all pairs
+pub fn multi_pairing(pairs: &[(G1Point, G2Point)]) -> Fp12 {
+  let f = pairs.iter()
+    .fold(Fp12::one(), |acc, (p, q)| acc.mul(&miller_loop(p, q)));
+  final_exponentiation(&f)
+}
+
+
+// Point encodings of the ZCash BLS12-381 format, as used by the BLS signature drafts
+// https://github.com/zkcrypto/pairing/tree/master/src/bls12_381#serialization
+// The top three bits of the first byte flag compression, infinity and the larger of ±y.
+
+const FLAG_COMPRESSED: u8 = 0x80;
+const FLAG_INFINITY: u8 = 0x40;
+const FLAG_Y_LARGER: u8 = 0x20;
+
+/// y > -y as integers in [0, p)
+fn is_larger_fp(y: &BigInt) -> bool {
+  y > &((Bls12381::p() - 1u8) >> 1)
+}
+
+/// y > -y comparing c1 first, then c0
+fn is_larger_fp2(y: &Fp2) -> bool {
+  if y.c1.is_zero() { is_larger_fp(&y.c0.get_num()) } else { is_larger_fp(&y.c1.get_num()) }
+}
+
+/// the 48-byte big-endian x, after its flag bits are checked and cleared; `None` for a bad
+/// flag combination or x >= p, `Some(None)` for the point at infinity
+fn split_flags(bytes: &[u8]) -> Option<Option<(Vec<u8>, bool)>> {
+  let flags = bytes[0] & 0xe0;
+  if flags & FLAG_COMPRESSED == 0 {
+    return None
+  }
+  let mut x = bytes.to_vec();
+  x[0] &= 0x1f;
+  if flags & FLAG_INFINITY != 0 {
+    let clear = flags & FLAG_Y_LARGER == 0 && x.iter().all(|b| *b == 0);
+    return if clear { Some(None) } else { None }
+  }
+  Some(Some((x, flags & FLAG_Y_LARGER != 0)))
+}
+
+fn parse_fp(bytes: &[u8]) -> Option<Fp> {
+  let n = bytes_to_int(bytes);
+  if n >= *Bls12381::p() { None } else { Some(fp(&n)) }
+}
+
+impl AffinePoint<Bls12381> {
+  /// 48 bytes: x with the flag bits
+  pub fn to_compressed(&self) -> [u8; 48] {
+    let mut out = [0u8; 48];
+    match (&self.x, &self.y) {
+      (Some(x_), Some(y_)) => {
+        out.copy_from_slice(&int_to_bytes(&x_.get_num(), 48));
+        out[0] |= FLAG_COMPRESSED;
+        if is_larger_fp(&y_.get_num()) {
+          out[0] |= FLAG_Y_LARGER;
+        }
+      }
+      _ => out[0] = FLAG_COMPRESSED | FLAG_INFINITY,
+    }
+    out
+  }
+
+  /// a point on E, or infinity; the subgroup is not checked
+  pub fn from_compressed(bytes: &[u8; 48]) -> Option<Self> {
+    let (x, larger) = match split_flags(bytes)? {
+      Some(x) => x,
+      None => return Some(Self::infinity()),
+    };
+    let x_ = parse_fp(&x)?;
+    let y_ = x_.mul(&x_).mul(&x_).add(&Fe::from_int(Bls12381::b())).sqrt()?;
+    let y_ = if is_larger_fp(&y_.get_num()) == larger { y_ } else { y_.neg() };
+    Some(Self::new(Some(x_), Some(y_)))
+  }
+}
+
+impl AffinePoint<Bls12381G2> {
+  /// 96 bytes: x.c1 with the flag bits, then x.c0
+  pub fn to_compressed(&self) -> [u8; 96] {
+    let mut out = [0u8; 96];
+    match (&self.x, &self.y) {
+      (Some(x_), Some(y_)) => {
+        out[..48].copy_from_slice(&int_to_bytes(&x_.c1.get_num(), 48));
+        out[48..].copy_from_slice(&int_to_bytes(&x_.c0.get_num(), 48));
+        out[0] |= FLAG_COMPRESSED;
+        if is_larger_fp2(y_) {
+          out[0] |= FLAG_Y_LARGER;
+        }
+      }
+      _ => out[0] = FLAG_COMPRESSED | FLAG_INFINITY,
+    }
+    out
+  }
+
+  /// a point on E', or infinity; the subgroup is not checked
+  pub fn from_compressed(bytes: &[u8; 96]) -> Option<Self> {
+    let (x, larger) = match split_flags(bytes)? {
+      Some(x) => x,
+      None => return Some(Self::infinity()),
+    };
+    let x_ = Fp2::new(parse_fp(&x[48..])?, parse_fp(&x[..48])?);
+    let y_ = x_.square().mul(&x_).add(&TWIST_B).sqrt()?;
+    let y_ = if is_larger_fp2(&y_) == larger { y_ } else { y_.neg() };
+    Some(Self::new(Some(x_), Some(y_)))
+  }
+
+  /// [h_eff]P, RFC 9380 section 8.8.2
+  pub fn clear_cofactor(&self) -> Self {
+    self.rmul(Bls12381G2::sswu_params().h_eff.clone())
+  }
+}
+
+/// E2': y^2 = x^3 + 240u x + 1012(1 + u), Z = -(2 + u), and the 3-isogeny E2' -> E'
+/// https://datatracker.ietf.org/doc/html/rfc9380#section-8.8.2
+impl SswuCurve for Bls12381G2 {
+  fn sswu_params() -> &'static SswuParams<Fp2> {
+    static PARAMS: Lazy<SswuParams<Fp2>> = Lazy::new(|| {
+      let p = Bls12381::p();
+      let k = |pairs: &[(&str, &str)]| pairs.iter().map(|(c0, c1)| fp2_from_hex(c0, c1)).collect::<Vec<_>>();
+      SswuParams {
+        a: Fp2::new(Fp::zero(), fp(&BigInt::from(240u8))),
+        b: Fp2::new(fp(&BigInt::from(1012u16)), fp(&BigInt::from(1012u16))),
+        z: Fp2::new(fp(&(p - 2u8)), fp(&(p - 1u8))),
+        // x_num, x_den, y_num, y_den, lowest degree first (appendix E.3)
+        isogeny: vec![
+          k(&[
+            ("5c759507e8e333ebb5b7a9a47d7ed8532c52d39fd3a042a88b58423c50ae15d5c2638e343d9c71c6238aaaaaaaa97d6",
+             "5c759507e8e333ebb5b7a9a47d7ed8532c52d39fd3a042a88b58423c50ae15d5c2638e343d9c71c6238aaaaaaaa97d6"),
+            ("0",
+             "11560bf17baa99bc32126fced787c88f984f87adf7ae0c7f9a208c6b4f20a4181472aaa9cb8d555526a9ffffffffc71a"),
+            ("11560bf17baa99bc32126fced787c88f984f87adf7ae0c7f9a208c6b4f20a4181472aaa9cb8d555526a9ffffffffc71e",
+             "8ab05f8bdd54cde190937e76bc3e447cc27c3d6fbd7063fcd104635a790520c0a395554e5c6aaaa9354ffffffffe38d"),
+            ("171d6541fa38ccfaed6dea691f5fb614cb14b4e7f4e810aa22d6108f142b85757098e38d0f671c7188e2aaaaaaaa5ed1",
+             "0"),
+          ]),
+          k(&[
+            ("0",
+             "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaa63"),
+            ("c",
+             "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaa9f"),
+            ("1", "0"),
+          ]),
+          k(&[
+            ("1530477c7ab4113b59a4c18b076d11930f7da5d4a07f649bf54439d87d27e500fc8c25ebf8c92f6812cfc71c71c6d706",
+             "1530477c7ab4113b59a4c18b076d11930f7da5d4a07f649bf54439d87d27e500fc8c25ebf8c92f6812cfc71c71c6d706"),
+            ("0",
+             "5c759507e8e333ebb5b7a9a47d7ed8532c52d39fd3a042a88b58423c50ae15d5c2638e343d9c71c6238aaaaaaaa97be"),
+            ("11560bf17baa99bc32126fced787c88f984f87adf7ae0c7f9a208c6b4f20a4181472aaa9cb8d555526a9ffffffffc71c",
+             "8ab05f8bdd54cde190937e76bc3e447cc27c3d6fbd7063fcd104635a790520c0a395554e5c6aaaa9354ffffffffe38f"),
+            ("124c9ad43b6cf79bfbf7043de3811ad0761b0f37a1e26286b0e977c69aa274524e79097a56dc4bd9e1b371c71c718b10",
+             "0"),
+          ]),
+          k(&[
+            ("1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffa8fb",
+             "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffa8fb"),
+            ("0",
+             "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffa9d3"),
+            ("12",
+             "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaa99"),
+            ("1", "0"),
+          ]),
+        ],
+        // a multiple of the G2 cofactor
+        h_eff: BigInt::parse_bytes(
+          b"bc69f08f2ee75b3584c6a0ea91b352888e2a8e9145ad7689986ff031508ffe1329c2f178731db956d82bf015d1212b02\
+            ec0ec69d7477c1ae954cbc06689f6a359894c0adebbf6b4e8020005aaa95551",
+          16,
+        ).expect("hex"),
+      }
+    });
+    &PARAMS
+  }
+
+  /// elements c0 + c1 u, L = 64 bytes per coordinate
+  fn hash_to_field(msg: &[u8], dst: &[u8], count: usize) -> Vec<Fp2> {
+    let u = hash_to_field(msg, dst, 2 * count, Bls12381::p(), 64)
+      .expect("the suites hash at most two elements");
+    u.chunks(2).map(|c| Fp2::new(fp(&c[0]), fp(&c[1]))).collect()
+  }
+}
+
+/// hash_to_curve with the suite BLS12381G2_XMD:SHA-256_SSWU_RO_
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Point {
+  hash_to_curve::<Bls12381G2>(msg, dst)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::helper::encode_hex;
+
+  /// an Fp12 element with every coefficient set
+  fn sample_fp12() -> Fp12 {
+    let c = |k: u32| Fp2::new(fp(&BigInt::from(k)), fp(&(Bls12381::p() - k)));
+    Fp12::new(Fp6::new(c(1), c(2), c(3)), Fp6::new(c(4), c(5), c(6)))
+  }
+
+  #[test]
+  fn frobenius_is_the_p_th_power() {
+    let a = sample_fp12();
+    assert_eq!(a.frobenius(), a.pow(Bls12381::p()));
+    // Fp6, through its embedding in Fp12
+    assert_eq!(a.c0.frobenius(), Fp12::new(a.c0.clone(), Fp6::zero()).pow(Bls12381::p()).c0);
+  }
+
+  #[test]
+  fn pairing_is_bilinear() {
+    let (g1, g2) = (Bls12381::generator(), G2Point::generator());
+    let e = pairing(&g1, &g2);
+    assert!(!e.is_one());
+    assert!(e.pow(Bls12381::n()).is_one());
+    let e35 = pairing(&g1.rmul(BigInt::from(3u8)), &g2.rmul(BigInt::from(5u8)));
+    assert_eq!(e35, e.pow(&BigInt::from(15u8)));
+    assert!(multi_pairing(&[(g1.clone(), g2.clone()), (g1.negate(), g2.clone())]).is_one());
+    assert!(pairing(&G1Point::infinity(), &g2).is_one());
+  }
+
+  #[test]
+  fn twist_order_and_cofactor_clearing() {
+    // a point of E' outside G2
+    let point = (1u32..).find_map(|k| {
+      let x = Fp2::new(fp(&BigInt::from(k)), Fp::one());
+      let y = x.square().mul(&x).add(&TWIST_B).sqrt()?;
+      Some(G2Point::new(Some(x), Some(y)))
+    }).unwrap();
+    assert!(!point.is_in_subgroup());
+    // r h' P = infinity, with the reduction in `rmul` not hiding it
+    let cofactor = &*TWIST_ORDER / Bls12381::n();
+    assert!(point.rmul(cofactor).is_in_subgroup());
+    assert!(point.clear_cofactor().is_in_subgroup());
+    assert!(G2Point::generator().is_in_subgroup());
+  }
+
+  #[test]
+  fn compressed_encodings() {
+    let (g1, g2) = (Bls12381::generator(), G2Point::generator());
+    assert_eq!(
+      encode_hex(&g1.to_compressed()),
+      "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+    );
+    assert_eq!(
+      encode_hex(&g2.to_compressed()),
+      "93e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e\
+       024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8"
+    );
+    for k in 1..6u8 {
+      let (p1, p2) = (g1.rmul(BigInt::from(k)), g2.rmul(BigInt::from(k)));
+      assert_eq!(G1Point::from_compressed(&p1.to_compressed()), Some(p1.clone()));
+      assert_eq!(G1Point::from_compressed(&p1.negate().to_compressed()), Some(p1.negate()));
+      assert_eq!(G2Point::from_compressed(&p2.to_compressed()), Some(p2.clone()));
+      assert_eq!(G2Point::from_compressed(&p2.negate().to_compressed()), Some(p2.negate()));
+    }
+    assert_eq!(G2Point::from_compressed(&G2Point::infinity().to_compressed()), Some(G2Point::infinity()));
+    // the uncompressed flag is not supported
+    let mut bytes = g1.to_compressed();
+    bytes[0] &= 0x7f;
+    assert_eq!(G1Point::from_compressed(&bytes), None);
+  }
+}
@@ -0,0 +1,327 @@
+//! Short Weierstrass curves `y^2 = x^3 + ax + b` over F_p, described by their domain parameters
+//! https://www.secg.org/sec2-v2.pdf
+//!
+//! Adding a curve means a marker type and its `CurveParams`; the field and point
+//! arithmetic in `Fe<C>` and `AffinePoint<C>` is shared by every curve. The prime p is
+//! checked once, when the parameters are first built.
+//!
+//! Curves as types replace the original `Point`, which carried the curve's `a` and `b`
+//! in every point; see `point` for the corresponding calls.
+
+use std::fmt::Debug;
+
+use num_bigint::BigInt;
+use num_traits::Num;
+use once_cell::sync::Lazy;
+
+use super::{
+  field_elements::{Field, PrimeField},
+  point::AffinePoint,
+};
+
+#[derive(Debug)]
+pub struct CurveParams {
+  pub name: &'static str,
+  /// base field F_p
+  pub field: PrimeField,
+  pub a: BigInt,
+  pub b: BigInt,
+  /// generator
+  pub gx: BigInt,
+  pub gy: BigInt,
+  /// order of the generator
+  pub n: BigInt,
+  pub h: BigInt,
+}
+
+fn parse_hex(s: &str) -> BigInt {
+  BigInt::from_str_radix(&s.replace('_', ""), 16)
+    .unwrap_or_else(|_| panic!("{} is not a hex number", s))
+}
+
+impl CurveParams {
+  /// parameters as big-endian hex, `_` separators allowed
+  #[allow(clippy::too_many_arguments)]
+  pub fn from_hex(
+    name: &'static str, p: &str, a: &str, b: &str, gx: &str, gy: &str, n: &str, h: u32
+  ) -> Self {
+    Self {
+      name,
+      field: PrimeField::new(name, parse_hex(p)),
+      a: parse_hex(a),
+      b: parse_hex(b),
+      gx: parse_hex(gx),
+      gy: parse_hex(gy),
+      n: parse_hex(n),
+      h: BigInt::from(h),
+    }
+  }
+}
+
+pub trait Curve: Debug + Clone + Copy + PartialEq + Eq + 'static {
+  fn params() -> &'static CurveParams;
+
+  fn name() -> &'static str {
+    Self::params().name
+  }
+
+  fn p() -> &'static BigInt {
+    &Self::params().field.p
+  }
+
+  fn a() -> &'static BigInt {
+    &Self::params().a
+  }
+
+  fn b() -> &'static BigInt {
+    &Self::params().b
+  }
+
+  fn n() -> &'static BigInt {
+    &Self::params().n
+  }
+
+  fn cofactor() -> &'static BigInt {
+    &Self::params().h
+  }
+
+  fn generator() -> AffinePoint<Self> {
+    AffinePoint::from_coordinates(&Self::params().gx, &Self::params().gy)
+  }
+
+  /// length of an encoded field element, e.g. one SEC1 coordinate
+  fn field_bytes() -> usize {
+    Self::params().field.bytes()
+  }
+
+  /// length of an encoded scalar
+  fn scalar_bytes() -> usize {
+    Self::n().bits().div_ceil(8) as usize
+  }
+}
+
+/// the coordinates of a curve's points lie in its base field
+impl<C: Curve> Field for C {
+  fn field() -> &'static PrimeField {
+    &C::params().field
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Secp256k1;
+
+impl Curve for Secp256k1 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "secp256k1",
+      "ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_fffe_ffff_fc2f",
+      "0",
+      "7",
+      "79be_667e_f9dc_bbac_55a0_6295_ce87_0b07_029b_fcdb_2dce_28d9_59f2_815b_16f8_1798",
+      "483a_da77_26a3_c465_5da4_fbfc_0e11_08a8_fd17_b448_a685_5419_9c47_d08f_fb10_d4b8",
+      "ffff_ffff_ffff_ffff_ffff_ffff_ffff_fffe_baae_dce6_af48_a03b_bfd2_5e8c_d036_4141",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// NIST P-256 (secp256r1, prime256v1), a = p - 3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P256;
+
+impl Curve for P256 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "P-256",
+      "ffff_ffff_0000_0001_0000_0000_0000_0000_0000_0000_ffff_ffff_ffff_ffff_ffff_ffff",
+      "ffff_ffff_0000_0001_0000_0000_0000_0000_0000_0000_ffff_ffff_ffff_ffff_ffff_fffc",
+      "5ac6_35d8_aa3a_93e7_b3eb_bd55_7698_86bc_651d_06b0_cc53_b0f6_3bce_3c3e_27d2_604b",
+      "6b17_d1f2_e12c_4247_f8bc_e6e5_63a4_40f2_7703_7d81_2deb_33a0_f4a1_3945_d898_c296",
+      "4fe3_42e2_fe1a_7f9b_8ee7_eb4a_7c0f_9e16_2bce_3357_6b31_5ece_cbb6_4068_37bf_51f5",
+      "ffff_ffff_0000_0000_ffff_ffff_ffff_ffff_bce6_faad_a717_9e84_f3b9_cac2_fc63_2551",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// NIST P-384 (secp384r1), a = p - 3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P384;
+
+impl Curve for P384 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "P-384",
+      "ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_fffffffe_ffffffff_00000000_00000000_ffffffff",
+      "ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_fffffffe_ffffffff_00000000_00000000_fffffffc",
+      "b3312fa7_e23ee7e4_988e056b_e3f82d19_181d9c6e_fe814112_0314088f_5013875a_c656398d_8a2ed19d_2a85c8ed_d3ec2aef",
+      "aa87ca22_be8b0537_8eb1c71e_f320ad74_6e1d3b62_8ba79b98_59f741e0_82542a38_5502f25d_bf55296c_3a545e38_72760ab7",
+      "3617de4a_96262c6f_5d9e98bf_9292dc29_f8f41dbd_289a147c_e9da3113_b5f0b8c0_0a60b1ce_1d7e819d_7a431d7c_90ea0e5f",
+      "ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_c7634d81_f4372ddf_581a0db2_48b0a77a_ecec196a_ccc52973",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// NIST P-521 (secp521r1), p = 2^521 - 1, a = p - 3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P521;
+
+impl Curve for P521 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "P-521",
+      "01ff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff",
+      "01ff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_fffffffc",
+      "0051_953eb961_8e1c9a1f_929a21a0_b68540ee_a2da725b_99b315f3_b8b48991_8ef109e1_56193951_ec7e937b_1652c0bd_3bb1bf07_3573df88_3d2c34f1_ef451fd4_6b503f00",
+      "00c6_858e06b7_0404e9cd_9e3ecb66_2395b442_9c648139_053fb521_f828af60_6b4d3dba_a14b5e77_efe75928_fe1dc127_a2ffa8de_3348b3c1_856a429b_f97e7e31_c2e5bd66",
+      "0118_39296a78_9a3bc004_5c8a5fb4_2c7d1bd9_98f54449_579b4468_17afbd17_273e662c_97ee7299_5ef42640_c550b901_3fad0761_353c7086_a272c240_88be9476_9fd16650",
+      "01ff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_ffffffff_fffffffa_51868783_bf2f966b_7fcc0148_f709a5d0_3bb5c9b8_899c47ae_bb6fb71e_91386409",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// brainpoolP256r1, RFC 5639 section 3.4; like the other Brainpool curves a random prime and a != -3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrainpoolP256r1;
+
+impl Curve for BrainpoolP256r1 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "brainpoolP256r1",
+      "a9fb57db_a1eea9bc_3e660a90_9d838d72_6e3bf623_d5262028_2013481d_1f6e5377",
+      "7d5a0975_fc2c3057_eef67530_417affe7_fb8055c1_26dc5c6c_e94a4b44_f330b5d9",
+      "26dc5c6c_e94a4b44_f330b5d9_bbd77cbf_95841629_5cf7e1ce_6bccdc18_ff8c07b6",
+      "8bd2aeb9_cb7e57cb_2c4b482f_fc81b7af_b9de27e1_e3bd23c2_3a4453bd_9ace3262",
+      "547ef835_c3dac4fd_97f8461a_14611dc9_c2774513_2ded8e54_5c1d54c7_2f046997",
+      "a9fb57db_a1eea9bc_3e660a90_9d838d71_8c397aa3_b561a6f7_901e0e82_974856a7",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// brainpoolP384r1, RFC 5639 section 3.6
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrainpoolP384r1;
+
+impl Curve for BrainpoolP384r1 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "brainpoolP384r1",
+      "8cb91e82_a3386d28_0f5d6f7e_50e641df_152f7109_ed5456b4_12b1da19_7fb71123_acd3a729_901d1a71_87470013_3107ec53",
+      "7bc382c6_3d8c150c_3c72080a_ce05afa0_c2bea28e_4fb22787_139165ef_ba91f90f_8aa5814a_503ad4eb_04a8c7dd_22ce2826",
+      "04a8c7dd_22ce2826_8b39b554_16f0447c_2fb77de1_07dcd2a6_2e880ea5_3eeb62d5_7cb43902_95dbc994_3ab78696_fa504c11",
+      "1d1c64f0_68cf45ff_a2a63a81_b7c13f6b_8847a3e7_7ef14fe3_db7fcafe_0cbd10e8_e826e034_36d646aa_ef87b2e2_47d4af1e",
+      "8abe1d75_20f9c2a4_5cb1eb8e_95cfd552_62b70b29_feec5864_e19c054f_f9912928_0e464621_77918111_42820341_263c5315",
+      "8cb91e82_a3386d28_0f5d6f7e_50e641df_152f7109_ed5456b3_1f166e6c_ac0425a7_cf3ab6af_6b7fc310_3b883202_e9046565",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// brainpoolP512r1, RFC 5639 section 3.7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrainpoolP512r1;
+
+impl Curve for BrainpoolP512r1 {
+  fn params() -> &'static CurveParams {
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams::from_hex(
+      "brainpoolP512r1",
+      "aadd9db8_dbe9c48b_3fd4e6ae_33c9fc07_cb308db3_b3c9d20e_d6639cca_70330871_7d4d9b00_9bc66842_aecda12a_e6a380e6_2881ff2f_2d82c685_28aa6056_583a48f3",
+      "7830a331_8b603b89_e2327145_ac234cc5_94cbdd8d_3df91610_a83441ca_ea9863bc_2ded5d5a_a8253aa1_0a2ef1c9_8b9ac8b5_7f1117a7_2bf2c7b9_e7c1ac4d_77fc94ca",
+      "3df91610_a83441ca_ea9863bc_2ded5d5a_a8253aa1_0a2ef1c9_8b9ac8b5_7f1117a7_2bf2c7b9_e7c1ac4d_77fc94ca_dc083e67_984050b7_5ebae5dd_2809bd63_8016f723",
+      "81aee4bd_d82ed964_5a21322e_9c4c6a93_85ed9f70_b5d916c1_b43b62ee_f4d0098e_ff3b1f78_e2d0d48d_50d1687b_93b97d5f_7c6d5047_406a5e68_8b352209_bcb9f822",
+      "7dde385d_566332ec_c0eabfa9_cf7822fd_f209f700_24a57b1a_a000c55b_881f8111_b2dcde49_4a5f485e_5bca4bd8_8a2763ae_d1ca2b2f_a8f05406_78cd1e0f_3ad80892",
+      "aadd9db8_dbe9c48b_3fd4e6ae_33c9fc07_cb308db3_b3c9d20e_d6639cca_70330870_553e5c41_4ca92619_41866119_7fac1047_1db1d381_085ddadd_b5879682_9ca90069",
+      1,
+    ));
+    &PARAMS
+  }
+}
+
+/// BLS12-381 G1, E(F_p): y^2 = x^3 + 4; the pairing and G2 are in `bls12_381`
+/// https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-pairing-friendly-curves-11#section-4.2.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bls12381;
+
+impl Curve for Bls12381 {
+  fn params() -> &'static CurveParams {
+    // the cofactor does not fit `from_hex`'s u32
+    static PARAMS: Lazy<CurveParams> = Lazy::new(|| CurveParams {
+      h: parse_hex("396c8c00_5555e156_8c00aaab_0000aaab"),
+      ..CurveParams::from_hex(
+        "BLS12-381",
+        "1a0111ea_397fe69a_4b1ba7b6_434bacd7_64774b84_f38512bf_6730d2a0_f6b0f624_1eabfffe_b153ffff_b9feffff_ffffaaab",
+        "0",
+        "4",
+        "17f1d3a7_3197d794_2695638c_4fa9ac0f_c3688c4f_9774b905_a14e3a3f_171bac58_6c55e83f_f97a1aef_fb3af00a_db22c6bb",
+        "08b3f481_e3aaa0f1_a09e30ed_741d8ae4_fcf5e095_d5d00af6_00db18cb_2c04b3ed_d03cc744_a2888ae4_0caa2329_46c5e7e1",
+        "73eda753_299d7d48_3339d808_09a1d805_53bda402_fffe5bfe_ffffffff_00000001",
+        1,
+      )
+    });
+    &PARAMS
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::helper::decode_hex;
+
+  fn check_generator<C: Curve>() {
+    let g = C::generator();
+    assert!(g.is_on_curve(), "{}", C::name());
+    assert!(!g.is_infinity());
+    assert!(AffinePoint::<C>::parse(&g.sec(true)) == Some(g.clone()));
+    // rmul reduces its scalar modulo n·h, so n·G is checked as (n-1)·G + G
+    let minus_g = g.rmul(C::n() - 1);
+    assert_eq!(minus_g, g.negate(), "{}", C::name());
+    assert!(minus_g.add(&g).is_infinity());
+  }
+
+  #[test]
+  fn generators_have_order_n() {
+    check_generator::<Secp256k1>();
+    check_generator::<P256>();
+    check_generator::<P384>();
+    check_generator::<P521>();
+    check_generator::<BrainpoolP256r1>();
+    check_generator::<BrainpoolP384r1>();
+    check_generator::<BrainpoolP512r1>();
+    check_generator::<Bls12381>();
+  }
+
+  /// RFC 7027 appendix A.1, ECDH on brainpoolP256r1
+  #[test]
+  fn rfc7027_brainpool_p256_ecdh() {
+    let int = |hex: &str| BigInt::from_str_radix(hex, 16).unwrap();
+    let point = |hex: &str| AffinePoint::<BrainpoolP256r1>::parse(&decode_hex(hex).unwrap()).unwrap();
+    let g = BrainpoolP256r1::generator();
+    let a = int("81db1ee100150ff2ea338d708271be38300cb54241d79950f77b063039804f1d");
+    let b = int("55e40bc41e37e3e2ad25c3c6654511ffa8474a91a0032087593852d3e7d76bd3");
+    let qa = point(
+      "0444106e913f92bc02a1705d9953a8414db95e1aaa49e81d9e85f929a8e3100be5\
+       8ab4846f11caccb73ce49cbdd120f5a900a69fd32c272223f789ef10eb089bdc"
+    );
+    let qb = point(
+      "048d2d688c6cf93e1160ad04cc4429117dc2c41825e1e9fca0addd34e6f1b39f7b\
+       990c57520812be512641e47034832106bc7d3e8dd0e4c7f1136d7006547cec6a"
+    );
+    let shared = point(
+      "0489afc39d41d3b327814b80940b042590f96556ec91e6ae7939bce31f3a18bf2b\
+       49c27868f4eca2179bfd7d59b1e3bf34c1dbde61ae12931648f43e59632504de"
+    );
+    assert_eq!(g.rmul(a.clone()), qa);
+    assert_eq!(g.rmul(b.clone()), qb);
+    assert_eq!(qb.rmul(a), shared);
+    assert_eq!(qa.rmul(b), shared);
+  }
+}
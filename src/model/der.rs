@@ -0,0 +1,236 @@
+//! ASN.1 DER, just the parts key files need
+//! https://www.itu.int/rec/T-REC-X.690
+
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+pub const SEQUENCE: u8 = 0x30;
+
+/// constructed, context-specific `[n]` tag
+pub const fn context(n: u8) -> u8 {
+  0xa0 | n
+}
+
+/// primitive, context-specific `[n]` tag, e.g. an IMPLICIT BIT STRING
+pub const fn context_primitive(n: u8) -> u8 {
+  0x80 | n
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum DerError {
+  /// the input ends inside a tag, length or value
+  UnexpectedEnd,
+  /// found the second tag where the first was expected
+  UnexpectedTag(u8, u8),
+  /// indefinite, non-minimal or oversized length
+  InvalidLength,
+  /// negative, empty or non-minimal INTEGER, or one too large for its use
+  InvalidInteger,
+  /// a BIT STRING with unused bits, a malformed OID or a multi-byte tag
+  InvalidValue,
+  /// bytes left after the outermost value
+  TrailingData,
+}
+
+fn encode_length(len: usize) -> Vec<u8> {
+  if len < 0x80 {
+    return vec![len as u8]
+  }
+  let bytes = len.to_be_bytes();
+  let zeros = bytes.iter().take_while(|b| **b == 0).count();
+  let mut out = vec![0x80 | (bytes.len() - zeros) as u8];
+  out.extend_from_slice(&bytes[zeros..]);
+  out
+}
+
+/// tag || length || content
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+  let mut out = vec![tag];
+  out.extend(encode_length(content.len()));
+  out.extend_from_slice(content);
+  out
+}
+
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+  encode(SEQUENCE, &items.concat())
+}
+
+/// non-negative INTEGER from big-endian magnitude bytes
+pub fn integer(magnitude: &[u8]) -> Vec<u8> {
+  let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+  let mut content = magnitude[zeros..].to_vec();
+  if content.first().is_none_or(|b| b & 0x80 != 0) {
+    content.insert(0, 0);
+  }
+  encode(INTEGER, &content)
+}
+
+/// BIT STRING of whole bytes
+pub fn bit_string(bytes: &[u8]) -> Vec<u8> {
+  let mut content = vec![0u8];
+  content.extend_from_slice(bytes);
+  encode(BIT_STRING, &content)
+}
+
+pub fn octet_string(bytes: &[u8]) -> Vec<u8> {
+  encode(OCTET_STRING, bytes)
+}
+
+/// OBJECT IDENTIFIER from its arcs, e.g. `&[1, 3, 132, 0, 10]`
+pub fn oid(arcs: &[u64]) -> Vec<u8> {
+  assert!(arcs.len() >= 2 && arcs[0] <= 2, "not an object identifier");
+  let mut content = Vec::new();
+  for arc in std::iter::once(arcs[0] * 40 + arcs[1]).chain(arcs[2..].iter().copied()) {
+    let mut base128 = vec![(arc & 0x7f) as u8];
+    let mut rest = arc >> 7;
+    while rest > 0 {
+      base128.push(0x80 | (rest & 0x7f) as u8);
+      rest >>= 7;
+    }
+    base128.reverse();
+    content.extend(base128);
+  }
+  encode(OBJECT_IDENTIFIER, &content)
+}
+
+/// reads DER values one after another from a byte slice
+#[derive(Debug, Clone)]
+pub struct DerReader<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+  pub fn new(data: &'a [u8]) -> Self {
+    Self { data }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn peek_tag(&self) -> Option<u8> {
+    self.data.first().copied()
+  }
+
+  /// errors unless everything has been read
+  pub fn finish(&self) -> Result<(), DerError> {
+    if self.is_empty() { Ok(()) } else { Err(DerError::TrailingData) }
+  }
+
+  fn take(&mut self, n: usize) -> Result<&'a [u8], DerError> {
+    if self.data.len() < n {
+      return Err(DerError::UnexpectedEnd)
+    }
+    let (head, rest) = self.data.split_at(n);
+    self.data = rest;
+    Ok(head)
+  }
+
+  /// (tag, content) of the next value; multi-byte tags are not supported
+  pub fn read_any(&mut self) -> Result<(u8, &'a [u8]), DerError> {
+    let tag = self.take(1)?[0];
+    if tag & 0x1f == 0x1f {
+      return Err(DerError::InvalidValue)
+    }
+    let first = self.take(1)?[0];
+    let len = if first < 0x80 {
+      first as usize
+    } else {
+      let count = (first & 0x7f) as usize;
+      if count == 0 || count > std::mem::size_of::<usize>() {
+        return Err(DerError::InvalidLength)
+      }
+      let bytes = self.take(count)?;
+      if bytes[0] == 0 {
+        return Err(DerError::InvalidLength)
+      }
+      let len = bytes.iter().fold(0usize, |len, b| (len << 8) | *b as usize);
+      if len < 0x80 {
+        return Err(DerError::InvalidLength)
+      }
+      len
+    };
+    Ok((tag, self.take(len)?))
+  }
+
+  /// content of the next value, which must have `tag`
+  pub fn read(&mut self, tag: u8) -> Result<&'a [u8], DerError> {
+    let (found, content) = self.read_any()?;
+    if found != tag {
+      return Err(DerError::UnexpectedTag(tag, found))
+    }
+    Ok(content)
+  }
+
+  /// the next value if it has `tag`, for OPTIONAL fields
+  pub fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>, DerError> {
+    if self.peek_tag() == Some(tag) { self.read(tag).map(Some) } else { Ok(None) }
+  }
+
+  pub fn read_sequence(&mut self) -> Result<DerReader<'a>, DerError> {
+    self.read(SEQUENCE).map(DerReader::new)
+  }
+
+  /// big-endian magnitude of a non-negative INTEGER, without the sign byte
+  pub fn read_integer(&mut self) -> Result<&'a [u8], DerError> {
+    let content = self.read(INTEGER)?;
+    match content {
+      [] => Err(DerError::InvalidInteger),
+      [first, ..] if first & 0x80 != 0 => Err(DerError::InvalidInteger),
+      [0, second, ..] if second & 0x80 == 0 => Err(DerError::InvalidInteger),
+      [0, rest @ ..] if !rest.is_empty() => Ok(rest),
+      _ => Ok(content),
+    }
+  }
+
+  /// a small non-negative INTEGER such as a version number
+  pub fn read_u64(&mut self) -> Result<u64, DerError> {
+    let magnitude = self.read_integer()?;
+    if magnitude.len() > 8 {
+      return Err(DerError::InvalidInteger)
+    }
+    Ok(magnitude.iter().fold(0u64, |n, b| (n << 8) | *b as u64))
+  }
+
+  /// BIT STRING content with no unused bits
+  pub fn read_bit_string(&mut self) -> Result<&'a [u8], DerError> {
+    match self.read(BIT_STRING)? {
+      [0, bytes @ ..] => Ok(bytes),
+      _ => Err(DerError::InvalidValue),
+    }
+  }
+
+  pub fn read_octet_string(&mut self) -> Result<&'a [u8], DerError> {
+    self.read(OCTET_STRING)
+  }
+
+  pub fn read_oid(&mut self) -> Result<Vec<u64>, DerError> {
+    let content = self.read(OBJECT_IDENTIFIER)?;
+    if content.is_empty() || content[content.len() - 1] & 0x80 != 0 {
+      return Err(DerError::InvalidValue)
+    }
+    let mut arcs = Vec::new();
+    let mut arc = 0u64;
+    let mut start = true;
+    for b in content {
+      // 0x80 would be a non-minimal leading byte
+      if (start && *b == 0x80) || arc > u64::MAX >> 7 {
+        return Err(DerError::InvalidValue)
+      }
+      arc = (arc << 7) | (b & 0x7f) as u64;
+      start = b & 0x80 == 0;
+      if start {
+        if arcs.is_empty() {
+          let first = (arc / 40).min(2);
+          arcs.push(first);
+          arcs.push(arc - first * 40);
+        } else {
+          arcs.push(arc);
+        }
+        arc = 0;
+      }
+    }
+    Ok(arcs)
+  }
+}
@@ -0,0 +1,127 @@
+//! Elliptic curve Diffie-Hellman over secp256k1
+//!
+//! `hashed` matches libsecp256k1's default `secp256k1_ecdh` output,
+//! SHA256 of the compressed shared point; `raw_x` is the SEC1 shared secret
+//! (the x coordinate).
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use super::{
+  constants::N,
+  field_elements::FieldOperation,
+  helper::{int_to_bytes32, sha256},
+  s256_point::S256Point,
+};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EcdhError {
+  /// the peer key or the shared point is the point at infinity
+  PointAtInfinity,
+  /// the peer key is not a point of secp256k1
+  NotOnCurve,
+  /// the secret is not in [1, n-1]
+  InvalidSecret,
+}
+
+/// secret * peer, after checking both inputs
+pub fn shared_point(secret: &BigInt, peer: &S256Point) -> Result<S256Point, EcdhError> {
+  if secret <= &BigInt::zero() || secret >= &N {
+    return Err(EcdhError::InvalidSecret)
+  }
+  if peer.x.is_none() && peer.y.is_none() {
+    return Err(EcdhError::PointAtInfinity)
+  }
+  if !peer.is_on_curve() {
+    return Err(EcdhError::NotOnCurve)
+  }
+  let shared = peer.rmul(secret.clone());
+  if shared.x.is_none() {
+    return Err(EcdhError::PointAtInfinity)
+  }
+  Ok(shared)
+}
+
+/// SHA256(02/03 || x)
+pub fn hashed(secret: &BigInt, peer: &S256Point) -> Result<[u8; 32], EcdhError> {
+  Ok(sha256(&shared_point(secret, peer)?.sec(true)))
+}
+
+/// x coordinate of the shared point
+pub fn raw_x(secret: &BigInt, peer: &S256Point) -> Result<[u8; 32], EcdhError> {
+  match shared_point(secret, peer)?.x {
+    Some(x_) => Ok(int_to_bytes32(&x_.get_num())),
+    None => Err(EcdhError::PointAtInfinity)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    constants::{Gs, PRIME},
+    helper::{decode_hex, encode_hex},
+    private_key::PrivateKey,
+    s256_field::S256Field,
+  };
+
+  fn secret(s: &str) -> BigInt {
+    BigInt::parse_bytes(s.as_bytes(), 16).unwrap()
+  }
+
+  fn uncompressed(x: &str, y: &str) -> S256Point {
+    S256Point::parse(&decode_hex(&format!("04{}{}", x, y)).unwrap()).unwrap()
+  }
+
+  // expected values from the Python cryptography package (OpenSSL), the hashed
+  // form as libsecp256k1's default hash function: SHA256(0x02 | (y & 1) || x)
+  #[test]
+  fn fixed_key_vectors() {
+    let a = secret("1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c5b6a7988");
+    let peer = uncompressed(
+      "4646ae5047316b4230d0086c8acec687f00b1cd9d1dc634f6cb358ac0a9a8fff",
+      "fe77b4dd0a4bfb95851f3b7355c781dd60f8418fc8a65d14907aff47c903a559",
+    );
+    assert_eq!(peer, Gs.rmul(secret("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")));
+    assert_eq!(
+      encode_hex(&raw_x(&a, &peer).unwrap()),
+      "f8e452088bab60e8472084d4299a56bd8f01c010b27f5e6c8e8ce00dc24709e1"
+    );
+    assert_eq!(
+      encode_hex(&hashed(&a, &peer).unwrap()),
+      "3b249e83e2eac57ec58aed0f259eb455d50cb77ff71f6e21623809c5b73a94de"
+    );
+  }
+
+  #[test]
+  fn both_parties_agree() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..4 {
+      let (alice, bob) = (PrivateKey::generate(&mut rng), PrivateKey::generate(&mut rng));
+      assert_eq!(alice.ecdh(&bob.point).unwrap(), bob.ecdh(&alice.point).unwrap());
+      assert_eq!(alice.ecdh_raw(&bob.point).unwrap(), bob.ecdh_raw(&alice.point).unwrap());
+      assert_ne!(alice.ecdh(&bob.point).unwrap(), alice.ecdh_raw(&bob.point).unwrap());
+    }
+  }
+
+  #[test]
+  fn invalid_inputs_are_rejected() {
+    let one = BigInt::from(1);
+    assert_eq!(shared_point(&one, &S256Point::infinity()).unwrap_err(), EcdhError::PointAtInfinity);
+    assert_eq!(hashed(&one, &S256Point::infinity()).unwrap_err(), EcdhError::PointAtInfinity);
+
+    // G with y + 1 is not on the curve
+    let g = Gs.clone();
+    let off_curve = S256Point {
+      x: g.x.clone(),
+      y: Some(S256Field::new(g.y.unwrap().get_num() + 1, PRIME.clone())),
+    };
+    assert_eq!(raw_x(&one, &off_curve).unwrap_err(), EcdhError::NotOnCurve);
+    assert_eq!(hashed(&one, &off_curve).unwrap_err(), EcdhError::NotOnCurve);
+
+    for s in [BigInt::zero(), N.clone(), -one.clone()] {
+      assert_eq!(shared_point(&s, &Gs).unwrap_err(), EcdhError::InvalidSecret);
+    }
+    assert_eq!(raw_x(&one, &Gs).unwrap(), int_to_bytes32(&Gs.x.as_ref().unwrap().get_num()));
+  }
+}
@@ -0,0 +1,539 @@
+//! ECDSA over any `Curve`, with RFC 6979 deterministic nonces
+//!
+//! * FIPS 186-5 section 6.4: https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf
+//! * RFC 6979: https://datatracker.ietf.org/doc/html/rfc6979
+//!
+//! Message hashes longer than the group order are truncated to its bit length,
+//! so any digest can be used with any curve. Signatures are not normalized to
+//! low s. The secp256k1 `PrivateKey::sign` keeps its own Bitcoin-style signing.
+
+use std::fmt;
+
+use hmac::{digest::{core_api::BlockSizeUser, Digest}, Mac, SimpleHmac};
+use num_bigint::BigInt;
+use num_traits::{Euclid, Zero};
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::{
+  curve::Curve,
+  der::{self, DerReader},
+  field_elements::FieldOperation,
+  helper::{bytes_to_int, int_to_bytes},
+  point::AffinePoint,
+};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EcdsaError {
+  /// the secret is not in [1, n-1]
+  InvalidKey,
+  /// `r || s` of the wrong length, or DER that is not `SEQUENCE { INTEGER r, INTEGER s }`
+  InvalidEncoding,
+  /// r or s not in [1, n-1]
+  InvalidSignature,
+}
+
+/// ECDSA signature, r and s as scalars mod n
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct EcdsaSignature {
+  pub r: BigInt,
+  pub s: BigInt,
+}
+
+impl EcdsaSignature {
+  /// rejected unless r and s are in [1, n-1]
+  pub fn new<C: Curve>(r: BigInt, s: BigInt) -> Result<Self, EcdsaError> {
+    if !is_scalar::<C>(&r) || !is_scalar::<C>(&s) {
+      return Err(EcdsaError::InvalidSignature)
+    }
+    Ok(Self { r, s })
+  }
+
+  /// fixed-width `r || s`, as JWS and WebAuthn's COSE use it
+  pub fn to_bytes<C: Curve>(&self) -> Vec<u8> {
+    let mut out = int_to_bytes(&self.r, C::scalar_bytes());
+    out.extend(int_to_bytes(&self.s, C::scalar_bytes()));
+    out
+  }
+
+  pub fn from_bytes<C: Curve>(bytes: &[u8]) -> Result<Self, EcdsaError> {
+    if bytes.len() != 2 * C::scalar_bytes() {
+      return Err(EcdsaError::InvalidEncoding)
+    }
+    let (r, s) = bytes.split_at(C::scalar_bytes());
+    Self::new::<C>(bytes_to_int(r), bytes_to_int(s))
+  }
+
+  /// `SEQUENCE { INTEGER r, INTEGER s }`, as X.509 and TLS use it
+  pub fn to_der(&self) -> Vec<u8> {
+    der::sequence(&[der::integer(&self.r.to_bytes_be().1), der::integer(&self.s.to_bytes_be().1)])
+  }
+
+  pub fn from_der<C: Curve>(data: &[u8]) -> Result<Self, EcdsaError> {
+    let parse = || -> Result<(BigInt, BigInt), der::DerError> {
+      let mut outer = DerReader::new(data);
+      let mut reader = outer.read_sequence()?;
+      outer.finish()?;
+      let r = bytes_to_int(reader.read_integer()?);
+      let s = bytes_to_int(reader.read_integer()?);
+      reader.finish()?;
+      Ok((r, s))
+    };
+    let (r, s) = parse().map_err(|_| EcdsaError::InvalidEncoding)?;
+    Self::new::<C>(r, s)
+  }
+}
+
+fn is_scalar<C: Curve>(k: &BigInt) -> bool {
+  !k.is_zero() && k < C::n()
+}
+
+/// bits2int: the leftmost bits of `hash`, as many as n has
+fn bits2int<C: Curve>(hash: &[u8]) -> BigInt {
+  let qlen = C::n().bits();
+  let hlen = 8 * hash.len() as u64;
+  let e = bytes_to_int(hash);
+  if hlen > qlen { e >> (hlen - qlen) } else { e }
+}
+
+fn inverse_mod_n<C: Curve>(k: &BigInt) -> BigInt {
+  k.modpow(&(C::n() - BigInt::from(2u8)), C::n())
+}
+
+fn hmac<D: Digest + BlockSizeUser + Clone>(key: &[u8], parts: &[&[u8]]) -> Zeroizing<Vec<u8>> {
+  let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key)
+    .expect("HMAC can take key of any size");
+  for part in parts {
+    mac.update(part);
+  }
+  Zeroizing::new(mac.finalize().into_bytes().to_vec())
+}
+
+/// RFC 6979 section 3.2 candidates for k, with HMAC over `D`
+struct NonceGenerator<C: Curve, D: Digest + BlockSizeUser + Clone> {
+  k: Zeroizing<Vec<u8>>,
+  v: Zeroizing<Vec<u8>>,
+  first: bool,
+  marker: std::marker::PhantomData<(C, D)>,
+}
+
+impl<C: Curve, D: Digest + BlockSizeUser + Clone> NonceGenerator<C, D> {
+  fn new(secret: &BigInt, hash: &[u8]) -> Self {
+    let rlen = C::scalar_bytes();
+    let x = Zeroizing::new(int_to_bytes(secret, rlen));
+    // bits2octets(h1)
+    let h1 = int_to_bytes(&Euclid::rem_euclid(&bits2int::<C>(hash), C::n()), rlen);
+
+    let hlen = <D as Digest>::output_size();
+    let v = Zeroizing::new(vec![0x01u8; hlen]);
+    let k = Zeroizing::new(vec![0x00u8; hlen]);
+    let k = hmac::<D>(&k, &[&v, &[0x00], &x, &h1]);
+    let v = hmac::<D>(&k, &[&v]);
+    let k = hmac::<D>(&k, &[&v, &[0x01], &x, &h1]);
+    let v = hmac::<D>(&k, &[&v]);
+    Self { k, v, first: true, marker: std::marker::PhantomData }
+  }
+
+  fn next_candidate(&mut self) -> BigInt {
+    if !self.first {
+      self.k = hmac::<D>(&self.k, &[&self.v, &[0x00]]);
+      self.v = hmac::<D>(&self.k, &[&self.v]);
+    }
+    self.first = false;
+    loop {
+      let mut t = Zeroizing::new(Vec::new());
+      while t.len() < C::scalar_bytes() {
+        self.v = hmac::<D>(&self.k, &[&self.v]);
+        t.extend_from_slice(&self.v);
+      }
+      let candidate = bits2int::<C>(&t);
+      if is_scalar::<C>(&candidate) {
+        return candidate
+      }
+      self.k = hmac::<D>(&self.k, &[&self.v, &[0x00]]);
+      self.v = hmac::<D>(&self.k, &[&self.v]);
+    }
+  }
+}
+
+/// ECDSA private key on the curve `C`; the secret is wiped on drop
+#[derive(Clone)]
+pub struct SigningKey<C: Curve> {
+  secret: Zeroizing<Vec<u8>>,
+  pub point: AffinePoint<C>,
+}
+
+impl<C: Curve> fmt::Debug for SigningKey<C> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SigningKey")
+      .field("secret", &"[REDACTED]")
+      .field("point", &self.point)
+      .finish()
+  }
+}
+
+impl<C: Curve> SigningKey<C> {
+  /// uniform in [1, n-1]: random bytes, redrawn until in range
+  pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+    let mut bytes = Zeroizing::new(vec![0u8; C::scalar_bytes()]);
+    let excess_bits = 8 * C::scalar_bytes() as u64 - C::n().bits();
+    loop {
+      rng.fill_bytes(&mut bytes);
+      bytes[0] &= 0xff >> excess_bits;
+      if let Ok(key) = Self::from_bytes(&bytes) {
+        return key
+      }
+    }
+  }
+
+  /// big-endian secret of the scalar length of `C`, rejected unless 1 <= secret < n
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, EcdsaError> {
+    if bytes.len() != C::scalar_bytes() {
+      return Err(EcdsaError::InvalidKey)
+    }
+    let secret = bytes_to_int(bytes);
+    if !is_scalar::<C>(&secret) {
+      return Err(EcdsaError::InvalidKey)
+    }
+    Ok(Self { secret: Zeroizing::new(bytes.to_vec()), point: C::generator().rmul(secret) })
+  }
+
+  pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+    self.secret.clone()
+  }
+
+  /// hashes `msg` with `D` and signs the digest, RFC 6979 nonces from HMAC-`D`
+  pub fn sign<D: Digest + BlockSizeUser + Clone>(&self, msg: &[u8]) -> EcdsaSignature {
+    self.sign_prehashed::<D>(&D::digest(msg))
+  }
+
+  /// signs a digest computed by the caller; `D` is only the RFC 6979 HMAC hash
+  pub fn sign_prehashed<D: Digest + BlockSizeUser + Clone>(&self, hash: &[u8]) -> EcdsaSignature {
+    let secret = bytes_to_int(&self.secret);
+    let e = bits2int::<C>(hash);
+    let mut nonces = NonceGenerator::<C, D>::new(&secret, hash);
+    loop {
+      let k = nonces.next_candidate();
+      let r = match C::generator().rmul(k.clone()).x {
+        Some(x_) => Euclid::rem_euclid(&x_.get_num(), C::n()),
+        None => continue,
+      };
+      let s = Euclid::rem_euclid(&(inverse_mod_n::<C>(&k) * (&e + &r * &secret)), C::n());
+      if !r.is_zero() && !s.is_zero() {
+        return EcdsaSignature { r, s }
+      }
+    }
+  }
+}
+
+impl<C: Curve> AffinePoint<C> {
+  /// ECDSA verification of `msg` hashed with `D`
+  pub fn verify_ecdsa<D: Digest>(&self, msg: &[u8], sig: &EcdsaSignature) -> bool {
+    self.verify_ecdsa_prehashed(&D::digest(msg), sig)
+  }
+
+  /// ECDSA verification of a digest computed by the caller
+  pub fn verify_ecdsa_prehashed(&self, hash: &[u8], sig: &EcdsaSignature) -> bool {
+    if !is_scalar::<C>(&sig.r) || !is_scalar::<C>(&sig.s) || !self.is_on_curve() {
+      return false
+    }
+    let e = bits2int::<C>(hash);
+    let w = inverse_mod_n::<C>(&sig.s);
+    let u1 = Euclid::rem_euclid(&(e * &w), C::n());
+    let u2 = Euclid::rem_euclid(&(&sig.r * &w), C::n());
+    match Self::multi_mul(&[(u1, C::generator()), (u2, self.clone())]).x {
+      Some(x_) => Euclid::rem_euclid(&x_.get_num(), C::n()) == sig.r,
+      None => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use num_traits::One;
+  use sha2::{Sha256, Sha384, Sha512};
+
+  use super::*;
+  use crate::model::{
+    curve::{BrainpoolP256r1, BrainpoolP384r1, BrainpoolP512r1, P256, P384, P521},
+    helper::{decode_hex, encode_hex},
+  };
+
+  /// d, Qx, Qy, the message digest, r, s
+  type CavpVector = [&'static str; 6];
+
+  /// NIST CAVP 186-4 SigGen, P-256 with SHA-256
+  const CAVP_P256: &[CavpVector] = &[
+    [
+      "519b423d715f8b581f4fa8ee59f4771a5b44c8130b4e3eacca54a56dda72b464",
+      "1ccbe91c075fc7f4f033bfa248db8fccd3565de94bbfb12f3c59ff46c271bf83",
+      "ce4014c68811f9a21a1fdb2c0e6113e06db7ca93b7404e78dc7ccd5ca89a4ca9",
+      "44acf6b7e36c1342c2c5897204fe09504e1e2efb1a900377dbc4e7a6a133ec56",
+      "f3ac8061b514795b8843e3d6629527ed2afd6b1f6a555a7acabb5e6f79c8c2ac",
+      "8bf77819ca05a6b2786c76262bf7371cef97b218e96f175a3ccdda2acc058903",
+    ],
+    [
+      "0f56db78ca460b055c500064824bed999a25aaf48ebb519ac201537b85479813",
+      "e266ddfdc12668db30d4ca3e8f7749432c416044f2d2b8c10bf3d4012aeffa8a",
+      "bfa86404a2e9ffe67d47c587ef7a97a7f456b863b4d02cfc6928973ab5b1cb39",
+      "9b2db89cb0e8fa3cc7608b4d6cc1dec0114e0b9ff4080bea12b134f489ab2bbc",
+      "976d3a4e9d23326dc0baa9fa560b7c4e53f42864f508483a6473b6a11079b2db",
+      "1b766e9ceb71ba6c01dcd46e0af462cd4cfa652ae5017d4555b8eeefe36e1932",
+    ],
+    [
+      "e283871239837e13b95f789e6e1af63bf61c918c992e62bca040d64cad1fc2ef",
+      "74ccd8a62fba0e667c50929a53f78c21b8ff0c3c737b0b40b1750b2302b0bde8",
+      "29074e21f3a0ef88b9efdf10d06aa4c295cc1671f758ca0e4cd108803d0f2614",
+      "b804cf88af0c2eff8bbbfb3660ebb3294138e9d3ebd458884e19818061dacff0",
+      "35fb60f5ca0f3ca08542fb3cc641c8263a2cab7a90ee6a5e1583fac2bb6f6bd1",
+      "ee59d81bc9db1055cc0ed97b159d8784af04e98511d0a9a407b99bb292572e96",
+    ],
+    [
+      "a3d2d3b7596f6592ce98b4bfe10d41837f10027a90d7bb75349490018cf72d07",
+      "322f80371bf6e044bc49391d97c1714ab87f990b949bc178cb7c43b7c22d89e1",
+      "3c15d54a5cc6b9f09de8457e873eb3deb1fceb54b0b295da6050294fae7fd999",
+      "85b957d92766235e7c880ac5447cfbe97f3cb499f486d1e43bcb5c2ff9608a1a",
+      "d7c562370af617b581c84a2468cc8bd50bb1cbf322de41b7887ce07c0e5884ca",
+      "b46d9f2d8c4bf83546ff178f1d78937c008d64e8ecc5cbb825cb21d94d670d89",
+    ],
+    [
+      "53a0e8a8fe93db01e7ae94e1a9882a102ebd079b3a535827d583626c272d280d",
+      "1bcec4570e1ec2436596b8ded58f60c3b1ebc6a403bc5543040ba82963057244",
+      "8af62a4c683f096b28558320737bf83b9959a46ad2521004ef74cf85e67494e1",
+      "3360d699222f21840827cf698d7cb635bee57dc80cd7733b682d41b55b666e22",
+      "18caaf7b663507a8bcd992b836dec9dc5703c080af5e51dfa3a9a7c387182604",
+      "77c68928ac3b88d985fb43fb615fb7ff45c18ba5c81af796c613dfa98352d29c",
+    ],
+  ];
+
+  /// NIST CAVP 186-4 SigGen, P-384 with SHA-384
+  const CAVP_P384: &[CavpVector] = &[
+    [
+      "201b432d8df14324182d6261db3e4b3f46a8284482d52e370da41e6cbdf45ec2952f5db7ccbce3bc29449f4fb080ac97",
+      "c2b47944fb5de342d03285880177ca5f7d0f2fcad7678cce4229d6e1932fcac11bfc3c3e97d942a3c56bf34123013dbf",
+      "37257906a8223866eda0743c519616a76a758ae58aee81c5fd35fbf3a855b7754a36d4a0672df95d6c44a81cf7620c2d",
+      "31a452d6164d904bb5724c878280231eae705c29ce9d4bc7d58e020e1085f17eebcc1a38f0ed0bf2b344d81fbd896825",
+      "50835a9251bad008106177ef004b091a1e4235cd0da84fff54542b0ed755c1d6f251609d14ecf18f9e1ddfe69b946e32",
+      "0475f3d30c6463b646e8d3bf2455830314611cbde404be518b14464fdb195fdcc92eb222e61f426a4a592c00a6a89721",
+    ],
+    [
+      "23d9f4ea6d87b7d6163d64256e3449255db14786401a51daa7847161bf56d494325ad2ac8ba928394e01061d882c3528",
+      "5d42d6301c54a438f65970bae2a098cbc567e98840006e356221966c86d82e8eca515bca850eaa3cd41f175f03a0cbfd",
+      "4aef5a0ceece95d382bd70ab5ce1cb77408bae42b51a08816d5e5e1d3da8c18fcc95564a752730b0aabea983ccea4e2e",
+      "a92784916a40feaebfeab16ea28c0c65e45c5e81eb634052944865708072e20110bd669a9838d7e722e94ac75245cdd3",
+      "fb318f4cb1276282bb43f733a7fb7c567ce94f4d02924fc758635ab2d1107108bf159b85db080cdc3b30fbb5400016f3",
+      "588e3d7af5da03eae255ecb1813100d95edc243476b724b22db8e85377660d7645ddc1c2c2ee4eaea8b683dbe22f86ca",
+    ],
+    [
+      "b5f670e98d8befc46f6f51fb2997069550c2a52ebfb4e5e25dd905352d9ef89eed5c2ecd16521853aadb1b52b8c42ae6",
+      "44ffb2a3a95e12d87c72b5ea0a8a7cb89f56b3bd46342b2303608d7216301c21b5d2921d80b6628dc512ccb84e2fc278",
+      "e4c1002f1828abaec768cadcb7cf42fbf93b1709ccae6df5b134c41fae2b9a188bfbe1eccff0bd348517d7227f2071a6",
+      "b2acf6b4ae1ba9985c1e657313d59157939c21868302f6f5c5dbf037867035ae7c2009bad9fce472579923f7b4b87795",
+      "b11db592e4ebc75b6472b879b1d8ce57452c615aef20f67a280f8bca9b11a30ad4ac9d69541258c7dd5d0b4ab8dd7d49",
+      "4eb51db8004e46d438359abf060a9444616cb46b4f99c9a05b53ba6df02e914c9c0b6cc3a9791d804d2e4c0984dab1cc",
+    ],
+    [
+      "de5975d8932533f092e76295ed6b23f10fc5fba48bfb82c6cc714826baf0126813247f8bd51d5738503654ab22459976",
+      "f1fabafc01fec7e96d982528d9ef3a2a18b7fe8ae0fa0673977341c7ae4ae8d8d3d67420343d013a984f5f61da29ae38",
+      "1a31cf902c46343d01b2ebb614bc789c313b5f91f9302ad9418e9c797563e2fa3d44500f47b4e26ad8fdec1a816d1dcf",
+      "ec21c9d03a7270ea9ce7e9ff83211bac2fb104d078217c370248a3aba81f6c586852f19ced56dc71f83f5251d7381c8a",
+      "c2fbdd6a56789024082173725d797ef9fd6accb6ae664b7260f9e83cb8ab2490428c8b9c52e153612295432fec4d59cd",
+      "8056c5bb57f41f73082888b234fcda320a33250b5da012ba1fdb4924355ae679012d81d2c08fc0f8634c708a4833232f",
+    ],
+    [
+      "11e0d470dc31fab0f5722f87b74a6c8d7414115e58ceb38bfcdced367beac3adbf1fe9ba5a04f72e978b1eb54597eabc",
+      "1950166989164cbfd97968c7e8adb6fbca1873ebef811ea259eb48b7d584627f0e6d6c64defe23cbc95236505a252aa1",
+      "41ef424b5cb076d4e32accd9250ea75fcf4ffd81814040c050d58c0a29b06be11edf67c911b403e418b7277417e52906",
+      "f0272d0a51ee61f86d0875ca7800e12744ef6ffbac72bdda7c54ba24e5a5a6bd69ebe6f429cc20ac12b926d392efc4ce",
+      "c38ef30f55624e8935680c29f8c24824877cf48ffc0ef015e62de1068893353030d1193bf9d34237d7ce6ba92c98b0fe",
+      "651b8c3d5c9d5b936d300802a06d82ad54f7b1ba4327b2f031c0c5b0cb215ad4354edc7f932d934e877dfa1cf51b13fe",
+    ],
+  ];
+
+  /// NIST CAVP 186-4 SigGen, P-521 with SHA-512
+  const CAVP_P521: &[CavpVector] = &[
+    [
+      "00f749d32704bc533ca82cef0acf103d8f4fba67f08d2678e515ed7db886267ffaf02fab0080dca2359b72f574ccc29a0f218c8655c0cccf9fee6c5e567aa14cb926",
+      "0061387fd6b95914e885f912edfbb5fb274655027f216c4091ca83e19336740fd81aedfe047f51b42bdf68161121013e0d55b117a14e4303f926c8debb77a7fdaad1",
+      "00e7d0c75c38626e895ca21526b9f9fdf84dcecb93f2b233390550d2b1463b7ee3f58df7346435ff0434199583c97c665a97f12f706f2357da4b40288def888e59e6",
+      "65f83408092261bda599389df03382c5be01a81fe00a36f3f4bb6541263f801627c440e50809712b0cace7c217e6e5051af81de9bfec3204dcd63c4f9a741047",
+      "004de826ea704ad10bc0f7538af8a3843f284f55c8b946af9235af5af74f2b76e099e4bc72fd79d28a380f8d4b4c919ac290d248c37983ba05aea42e2dd79fdd33e8",
+      "0087488c859a96fea266ea13bf6d114c429b163be97a57559086edb64aed4a18594b46fb9efc7fd25d8b2de8f09ca0587f54bd287299f47b2ff124aac566e8ee3b43",
+    ],
+    [
+      "01a4d2623a7d59c55f408331ba8d1523b94d6bf8ac83375ceb57a2b395a5bcf977cfc16234d4a97d6f6ee25a99aa5bff15ff535891bcb7ae849a583e01ac49e0e9b6",
+      "004d5c8afee038984d2ea96681ec0dccb6b52dfa4ee2e2a77a23c8cf43ef19905a34d6f5d8c5cf0981ed804d89d175b17d1a63522ceb1e785c0f5a1d2f3d15e51352",
+      "0014368b8e746807b2b68f3615cd78d761a464ddd7918fc8df51d225962fdf1e3dc243e265100ff0ec133359e332e44dd49afd8e5f38fe86133573432d33c02fa0a3",
+      "a6200971c6a289e2fcb80f78ec08a5079ea2675efd68bcab479552aa5bcb8edf3c993c79d7cebcc23c20e5af41723052b871134cc71d5c57206182a7068cc39b",
+      "01a3c4a6386c4fb614fba2cb9e74201e1aaa0001aa931a2a939c92e04b8344535a20f53c6e3c69c75c2e5d2fe3549ed27e6713cb0f4a9a94f6189eb33bff7d453fce",
+      "016a997f81aa0bea2e1469c8c1dab7df02a8b2086ba482c43af04f2174831f2b1761658795adfbdd44190a9b06fe10e578987369f3a2eced147cff89d8c2818f7471",
+    ],
+    [
+      "014787f95fb1057a2f3867b8407e54abb91740c097dac5024be92d5d65666bb16e4879f3d3904d6eab269cf5e7b632ab3c5f342108d1d4230c30165fba3a1bf1c66f",
+      "00c2d540a7557f4530de35bbd94da8a6defbff783f54a65292f8f76341c996cea38795805a1b97174a9147a8644282e0d7040a6f83423ef2a0453248156393a1782e",
+      "0119f746c5df8cec24e4849ac1870d0d8594c799d2ceb6c3bdf891dfbd2242e7ea24d6aec3166214734acc4cbf4da8f71e2429c5c187b2b3a048527c861f58a9b97f",
+      "46ff533622cc90321a3aeb077ec4db4fbf372c7a9db48b59de7c5d59e6314110676ba5491bd20d0f02774eef96fc2e88ca99857d21ef255184c93fb1ff4f01d3",
+      "010ed3ab6d07a15dc3376494501c27ce5f78c8a2b30cc809d3f9c3bf1aef437e590ef66abae4e49065ead1af5f752ec145acfa98329f17bca9991a199579c41f9229",
+      "008c3457fe1f93d635bb52df9218bf3b49a7a345b8a8a988ac0a254340546752cddf02e6ce47eee58ea398fdc9130e55a4c09f5ae548c715f5bcd539f07a34034d78",
+    ],
+    [
+      "015807c101099c8d1d3f24b212af2c0ce525432d7779262eed0709275de9a1d8a8eeeadf2f909cf08b4720815bc1205a23ad1f825618cb78bde747acad8049ca9742",
+      "0160d7ea2e128ab3fabd1a3ad5455cb45e2f977c2354a1345d4ae0c7ce4e492fb9ff958eddc2aa61735e5c1971fa6c99beda0f424a20c3ce969380aaa52ef5f5daa8",
+      "014e4c83f90d196945fb4fe1e41913488aa53e24c1d2142d35a1eed69fed784c0ef44d71bc21afe0a0065b3b87069217a5abab4355cf8f4ceae5657cd4b9c8008f1f",
+      "6b514f8d85145e30ced23b4b22c85d79ed2bfcfed5b6b2b03f7c730f1981d46d4dadd6699c28627d41c8684bac305b59eb1d9c966de184ae3d7470a801c99fd4",
+      "01e2bf98d1186d7bd3509f517c220de51c9200981e9b344b9fb0d36f34d969026c80311e7e73bb13789a99e0d59e82ebe0e9595d9747204c5f5550c30d934aa30c05",
+      "012fed45cc874dc3ed3a11dd70f7d5c61451fbea497dd63e226e10364e0718d3722c27c7b4e5027051d54b8f2a57fc58bc070a55b1a5877b0f388d768837ef2e9cec",
+    ],
+    [
+      "018692def0b516edcdd362f42669999cf27a65482f9358fcab312c6869e22ac469b82ca9036fe123935b8b9ed064acb347227a6e377fb156ec833dab9f170c2ac697",
+      "01ceee0be3293d8c0fc3e38a78df55e85e6b4bbce0b9995251f0ac55234140f82ae0a434b2bb41dc0aa5ecf950d4628f82c7f4f67651b804d55d844a02c1da6606f7",
+      "01f775eb6b3c5e43fc754052d1f7fc5b99137afc15d231a0199a702fc065c917e628a54e038cbfebe05c90988b65183b368a2061e5b5c1b025bbf2b748fae00ba297",
+      "53c86e0b08b28e22131324f6bfad52984879ab09363d6b6c051aac78bf3568be3faeade6a2dda57dece4527abaa148326d3adbd2d725374bdac9ccb8ac39e51e",
+      "014dfa43046302b81fd9a34a454dea25ccb594ace8df4f9d98556ca5076bcd44b2a9775dfaca50282b2c8988868e5a31d9eb08e794016996942088d43ad3379eb9a1",
+      "0120be63bd97691f6258b5e78817f2dd6bf5a7bf79d01b8b1c3382860c4b00f89894c72f93a69f3119cb74c90b03e9ede27bd298b357b9616a7282d176f3899aaa24",
+    ],
+  ];
+
+  fn int(hex: &str) -> BigInt {
+    bytes_to_int(&decode_hex(hex).unwrap())
+  }
+
+  fn check_cavp<C: Curve>(vectors: &[CavpVector]) {
+    for [d, qx, qy, digest, r, s] in vectors {
+      let key = SigningKey::<C>::from_bytes(&decode_hex(d).unwrap()).unwrap();
+      assert_eq!(key.point, AffinePoint::from_coordinates(&int(qx), &int(qy)));
+      let sig = EcdsaSignature::new::<C>(int(r), int(s)).unwrap();
+      let mut digest = decode_hex(digest).unwrap();
+      assert!(key.point.verify_ecdsa_prehashed(&digest, &sig));
+      assert_eq!(EcdsaSignature::from_bytes::<C>(&sig.to_bytes::<C>()), Ok(sig.clone()));
+      assert_eq!(EcdsaSignature::from_der::<C>(&sig.to_der()), Ok(sig.clone()));
+      digest[3] ^= 1;
+      assert!(!key.point.verify_ecdsa_prehashed(&digest, &sig));
+    }
+  }
+
+  /// RFC 6979 deterministic signature, r || s
+  fn sign_hex<C: Curve, D: Digest + BlockSizeUser + Clone>(key: &SigningKey<C>, msg: &[u8]) -> String {
+    let sig = key.sign::<D>(msg);
+    assert!(key.point.verify_ecdsa::<D>(msg, &sig));
+    encode_hex(&sig.to_bytes::<C>())
+  }
+
+  #[test]
+  fn cavp_p256() {
+    check_cavp::<P256>(CAVP_P256);
+  }
+
+  /// RFC 6979 appendix A.2.5
+  #[test]
+  fn rfc6979_p256() {
+    let key = SigningKey::<P256>::from_bytes(
+      &decode_hex("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721").unwrap()
+    ).unwrap();
+    assert_eq!(
+      sign_hex::<P256, Sha256>(&key, b"sample"),
+      "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716\
+       f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"
+    );
+    assert_eq!(
+      sign_hex::<P256, Sha256>(&key, b"test"),
+      "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367\
+       019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083"
+    );
+    // a digest longer than n, truncated to its leftmost 256 bits
+    assert_eq!(
+      sign_hex::<P256, Sha384>(&key, b"test"),
+      "83910e8b48bb0c74244ebdf7f07a1c5413d61472bd941ef3920e623fbccebeb6\
+       8ddbec54cf8cd5874883841d712142a56a8d0f218f5003cb0296b6b509619f2c"
+    );
+  }
+
+  #[test]
+  fn out_of_range_values_are_rejected() {
+    assert_eq!(SigningKey::<P256>::from_bytes(&int_to_bytes(P256::n(), 32)).err(), Some(EcdsaError::InvalidKey));
+    assert_eq!(SigningKey::<P256>::from_bytes(&[0u8; 32]).err(), Some(EcdsaError::InvalidKey));
+    assert!(EcdsaSignature::new::<P256>(BigInt::zero(), BigInt::one()).is_err());
+    assert!(EcdsaSignature::new::<P256>(BigInt::one(), P256::n().clone()).is_err());
+  }
+
+  #[test]
+  fn cavp_p384() {
+    check_cavp::<P384>(CAVP_P384);
+  }
+
+  #[test]
+  fn cavp_p521() {
+    check_cavp::<P521>(CAVP_P521);
+  }
+
+  /// RFC 6979 appendix A.2.6
+  #[test]
+  fn rfc6979_p384() {
+    let key = SigningKey::<P384>::from_bytes(&decode_hex(
+      "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5"
+    ).unwrap()).unwrap();
+    assert_eq!(
+      sign_hex::<P384, Sha384>(&key, b"sample"),
+      "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c81a648152e44acf96e36dd1e80fabe46\
+       99ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94fa329c145786e679e7b82c71a38628ac8"
+    );
+    assert_eq!(
+      sign_hex::<P384, Sha384>(&key, b"test"),
+      "8203b63d3c853e8d77227fb377bcf7b7b772e97892a80f36ab775d509d7a5feb0542a7f0812998da8f1dd3ca3cf023db\
+       ddd0760448d42d8a43af45af836fce4de8be06b485e9b61b827c2f13173923e06a739f040649a667bf3b828246baa5a5"
+    );
+    // a digest shorter than n is used whole
+    assert_eq!(
+      sign_hex::<P384, Sha256>(&key, b"test"),
+      "6d6defac9ab64dabafe36c6bf510352a4cc27001263638e5b16d9bb51d451559f918eedaf2293be5b475cc8f0188636b\
+       2d46f3becbcc523d5f1a1256bf0c9b024d879ba9e838144c8ba6baeb4b53b47d51ab373f9845c0514eefb14024787265"
+    );
+  }
+
+  /// RFC 6979 appendix A.2.7, r and s are 66 bytes each
+  #[test]
+  fn rfc6979_p521() {
+    let key = SigningKey::<P521>::from_bytes(&decode_hex(
+      "00fad06daa62ba3b25d2fb40133da757205de67f5bb0018fee8c86e1b68c7e75caa896eb32f1f47c70855836a6d16fcc1466f6d8fbec67db89ec0c08b0e996b83538"
+    ).unwrap()).unwrap();
+    assert_eq!(
+      sign_hex::<P521, Sha512>(&key, b"sample"),
+      "00c328fafcbd79dd77850370c46325d987cb525569fb63c5d3bc53950e6d4c5f174e25a1ee9017b5d450606add152b534931d7d4e8455cc91f9b15bf05ec36e377fa\
+       00617cce7cf5064806c467f678d3b4080d6f1cc50af26ca209417308281b68af282623eaa63e5b5c0723d8b8c37ff0777b1a20f8ccb1dccc43997f1ee0e44da4a67a"
+    );
+    assert_eq!(
+      sign_hex::<P521, Sha256>(&key, b"sample"),
+      "01511bb4d675114fe266fc4372b87682baecc01d3cc62cf2303c92b3526012659d16876e25c7c1e57648f23b73564d67f61c6f14d527d54972810421e7d87589e1a7\
+       004a171143a83163d6df460aaf61522695f207a58b95c0644d87e52aa1a347916e4f7a72930b1bc06dbe22ce3f58264afd23704cbb63b29b931f7de6c9d949a7ecfc"
+    );
+  }
+
+  /// deterministic signatures with the RFC 7027 keys, cross-checked against OpenSSL
+  #[test]
+  fn brainpool_signatures() {
+    let key = SigningKey::<BrainpoolP256r1>::from_bytes(&decode_hex(
+      "81db1ee100150ff2ea338d708271be38300cb54241d79950f77b063039804f1d"
+    ).unwrap()).unwrap();
+    assert_eq!(
+      sign_hex::<BrainpoolP256r1, Sha256>(&key, b"sample"),
+      "8a6b3a1a10a8ce1459cdeed0cb1884597642e6bc47b9c552ea6114a0d03c40dd\
+       466c80b901174c93369e5736a6993c081087040f5c7039f314e02a2853d6f87f"
+    );
+    let key = SigningKey::<BrainpoolP384r1>::from_bytes(&decode_hex(
+      "1e20f5e048a5886f1f157c74e91bde2b98c8b52d58e5003d57053fc4b0bd65d6f15eb5d1ee1610df870795143627d042"
+    ).unwrap()).unwrap();
+    assert_eq!(
+      sign_hex::<BrainpoolP384r1, Sha384>(&key, b"sample"),
+      "2a0b927997ff034d1d5081be0f3f8c6eecac3dc9bb194774e69d5363cd924741e1802c3c0caea91ec144c869c993cfd6\
+       22bc8dbdc26500cc666a7128a19aae17e79ec1853d8703dc0b6e703052e39cd4f3f3819bfef8094335ede131acfe3071"
+    );
+    let key = SigningKey::<BrainpoolP512r1>::from_bytes(&decode_hex(
+      "16302ff0dbbb5a8d733dab7141c1b45acbc8715939677f6a56850a38bd87bd59b09e80279609ff333eb9d4c061231fb26f92eeb04982a5f1d1764cad57665422"
+    ).unwrap()).unwrap();
+    assert_eq!(
+      sign_hex::<BrainpoolP512r1, Sha512>(&key, b"sample"),
+      "5a62cd28cd5aa4cd20a8aa2973e593006eca66d956354ab09ab1da0efbb72e0035586a4174a17fb01b50003ebc64b6719f7695e14777dbc630c8b72f67fe1532\
+       a5a80b85ad0a792960106c1fe420c57e6ef456a80493c51d3ce7768fe2717ea632202f160bc714dfd3fe1823d77b40bf21d16b2ac9288f8728e05503d40f8ab5"
+    );
+  }
+}
@@ -0,0 +1,188 @@
+//! ECIES hybrid encryption to a secp256k1 public key
+//!
+//! Wire format, the eciespy / ecies-rs default:
+//! `ephemeral public key (65 bytes, uncompressed) || nonce (16) || tag (16) || ciphertext`
+//!
+//! The AES-256-GCM key is HKDF-SHA256 of
+//! `ephemeral public key || shared point`, both uncompressed, with an empty
+//! salt and info.
+
+use aes_gcm::{
+  aead::{generic_array::{typenum::U16, GenericArray}, AeadInPlace, KeyInit},
+  aes::Aes256,
+  AesGcm,
+};
+use hkdf::Hkdf;
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::One;
+use rand::RngCore;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::{
+  constants::{Gs, N},
+  ecdh::{shared_point, EcdhError},
+  helper::SecretScalar,
+  s256_point::S256Point,
+};
+
+type Aes256Gcm16 = AesGcm<Aes256, U16>;
+
+const PUBLIC_KEY_LENGTH: usize = 65;
+const NONCE_LENGTH: usize = 16;
+const TAG_LENGTH: usize = 16;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EciesError {
+  /// shorter than the ephemeral key, nonce and tag
+  MessageTooShort,
+  /// the ephemeral public key is not an uncompressed point on the curve
+  InvalidEphemeralKey,
+  /// ECDH with the receiver key failed
+  Ecdh(EcdhError),
+  /// the tag does not authenticate the ciphertext
+  DecryptionFailed,
+}
+
+impl From<EcdhError> for EciesError {
+  fn from(e: EcdhError) -> Self {
+    EciesError::Ecdh(e)
+  }
+}
+
+fn derive_key(ephemeral_key: &S256Point, shared: &S256Point) -> Aes256Gcm16 {
+  let mut ikm = Zeroizing::new(ephemeral_key.sec(false));
+  ikm.extend(shared.sec(false));
+  let mut okm = Zeroizing::new([0u8; 32]);
+  Hkdf::<Sha256>::new(None, &ikm)
+    .expand(&[], okm.as_mut())
+    .expect("32 bytes is a valid HKDF-SHA256 output length");
+  Aes256Gcm16::new(GenericArray::from_slice(okm.as_ref()))
+}
+
+pub fn encrypt(receiver: &S256Point, msg: &[u8]) -> Result<Vec<u8>, EciesError> {
+  let mut rng = rand::thread_rng();
+  // wiped on drop: anyone holding it can decrypt
+  let ephemeral_secret = SecretScalar::new(rng.gen_bigint_range(&BigInt::one(), &N));
+  let ephemeral_key = Gs.rmul(ephemeral_secret.clone_int());
+  let shared = shared_point(&ephemeral_secret, receiver)?;
+  let cipher = derive_key(&ephemeral_key, &shared);
+
+  let mut nonce = [0u8; NONCE_LENGTH];
+  rng.fill_bytes(&mut nonce);
+  let mut ciphertext = msg.to_vec();
+  let tag = cipher
+    .encrypt_in_place_detached(GenericArray::from_slice(&nonce), &[], &mut ciphertext)
+    .expect("message exceeds the AES-GCM length limit");
+
+  let mut out = ephemeral_key.sec(false);
+  out.extend_from_slice(&nonce);
+  out.extend_from_slice(&tag);
+  out.extend(ciphertext);
+  Ok(out)
+}
+
+pub fn decrypt(secret: &BigInt, data: &[u8]) -> Result<Vec<u8>, EciesError> {
+  if data.len() < PUBLIC_KEY_LENGTH + NONCE_LENGTH + TAG_LENGTH {
+    return Err(EciesError::MessageTooShort)
+  }
+  let (ephemeral_key, rest) = data.split_at(PUBLIC_KEY_LENGTH);
+  let (nonce, rest) = rest.split_at(NONCE_LENGTH);
+  let (tag, ciphertext) = rest.split_at(TAG_LENGTH);
+
+  let ephemeral_key = S256Point::parse(ephemeral_key).ok_or(EciesError::InvalidEphemeralKey)?;
+  let shared = shared_point(secret, &ephemeral_key)?;
+  let cipher = derive_key(&ephemeral_key, &shared);
+
+  let mut plaintext = ciphertext.to_vec();
+  cipher
+    .decrypt_in_place_detached(
+      GenericArray::from_slice(nonce),
+      &[],
+      &mut plaintext,
+      GenericArray::from_slice(tag),
+    )
+    .map_err(|_| EciesError::DecryptionFailed)?;
+  Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{helper::decode_hex, private_key::PrivateKey};
+
+  const OVERHEAD: usize = PUBLIC_KEY_LENGTH + NONCE_LENGTH + TAG_LENGTH;
+
+  fn receiver() -> PrivateKey {
+    let secret = BigInt::parse_bytes(b"5f1c6c2b8b6b3f0e2d4a1c9e7b3d5f7a9c1e3b5d7f9a1c3e5b7d9f1a3c5e7b9d", 16).unwrap();
+    PrivateKey::try_new(&secret).unwrap()
+  }
+
+  // eciespy's default construction (uncompressed keys in the HKDF input,
+  // 16 byte nonce), built with the Python cryptography package from the
+  // ephemeral secret 3a7f6e2c...2b and the nonce 00 01 .. 0f
+  #[test]
+  fn decrypts_fixed_ciphertext() {
+    let key = receiver();
+    assert_eq!(key.point.sec(false), decode_hex(
+      "04e8d8c3d3de79303547f8e719dcbcd754fd383ad201a46ef812bbf7d8943cde9d\
+       fb410031fdf67380ab8af315d0c88e3a573dac81d123f488fbecec17948f0871"
+    ).unwrap());
+    let data = decode_hex(
+      "04180260394e051c03660f789f8644b688b4d2b2d50787b13d66316c060587bb3e\
+       af954acb69ac513f30eaec9022ec5551fff3b8e07a079db71ed596b93f7c3bee\
+       000102030405060708090a0b0c0d0e0f\
+       a4d5eb1dedea9623b606bf482a4a59e9\
+       7d1bd10f5c905180985f"
+    ).unwrap();
+    assert_eq!(key.decrypt(&data).unwrap(), b"helloworld");
+  }
+
+  #[test]
+  fn round_trip() {
+    let key = receiver();
+    for msg in [&b""[..], b"x", &[0xa5; 1000]] {
+      let data = key.point.encrypt(msg).unwrap();
+      assert_eq!(data.len(), OVERHEAD + msg.len());
+      assert_eq!(data[0], 4);
+      assert_eq!(key.decrypt(&data).unwrap(), msg);
+      // a fresh ephemeral key and nonce every time
+      assert_ne!(key.point.encrypt(msg).unwrap(), data);
+    }
+    let other = PrivateKey::generate(&mut rand::thread_rng());
+    let data = key.point.encrypt(b"for key only").unwrap();
+    assert_eq!(other.decrypt(&data).unwrap_err(), EciesError::DecryptionFailed);
+  }
+
+  #[test]
+  fn tampering_is_detected() {
+    let key = receiver();
+    let data = key.point.encrypt(b"attack at dawn").unwrap();
+    // nonce, tag and ciphertext bytes all fail authentication
+    for i in PUBLIC_KEY_LENGTH..data.len() {
+      let mut tampered = data.clone();
+      tampered[i] ^= 1;
+      assert_eq!(key.decrypt(&tampered).unwrap_err(), EciesError::DecryptionFailed);
+    }
+    // an ephemeral key off the curve does not parse
+    let mut tampered = data.clone();
+    tampered[PUBLIC_KEY_LENGTH - 1] ^= 1;
+    assert_eq!(key.decrypt(&tampered).unwrap_err(), EciesError::InvalidEphemeralKey);
+    let mut compressed = data.clone();
+    compressed[0] = 2;
+    assert_eq!(key.decrypt(&compressed).unwrap_err(), EciesError::InvalidEphemeralKey);
+  }
+
+  #[test]
+  fn truncated_input_is_rejected() {
+    let key = receiver();
+    let data = key.point.encrypt(b"attack at dawn").unwrap();
+    for len in [0, 1, PUBLIC_KEY_LENGTH, OVERHEAD - 1] {
+      assert_eq!(key.decrypt(&data[..len]).unwrap_err(), EciesError::MessageTooShort);
+    }
+    // the tag no longer matches once ciphertext bytes are cut off
+    for len in [OVERHEAD, data.len() - 1] {
+      assert_eq!(key.decrypt(&data[..len]).unwrap_err(), EciesError::DecryptionFailed);
+    }
+  }
+}
@@ -0,0 +1,393 @@
+//! Ed25519, Ed25519ctx and Ed25519ph signatures
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.1
+//!
+//! RFC 8032 leaves some verification details open, so `verify` takes a `Verification`:
+//! * `Rfc8032`: canonical A and R, S < L, the cofactored equation [8][S]B = [8]R + [8][k]A
+//! * `Strict`: also rejects small-order A and R and checks [S]B = R + [k]A without the cofactor
+//! * `Zip215`: non-canonical A and R accepted, S < L, cofactored; every verifier agrees on the result
+//!   https://zips.z.cash/zip-0215
+
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::Euclid;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use zeroize::Zeroizing;
+
+use super::{
+  edwards::{EdwardsPoint, BASE, L},
+  helper::{int_to_le_bytes32, le_bytes_to_int},
+};
+
+/// dom2 prefix of Ed25519ctx and Ed25519ph
+const DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Ed25519Error {
+  /// the public key is not the encoding of a curve point
+  InvalidKey,
+  /// a context longer than 255 bytes, or an empty Ed25519ctx context
+  InvalidContext,
+  /// the signature does not verify under the chosen rules
+  InvalidSignature,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum Verification {
+  #[default]
+  Rfc8032,
+  Strict,
+  Zip215,
+}
+
+/// which of the RFC 8032 variants, selecting dom2(phflag, context)
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum Variant<'a> {
+  Pure,
+  Ctx(&'a [u8]),
+  Ph(&'a [u8]),
+}
+
+impl Variant<'_> {
+  fn dom2(&self) -> Result<Vec<u8>, Ed25519Error> {
+    let (flag, context) = match self {
+      Variant::Pure => return Ok(Vec::new()),
+      Variant::Ctx([]) => return Err(Ed25519Error::InvalidContext),
+      Variant::Ctx(context) => (0u8, context),
+      Variant::Ph(context) => (1u8, context),
+    };
+    if context.len() > 255 {
+      return Err(Ed25519Error::InvalidContext)
+    }
+    let mut out = DOM2_PREFIX.to_vec();
+    out.push(flag);
+    out.push(context.len() as u8);
+    out.extend_from_slice(context);
+    Ok(out)
+  }
+
+  /// PH(M): SHA-512 for Ed25519ph, the identity otherwise
+  fn message(&self, msg: &[u8]) -> Vec<u8> {
+    match self {
+      Variant::Ph(_) => Sha512::digest(msg).to_vec(),
+      _ => msg.to_vec(),
+    }
+  }
+}
+
+/// SHA-512(parts) as a little-endian integer mod L
+fn hash_to_scalar(parts: &[&[u8]]) -> BigInt {
+  let mut hasher = Sha512::new();
+  for part in parts {
+    hasher.update(part);
+  }
+  Euclid::rem_euclid(&le_bytes_to_int(&hasher.finalize()), &L)
+}
+
+/// `R || S`, 64 bytes
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Ed25519Signature {
+  pub r: [u8; 32],
+  pub s: [u8; 32],
+}
+
+impl Ed25519Signature {
+  pub fn from_bytes(bytes: &[u8; 64]) -> Self {
+    let (mut r, mut s) = ([0u8; 32], [0u8; 32]);
+    r.copy_from_slice(&bytes[..32]);
+    s.copy_from_slice(&bytes[32..]);
+    Self { r, s }
+  }
+
+  pub fn to_bytes(&self) -> [u8; 64] {
+    let mut out = [0u8; 64];
+    out[..32].copy_from_slice(&self.r);
+    out[32..].copy_from_slice(&self.s);
+    out
+  }
+}
+
+/// the 32-byte public key A; the bytes are kept as given, since they are hashed into k
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Ed25519PublicKey {
+  bytes: [u8; 32],
+  point: EdwardsPoint,
+}
+
+impl Ed25519PublicKey {
+  /// any encoding of a curve point; non-canonical and small-order keys are refused at verification
+  /// unless `Verification::Zip215` is used
+  pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, Ed25519Error> {
+    let point = EdwardsPoint::decompress_zip215(bytes).ok_or(Ed25519Error::InvalidKey)?;
+    Ok(Self { bytes: *bytes, point })
+  }
+
+  pub fn to_bytes(&self) -> [u8; 32] {
+    self.bytes
+  }
+
+  pub fn point(&self) -> &EdwardsPoint {
+    &self.point
+  }
+
+  pub fn verify(&self, msg: &[u8], sig: &Ed25519Signature, rules: Verification) -> Result<(), Ed25519Error> {
+    self.verify_variant(Variant::Pure, msg, sig, rules)
+  }
+
+  pub fn verify_ctx(
+    &self, msg: &[u8], context: &[u8], sig: &Ed25519Signature, rules: Verification
+  ) -> Result<(), Ed25519Error> {
+    self.verify_variant(Variant::Ctx(context), msg, sig, rules)
+  }
+
+  /// `msg` is the message itself, hashed here with SHA-512
+  pub fn verify_ph(
+    &self, msg: &[u8], context: &[u8], sig: &Ed25519Signature, rules: Verification
+  ) -> Result<(), Ed25519Error> {
+    self.verify_variant(Variant::Ph(context), msg, sig, rules)
+  }
+
+  fn verify_variant(
+    &self, variant: Variant, msg: &[u8], sig: &Ed25519Signature, rules: Verification
+  ) -> Result<(), Ed25519Error> {
+    let dom = variant.dom2()?;
+    let s = le_bytes_to_int(&sig.s);
+    if s >= *L {
+      return Err(Ed25519Error::InvalidSignature)
+    }
+    let r = match rules {
+      Verification::Zip215 => EdwardsPoint::decompress_zip215(&sig.r),
+      _ => EdwardsPoint::decompress(&sig.r),
+    }.ok_or(Ed25519Error::InvalidSignature)?;
+    if rules != Verification::Zip215 && !EdwardsPoint::is_canonical_encoding(&self.bytes) {
+      return Err(Ed25519Error::InvalidSignature)
+    }
+    if rules == Verification::Strict && (self.point.is_small_order() || r.is_small_order()) {
+      return Err(Ed25519Error::InvalidSignature)
+    }
+
+    let k = hash_to_scalar(&[&dom, &sig.r, &self.bytes, &variant.message(msg)]);
+    // [S]B - [k]A - R
+    let check = BASE.rmul(s).add(&self.point.rmul(k).negate()).add(&r.negate());
+    let valid = match rules {
+      Verification::Strict => check.is_identity(),
+      _ => check.is_small_order(),
+    };
+    if valid { Ok(()) } else { Err(Ed25519Error::InvalidSignature) }
+  }
+}
+
+/// Ed25519 private key: the 32-byte seed, wiped on drop
+#[derive(Clone)]
+pub struct Ed25519SigningKey {
+  seed: Zeroizing<[u8; 32]>,
+  pub public_key: Ed25519PublicKey,
+}
+
+impl fmt::Debug for Ed25519SigningKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Ed25519SigningKey")
+      .field("seed", &"[REDACTED]")
+      .field("public_key", &self.public_key)
+      .finish()
+  }
+}
+
+impl Ed25519SigningKey {
+  pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    rng.fill_bytes(seed.as_mut());
+    Self::from_seed(&seed)
+  }
+
+  pub fn from_seed(seed: &[u8; 32]) -> Self {
+    let (scalar, _) = Self::expand(seed);
+    let point = BASE.rmul(scalar);
+    let public_key = Ed25519PublicKey { bytes: point.compress(), point };
+    Self { seed: Zeroizing::new(*seed), public_key }
+  }
+
+  pub fn to_seed(&self) -> Zeroizing<[u8; 32]> {
+    self.seed.clone()
+  }
+
+  /// SHA-512(seed): the clamped scalar s from the low half, the nonce prefix from the high half
+  fn expand(seed: &[u8; 32]) -> (BigInt, Zeroizing<[u8; 32]>) {
+    let h = Zeroizing::new(<[u8; 64]>::from(Sha512::digest(seed)));
+    let mut low = Zeroizing::new([0u8; 32]);
+    low.copy_from_slice(&h[..32]);
+    low[0] &= 0xf8;
+    low[31] &= 0x7f;
+    low[31] |= 0x40;
+    let mut prefix = Zeroizing::new([0u8; 32]);
+    prefix.copy_from_slice(&h[32..]);
+    (le_bytes_to_int(low.as_ref()), prefix)
+  }
+
+  pub fn sign(&self, msg: &[u8]) -> Ed25519Signature {
+    self.sign_variant(Variant::Pure, msg).expect("Ed25519 has no context to reject")
+  }
+
+  /// Ed25519ctx, `context` of 1 to 255 bytes
+  pub fn sign_ctx(&self, msg: &[u8], context: &[u8]) -> Result<Ed25519Signature, Ed25519Error> {
+    self.sign_variant(Variant::Ctx(context), msg)
+  }
+
+  /// Ed25519ph over SHA-512(msg), `context` of up to 255 bytes
+  pub fn sign_ph(&self, msg: &[u8], context: &[u8]) -> Result<Ed25519Signature, Ed25519Error> {
+    self.sign_variant(Variant::Ph(context), msg)
+  }
+
+  fn sign_variant(&self, variant: Variant, msg: &[u8]) -> Result<Ed25519Signature, Ed25519Error> {
+    let dom = variant.dom2()?;
+    let msg = variant.message(msg);
+    let (s, prefix) = Self::expand(&self.seed);
+    let a = self.public_key.bytes;
+
+    let r = hash_to_scalar(&[&dom, prefix.as_ref(), &msg]);
+    let r_bytes = BASE.rmul(r.clone()).compress();
+    let k = hash_to_scalar(&[&dom, &r_bytes, &a, &msg]);
+    let s = Euclid::rem_euclid(&(r + k * s), &L);
+    Ok(Ed25519Signature { r: r_bytes, s: int_to_le_bytes32(&s) })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{edwards::P, helper::{decode_hex, encode_hex}};
+
+  /// RFC 8032 section 7.1, TEST 1 and TEST 2
+  #[test]
+  fn rfc8032_vectors() {
+    let vectors = [
+      (
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "",
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+      ),
+      (
+        "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "72",
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+      ),
+    ];
+    for (seed, public, msg, sig) in vectors {
+      let key = Ed25519SigningKey::from_seed(&decode_hex(seed).unwrap().try_into().unwrap());
+      assert_eq!(encode_hex(&key.public_key.to_bytes()), public);
+      let msg = decode_hex(msg).unwrap();
+      let signature = key.sign(&msg);
+      assert_eq!(encode_hex(&signature.to_bytes()), sig);
+      for rules in [Verification::Rfc8032, Verification::Strict, Verification::Zip215] {
+        assert_eq!(key.public_key.verify(&msg, &signature, rules), Ok(()));
+        assert_eq!(key.public_key.verify(b"other", &signature, rules), Err(Ed25519Error::InvalidSignature));
+      }
+    }
+  }
+
+  /// RFC 8032 section 7.2, the four Ed25519ctx tests
+  #[test]
+  fn rfc8032_ctx_vectors() {
+    let vectors = [
+      (
+        "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+        "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+        "f726936d19c800494e3fdaff20b276a8",
+        "666f6f",
+        "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d",
+      ),
+      (
+        "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+        "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+        "f726936d19c800494e3fdaff20b276a8",
+        "626172",
+        "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d",
+      ),
+      (
+        "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+        "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+        "508e9e6882b979fea900f62adceaca35",
+        "666f6f",
+        "8b70c1cc8310e1de20ac53ce28ae6e7207f33c3295e03bb5c0732a1d20dc64908922a8b052cf99b7c4fe107a5abb5b2c4085ae75890d02df26269d8945f84b0b",
+      ),
+      (
+        "ab9c2853ce297ddab85c993b3ae14bcad39b2c682beabc27d6d4eb20711d6560",
+        "0f1d1274943b91415889152e893d80e93275a1fc0b65fd71b4b0dda10ad7d772",
+        "f726936d19c800494e3fdaff20b276a8",
+        "666f6f",
+        "21655b5f1aa965996b3f97b3c849eafba922a0a62992f73b3d1b73106a84ad85e9b86a7b6005ea868337ff2d20a7f5fbd4cd10b0be49a68da2b2e0dc0ad8960f",
+      ),
+    ];
+    for (seed, public, msg, context, sig) in vectors {
+      let key = Ed25519SigningKey::from_seed(&decode_hex(seed).unwrap().try_into().unwrap());
+      assert_eq!(encode_hex(&key.public_key.to_bytes()), public);
+      let (msg, context) = (decode_hex(msg).unwrap(), decode_hex(context).unwrap());
+      let signature = key.sign_ctx(&msg, &context).unwrap();
+      assert_eq!(encode_hex(&signature.to_bytes()), sig);
+      for rules in [Verification::Rfc8032, Verification::Strict, Verification::Zip215] {
+        assert_eq!(key.public_key.verify_ctx(&msg, &context, &signature, rules), Ok(()));
+        assert_eq!(key.public_key.verify_ctx(&msg, b"baz", &signature, rules), Err(Ed25519Error::InvalidSignature));
+        // the dom2 prefix separates Ed25519ctx from plain Ed25519
+        assert_eq!(key.public_key.verify(&msg, &signature, rules), Err(Ed25519Error::InvalidSignature));
+      }
+    }
+    let key = Ed25519SigningKey::from_seed(&[1; 32]);
+    assert_eq!(key.sign_ctx(b"msg", b"").unwrap_err(), Ed25519Error::InvalidContext);
+    assert_eq!(key.sign_ctx(b"msg", &[0; 256]).unwrap_err(), Ed25519Error::InvalidContext);
+  }
+
+  /// RFC 8032 section 7.3, TEST abc
+  #[test]
+  fn rfc8032_ph_vector() {
+    let seed = decode_hex("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42").unwrap();
+    let key = Ed25519SigningKey::from_seed(&seed.try_into().unwrap());
+    assert_eq!(
+      encode_hex(&key.public_key.to_bytes()),
+      "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf"
+    );
+    let signature = key.sign_ph(b"abc", b"").unwrap();
+    assert_eq!(
+      encode_hex(&signature.to_bytes()),
+      "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406"
+    );
+    for rules in [Verification::Rfc8032, Verification::Strict, Verification::Zip215] {
+      assert_eq!(key.public_key.verify_ph(b"abc", b"", &signature, rules), Ok(()));
+      assert_eq!(key.public_key.verify_ph(b"abd", b"", &signature, rules), Err(Ed25519Error::InvalidSignature));
+      assert_eq!(key.public_key.verify_ph(b"abc", b"x", &signature, rules), Err(Ed25519Error::InvalidSignature));
+      assert_eq!(key.public_key.verify(b"abc", &signature, rules), Err(Ed25519Error::InvalidSignature));
+    }
+  }
+
+  #[test]
+  fn small_order_points_are_strict_only() {
+    // y = 1 is the identity, y = 0 a point of order 4; with S = 0,
+    // [S]B - R - [k]A has small order, so the cofactored equation holds
+    let (mut identity, order_4) = ([0u8; 32], [0u8; 32]);
+    identity[0] = 1;
+    assert!(EdwardsPoint::decompress(&identity).unwrap().is_identity());
+    assert!(EdwardsPoint::decompress(&order_4).unwrap().is_small_order());
+
+    for (a, r) in [(identity, order_4), (order_4, identity), (order_4, order_4)] {
+      let key = Ed25519PublicKey::from_bytes(&a).unwrap();
+      let signature = Ed25519Signature { r, s: [0; 32] };
+      for msg in [&b""[..], b"msg"] {
+        assert_eq!(key.verify(msg, &signature, Verification::Rfc8032), Ok(()));
+        assert_eq!(key.verify(msg, &signature, Verification::Zip215), Ok(()));
+        assert_eq!(key.verify(msg, &signature, Verification::Strict), Err(Ed25519Error::InvalidSignature));
+      }
+    }
+  }
+
+  #[test]
+  fn non_canonical_encoding_is_zip215_only() {
+    // y = p + 1 is a second encoding of y = 1, the identity, accepted by ZIP-215 only
+    let identity = int_to_le_bytes32(&(P.clone() + 1u8));
+    assert!(EdwardsPoint::decompress(&identity).is_none());
+    assert!(EdwardsPoint::decompress_zip215(&identity).unwrap().is_identity());
+    assert!(!EdwardsPoint::is_canonical_encoding(&identity));
+    assert!(Ed25519PublicKey::from_bytes(&identity).is_ok());
+  }
+}
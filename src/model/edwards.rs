@@ -0,0 +1,201 @@
+//! edwards25519, the twisted Edwards curve -x^2 + y^2 = 1 + d x^2 y^2 over F_p, p = 2^255 - 19
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.1
+//!
+//! Points are kept in extended coordinates (X : Y : Z : T), x = X/Z, y = Y/Z, xy = T/Z.
+//! The addition law is complete, so doubling and the identity need no special cases.
+
+use num_bigint::BigInt;
+use num_traits::{Euclid, One, Zero};
+use num_integer::Integer;
+use once_cell::sync::Lazy;
+
+use super::{
+  field_elements::{Fe, Field, FieldOperation, PrimeField},
+  helper::{le_bytes_to_int, int_to_le_bytes32},
+};
+
+/// p = 2^255 - 19
+pub static P: Lazy<BigInt> = Lazy::new(|| (BigInt::one() << 255) - 19);
+
+/// F_p, p = 2^255 - 19, the field of edwards25519 and curve25519
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fp25519;
+
+impl Field for Fp25519 {
+  fn field() -> &'static PrimeField {
+    static FIELD: Lazy<PrimeField> = Lazy::new(|| PrimeField::new("2^255 - 19", P.clone()));
+    &FIELD
+  }
+}
+
+type Fe25519 = Fe<Fp25519>;
+/// order of the prime-order subgroup, L = 2^252 + 27742317777372353535851937790883648493
+pub static L: Lazy<BigInt> = Lazy::new(|| {
+  (BigInt::one() << 252) + "27742317777372353535851937790883648493".parse::<BigInt>().expect("decimal")
+});
+/// d = -121665/121666
+static D: Lazy<Fe25519> = Lazy::new(|| fe(-121665).truediv(&fe(121666)));
+/// 2d, as the addition formula uses it
+static D2: Lazy<Fe25519> = Lazy::new(|| D.add(&D));
+/// 2^((p-1)/4), a square root of -1
+static SQRT_M1: Lazy<Fe25519> = Lazy::new(|| fe(2).pow((P.clone() - 1u8) / 4u8));
+/// B = (x, 4/5) with x even
+pub static BASE: Lazy<EdwardsPoint> = Lazy::new(|| {
+  let mut y = [0x66u8; 32];
+  y[0] = 0x58;
+  EdwardsPoint::decompress(&y).expect("base point")
+});
+
+fn fe<T: Into<BigInt>>(n: T) -> Fe25519 {
+  Fe::from_int(&n.into())
+}
+
+#[derive(Debug, Clone)]
+pub struct EdwardsPoint {
+  x: Fe25519,
+  y: Fe25519,
+  z: Fe25519,
+  t: Fe25519,
+}
+
+/// equal as projective points: X1/Z1 == X2/Z2 and Y1/Z1 == Y2/Z2
+impl PartialEq for EdwardsPoint {
+  fn eq(&self, other: &Self) -> bool {
+    self.x.mul(&other.z) == other.x.mul(&self.z) && self.y.mul(&other.z) == other.y.mul(&self.z)
+  }
+}
+
+impl Eq for EdwardsPoint {}
+
+impl EdwardsPoint {
+  /// the neutral element (0, 1)
+  pub fn identity() -> Self {
+    Self { x: fe(0), y: fe(1), z: fe(1), t: fe(0) }
+  }
+
+  pub fn is_identity(&self) -> bool {
+    *self == Self::identity()
+  }
+
+  /// RFC 8032 5.1.4, add-2008-hwcd-3
+  pub fn add(&self, other: &Self) -> Self {
+    let a = self.y.sub(&self.x).mul(&other.y.sub(&other.x));
+    let b = self.y.add(&self.x).mul(&other.y.add(&other.x));
+    let c = self.t.mul(&D2).mul(&other.t);
+    let d = self.z.add(&self.z).mul(&other.z);
+    let (e, f, g, h) = (b.sub(&a), d.sub(&c), d.add(&c), b.add(&a));
+    Self { x: e.mul(&f), y: g.mul(&h), t: e.mul(&h), z: f.mul(&g) }
+  }
+
+  /// RFC 8032 5.1.4, dbl-2008-hwcd
+  pub fn double(&self) -> Self {
+    let a = self.x.mul(&self.x);
+    let b = self.y.mul(&self.y);
+    let zz = self.z.mul(&self.z);
+    let c = zz.add(&zz);
+    let h = a.add(&b);
+    let xy = self.x.add(&self.y);
+    let e = h.sub(&xy.mul(&xy));
+    let g = a.sub(&b);
+    let f = c.add(&g);
+    Self { x: e.mul(&f), y: g.mul(&h), t: e.mul(&h), z: f.mul(&g) }
+  }
+
+  pub fn negate(&self) -> Self {
+    Self { x: self.x.neg(), y: self.y.clone(), z: self.z.clone(), t: self.t.neg() }
+  }
+
+  /// Double-and-add algorithm, `coef` taken mod 8L (the order of the whole group)
+  pub fn rmul(&self, coef: BigInt) -> Self {
+    let mut c = Euclid::rem_euclid(&coef, &(L.clone() * 8u8));
+    let mut result = Self::identity();
+    let mut base = self.clone();
+
+    while c > BigInt::zero() {
+      if c.is_odd() {
+        result = result.add(&base);
+      }
+      base = base.double();
+      c >>= 1;
+    }
+    result
+  }
+
+  /// [8]P
+  pub fn mul_by_cofactor(&self) -> Self {
+    self.double().double().double()
+  }
+
+  /// in the 8-torsion subgroup
+  pub fn is_small_order(&self) -> bool {
+    self.mul_by_cofactor().is_identity()
+  }
+
+  /// in the prime-order subgroup generated by `BASE`
+  pub fn is_torsion_free(&self) -> bool {
+    self.rmul(L.clone()).is_identity()
+  }
+
+  /// 32 bytes: y little-endian, the low bit of x in the top bit
+  pub fn compress(&self) -> [u8; 32] {
+    let z_inv = self.z.inverse().expect("Z != 0");
+    let (x, y) = (self.x.mul(&z_inv).get_num(), self.y.mul(&z_inv).get_num());
+    let mut out = int_to_le_bytes32(&y);
+    if x.is_odd() {
+      out[31] |= 0x80;
+    }
+    out
+  }
+
+  /// RFC 8032 5.1.3: rejects y >= p and the encoding of x = 0 with the sign bit set
+  pub fn decompress(bytes: &[u8; 32]) -> Option<Self> {
+    Self::decode(bytes, true)
+  }
+
+  /// ZIP-215 decoding: y is reduced mod p and x = 0 may carry either sign bit,
+  /// so every point has a few more encodings than `compress` produces
+  pub fn decompress_zip215(bytes: &[u8; 32]) -> Option<Self> {
+    Self::decode(bytes, false)
+  }
+
+  /// whether `decompress` accepts the bytes, i.e. they are the one `compress` output for their point
+  pub fn is_canonical_encoding(bytes: &[u8; 32]) -> bool {
+    Self::decompress(bytes).is_some()
+  }
+
+  fn decode(bytes: &[u8; 32], canonical: bool) -> Option<Self> {
+    let x_odd = bytes[31] & 0x80 != 0;
+    let mut y_bytes = *bytes;
+    y_bytes[31] &= 0x7f;
+    let y = le_bytes_to_int(&y_bytes);
+    if canonical && y >= *P {
+      return None
+    }
+    let y = fe(y);
+
+    // x^2 = (y^2 - 1) / (d y^2 + 1), root candidate (u/v)^((p+3)/8) = u v^3 (u v^7)^((p-5)/8)
+    let y2 = y.mul(&y);
+    let u = y2.sub(&fe(1));
+    let v = D.mul(&y2).add(&fe(1));
+    let v3 = v.mul(&v).mul(&v);
+    let v7 = v3.mul(&v3).mul(&v);
+    let x = u.mul(&v3).mul(&u.mul(&v7).pow((P.clone() - 5u8) / 8u8));
+    let vx2 = v.mul(&x).mul(&x);
+    let mut x = if vx2 == u {
+      x
+    } else if vx2 == u.neg() {
+      x.mul(&SQRT_M1)
+    } else {
+      return None
+    };
+
+    if x.is_zero() && x_odd && canonical {
+      return None
+    }
+    if x.get_num().is_odd() != x_odd {
+      x = x.neg();
+    }
+    let t = x.mul(&y);
+    Some(Self { x, y, z: fe(1), t })
+  }
+}
@@ -0,0 +1,519 @@
+//! Tower extensions of F_p for pairings on BLS12 curves
+//!
+//! * Fp2 = Fp[u] / (u^2 + 1)
+//! * Fp6 = Fp2[v] / (v^3 - ξ), ξ = u + 1
+//! * Fp12 = Fp6[w] / (w^2 - v)
+//!
+//! over the BLS12-381 base field, where p ≡ 3 (mod 4), so that -1 is not a square, and
+//! ξ is neither a square nor a cube in Fp2. The Frobenius coefficients are computed once.
+
+use std::ops;
+
+use num_bigint::BigInt;
+use num_traits::One;
+use once_cell::sync::Lazy;
+
+use super::{
+  curve::{Bls12381, Curve},
+  field_elements::{Fe, FieldOperation},
+  point::CurveField,
+};
+
+/// the base field F_p of BLS12-381
+pub type Fp = Fe<Bls12381>;
+
+/// ξ^((p-1)/3), the Frobenius coefficient of v
+static FROBENIUS_V: Lazy<Fp2> = Lazy::new(|| frobenius_coefficient(3));
+/// ξ^(2(p-1)/3), of v^2
+static FROBENIUS_V2: Lazy<Fp2> = Lazy::new(|| FROBENIUS_V.square());
+/// ξ^((p-1)/6), of w
+static FROBENIUS_W: Lazy<Fp2> = Lazy::new(|| frobenius_coefficient(6));
+
+/// c0 + c1 u
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fp2 {
+  pub c0: Fp,
+  pub c1: Fp,
+}
+
+impl Fp2 {
+  pub fn new(c0: Fp, c1: Fp) -> Self {
+    Self { c0, c1 }
+  }
+
+  pub fn zero() -> Self {
+    Self::new(Fp::zero(), Fp::zero())
+  }
+
+  pub fn one() -> Self {
+    Self::new(Fp::one(), Fp::zero())
+  }
+
+  /// the embedding of F_p
+  pub fn from_fp(c0: &Fp) -> Self {
+    Self::new(c0.clone(), Fp::zero())
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.c0.is_zero() && self.c1.is_zero()
+  }
+
+  pub fn add(&self, other: &Self) -> Self {
+    Self::new(&self.c0 + &other.c0, &self.c1 + &other.c1)
+  }
+
+  pub fn sub(&self, other: &Self) -> Self {
+    Self::new(&self.c0 - &other.c0, &self.c1 - &other.c1)
+  }
+
+  pub fn neg(&self) -> Self {
+    Self::new(-&self.c0, -&self.c1)
+  }
+
+  pub fn double(&self) -> Self {
+    self.add(self)
+  }
+
+  /// Karatsuba: (a0 + a1 u)(b0 + b1 u) = a0 b0 - a1 b1 + ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) u
+  pub fn mul(&self, other: &Self) -> Self {
+    let aa = &self.c0 * &other.c0;
+    let bb = &self.c1 * &other.c1;
+    let cross = &(&self.c0 + &self.c1) * &(&other.c0 + &other.c1);
+    Self::new(&aa - &bb, &(&cross - &aa) - &bb)
+  }
+
+  pub fn square(&self) -> Self {
+    self.mul(self)
+  }
+
+  pub fn mul_by_fp(&self, k: &Fp) -> Self {
+    Self::new(&self.c0 * k, &self.c1 * k)
+  }
+
+  /// times ξ = u + 1: (a0 - a1) + (a0 + a1) u
+  pub fn mul_by_nonresidue(&self) -> Self {
+    Self::new(&self.c0 - &self.c1, &self.c0 + &self.c1)
+  }
+
+  /// a0 - a1 u, which is also the Frobenius map x -> x^p
+  pub fn conjugate(&self) -> Self {
+    Self::new(self.c0.clone(), -&self.c1)
+  }
+
+  pub fn frobenius(&self) -> Self {
+    self.conjugate()
+  }
+
+  /// a0^2 + a1^2, in F_p
+  pub fn norm(&self) -> Fp {
+    &(&self.c0 * &self.c0) + &(&self.c1 * &self.c1)
+  }
+
+  /// conjugate / norm, `None` for zero
+  pub fn inverse(&self) -> Option<Self> {
+    let inv = self.norm().inverse()?;
+    Some(self.conjugate().mul_by_fp(&inv))
+  }
+
+  pub fn pow(&self, exp: &BigInt) -> Self {
+    let mut result = Self::one();
+    for i in (0..exp.bits()).rev() {
+      result = result.square();
+      if exp.bit(i) {
+        result = result.mul(self);
+      }
+    }
+    result
+  }
+
+  /// a square root for p ≡ 3 (mod 4), `None` if there is none
+  /// (Algorithm 9 of https://eprint.iacr.org/2012/685)
+  pub fn sqrt(&self) -> Option<Self> {
+    if self.is_zero() {
+      return Some(self.clone())
+    }
+    let p = Bls12381::p();
+    let a1 = self.pow(&((p - BigInt::from(3u8)) >> 2));
+    let alpha = a1.mul(&a1).mul(self);
+    let x0 = a1.mul(self);
+    let root = if alpha == Self::one().neg() {
+      // times u
+      Self::new(-&x0.c1, x0.c0.clone())
+    } else {
+      Self::one().add(&alpha).pow(&((p - BigInt::one()) >> 1)).mul(&x0)
+    };
+    if root.square() == *self { Some(root) } else { None }
+  }
+
+  /// sgn0 of RFC 9380 section 4.1: the parity of c0, or of c1 when c0 is zero
+  pub fn sgn0(&self) -> bool {
+    let sign_0 = self.c0.get_num().bit(0);
+    let zero_0 = self.c0.is_zero();
+    let sign_1 = self.c1.get_num().bit(0);
+    sign_0 || (zero_0 && sign_1)
+  }
+}
+
+/// the coordinates of the BLS12-381 twist
+impl CurveField for Fp2 {
+  fn zero() -> Self {
+    Fp2::zero()
+  }
+
+  fn one() -> Self {
+    Fp2::one()
+  }
+
+  fn is_zero(&self) -> bool {
+    Fp2::is_zero(self)
+  }
+
+  fn add(&self, other: &Self) -> Self {
+    Fp2::add(self, other)
+  }
+
+  fn sub(&self, other: &Self) -> Self {
+    Fp2::sub(self, other)
+  }
+
+  fn mul(&self, other: &Self) -> Self {
+    Fp2::mul(self, other)
+  }
+
+  fn neg(&self) -> Self {
+    Fp2::neg(self)
+  }
+
+  fn inverse(&self) -> Option<Self> {
+    Fp2::inverse(self)
+  }
+
+  fn sqrt(&self) -> Option<Self> {
+    Fp2::sqrt(self)
+  }
+
+  fn sgn0(&self) -> bool {
+    Fp2::sgn0(self)
+  }
+}
+
+/// c0 + c1 v + c2 v^2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fp6 {
+  pub c0: Fp2,
+  pub c1: Fp2,
+  pub c2: Fp2,
+}
+
+impl Fp6 {
+  pub fn new(c0: Fp2, c1: Fp2, c2: Fp2) -> Self {
+    Self { c0, c1, c2 }
+  }
+
+  pub fn zero() -> Self {
+    Self::new(Fp2::zero(), Fp2::zero(), Fp2::zero())
+  }
+
+  pub fn one() -> Self {
+    Self::new(Fp2::one(), Fp2::zero(), Fp2::zero())
+  }
+
+  pub fn from_fp2(c0: &Fp2) -> Self {
+    Self::new(c0.clone(), Fp2::zero(), Fp2::zero())
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.c0.is_zero() && self.c1.is_zero() && self.c2.is_zero()
+  }
+
+  pub fn add(&self, other: &Self) -> Self {
+    Self::new(self.c0.add(&other.c0), self.c1.add(&other.c1), self.c2.add(&other.c2))
+  }
+
+  pub fn sub(&self, other: &Self) -> Self {
+    Self::new(self.c0.sub(&other.c0), self.c1.sub(&other.c1), self.c2.sub(&other.c2))
+  }
+
+  pub fn neg(&self) -> Self {
+    Self::new(self.c0.neg(), self.c1.neg(), self.c2.neg())
+  }
+
+  pub fn mul(&self, other: &Self) -> Self {
+    let (a0, a1, a2) = (&self.c0, &self.c1, &self.c2);
+    let (b0, b1, b2) = (&other.c0, &other.c1, &other.c2);
+    let c0 = a0.mul(b0).add(&a1.mul(b2).add(&a2.mul(b1)).mul_by_nonresidue());
+    let c1 = a0.mul(b1).add(&a1.mul(b0)).add(&a2.mul(b2).mul_by_nonresidue());
+    let c2 = a0.mul(b2).add(&a1.mul(b1)).add(&a2.mul(b0));
+    Self::new(c0, c1, c2)
+  }
+
+  pub fn square(&self) -> Self {
+    self.mul(self)
+  }
+
+  pub fn mul_by_fp2(&self, k: &Fp2) -> Self {
+    Self::new(self.c0.mul(k), self.c1.mul(k), self.c2.mul(k))
+  }
+
+  /// times v: (ξ c2, c0, c1)
+  pub fn mul_by_nonresidue(&self) -> Self {
+    Self::new(self.c2.mul_by_nonresidue(), self.c0.clone(), self.c1.clone())
+  }
+
+  pub fn inverse(&self) -> Option<Self> {
+    let (a0, a1, a2) = (&self.c0, &self.c1, &self.c2);
+    let t0 = a0.square().sub(&a1.mul(a2).mul_by_nonresidue());
+    let t1 = a2.square().mul_by_nonresidue().sub(&a0.mul(a1));
+    let t2 = a1.square().sub(&a0.mul(a2));
+    let den = a0.mul(&t0).add(&a2.mul(&t1).add(&a1.mul(&t2)).mul_by_nonresidue());
+    let inv = den.inverse()?;
+    Some(Self::new(t0.mul(&inv), t1.mul(&inv), t2.mul(&inv)))
+  }
+
+  /// x -> x^p: v^p = ξ^((p-1)/3) v
+  pub fn frobenius(&self) -> Self {
+    Self::new(
+      self.c0.frobenius(),
+      self.c1.frobenius().mul(&FROBENIUS_V),
+      self.c2.frobenius().mul(&FROBENIUS_V2),
+    )
+  }
+}
+
+/// ξ^((p-1)/k)
+fn frobenius_coefficient(k: u8) -> Fp2 {
+  Fp2::one().mul_by_nonresidue().pow(&((Bls12381::p() - BigInt::one()) / k))
+}
+
+/// c0 + c1 w
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fp12 {
+  pub c0: Fp6,
+  pub c1: Fp6,
+}
+
+impl Fp12 {
+  pub fn new(c0: Fp6, c1: Fp6) -> Self {
+    Self { c0, c1 }
+  }
+
+  pub fn zero() -> Self {
+    Self::new(Fp6::zero(), Fp6::zero())
+  }
+
+  pub fn one() -> Self {
+    Self::new(Fp6::one(), Fp6::zero())
+  }
+
+  pub fn from_fp2(c0: &Fp2) -> Self {
+    Self::new(Fp6::from_fp2(c0), Fp6::zero())
+  }
+
+  pub fn from_fp(c0: &Fp) -> Self {
+    Self::from_fp2(&Fp2::from_fp(c0))
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.c0.is_zero() && self.c1.is_zero()
+  }
+
+  pub fn is_one(&self) -> bool {
+    *self == Self::one()
+  }
+
+  pub fn add(&self, other: &Self) -> Self {
+    Self::new(self.c0.add(&other.c0), self.c1.add(&other.c1))
+  }
+
+  pub fn sub(&self, other: &Self) -> Self {
+    Self::new(self.c0.sub(&other.c0), self.c1.sub(&other.c1))
+  }
+
+  pub fn neg(&self) -> Self {
+    Self::new(self.c0.neg(), self.c1.neg())
+  }
+
+  /// Karatsuba over Fp6: a0 b0 + a1 b1 v + ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) w
+  pub fn mul(&self, other: &Self) -> Self {
+    let aa = self.c0.mul(&other.c0);
+    let bb = self.c1.mul(&other.c1);
+    let cross = self.c0.add(&self.c1).mul(&other.c0.add(&other.c1));
+    Self::new(aa.add(&bb.mul_by_nonresidue()), cross.sub(&aa).sub(&bb))
+  }
+
+  pub fn square(&self) -> Self {
+    self.mul(self)
+  }
+
+  /// a0 - a1 w, which is x -> x^(p^6)
+  pub fn conjugate(&self) -> Self {
+    Self::new(self.c0.clone(), self.c1.neg())
+  }
+
+  /// conjugate / (a0^2 - a1^2 v)
+  pub fn inverse(&self) -> Option<Self> {
+    let den = self.c0.square().sub(&self.c1.square().mul_by_nonresidue());
+    let inv = den.inverse()?;
+    Some(Self::new(self.c0.mul(&inv), self.c1.neg().mul(&inv)))
+  }
+
+  pub fn pow(&self, exp: &BigInt) -> Self {
+    let mut result = Self::one();
+    for i in (0..exp.bits()).rev() {
+      result = result.square();
+      if exp.bit(i) {
+        result = result.mul(self);
+      }
+    }
+    result
+  }
+
+  /// x -> x^p: w^p = ξ^((p-1)/6) w
+  pub fn frobenius(&self) -> Self {
+    Self::new(self.c0.frobenius(), self.c1.frobenius().mul_by_fp2(&FROBENIUS_W))
+  }
+}
+
+macro_rules! impl_ops {
+  ($t:ty) => {
+    impl ops::Add for &$t {
+      type Output = $t;
+
+      fn add(self, other: Self) -> $t {
+        <$t>::add(self, other)
+      }
+    }
+
+    impl ops::Sub for &$t {
+      type Output = $t;
+
+      fn sub(self, other: Self) -> $t {
+        <$t>::sub(self, other)
+      }
+    }
+
+    impl ops::Mul for &$t {
+      type Output = $t;
+
+      fn mul(self, other: Self) -> $t {
+        <$t>::mul(self, other)
+      }
+    }
+
+    impl ops::Neg for &$t {
+      type Output = $t;
+
+      fn neg(self) -> $t {
+        <$t>::neg(self)
+      }
+    }
+  };
+}
+
+impl_ops!(Fp2);
+impl_ops!(Fp6);
+impl_ops!(Fp12);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// deterministic, full width field elements
+  fn fp(seed: u32) -> Fp {
+    Fp::from_int(&(BigInt::from(seed) + 0x9e37_79b9_u32).pow(47))
+  }
+
+  fn fp2(seed: u32) -> Fp2 {
+    Fp2::new(fp(seed), fp(seed + 1000))
+  }
+
+  fn fp6(seed: u32) -> Fp6 {
+    Fp6::new(fp2(seed), fp2(seed + 2000), fp2(seed + 4000))
+  }
+
+  fn fp12(seed: u32) -> Fp12 {
+    Fp12::new(fp6(seed), fp6(seed + 8000))
+  }
+
+  fn fp6_pow(x: &Fp6, exp: &BigInt) -> Fp6 {
+    (0..exp.bits()).rev().fold(Fp6::one(), |acc, i| {
+      let acc = acc.square();
+      if exp.bit(i) { acc.mul(x) } else { acc }
+    })
+  }
+
+  #[test]
+  fn inverses() {
+    assert_eq!(Fp2::zero().inverse(), None);
+    assert_eq!(Fp6::zero().inverse(), None);
+    assert_eq!(Fp12::zero().inverse(), None);
+    for seed in 1..4 {
+      let (a, b, c) = (fp2(seed), fp6(seed), fp12(seed));
+      assert_eq!(a.mul(&a.inverse().unwrap()), Fp2::one());
+      assert_eq!(b.mul(&b.inverse().unwrap()), Fp6::one());
+      assert_eq!(c.mul(&c.inverse().unwrap()), Fp12::one());
+      assert_eq!(c.inverse().unwrap().inverse().unwrap(), c);
+    }
+    // elements with zero components take other paths through the formulas
+    let u = Fp2::new(Fp::zero(), Fp::one());
+    assert_eq!(u.mul(&u.inverse().unwrap()), Fp2::one());
+    let v = Fp6::new(Fp2::zero(), Fp2::one(), Fp2::zero());
+    assert_eq!(v.mul(&v.inverse().unwrap()), Fp6::one());
+    let w = Fp12::new(Fp6::zero(), Fp6::one());
+    assert_eq!(w.mul(&w.inverse().unwrap()), Fp12::one());
+  }
+
+  #[test]
+  fn frobenius_is_exponentiation_by_p() {
+    let p = Bls12381::p();
+    let (a, b, c) = (fp2(7), fp6(7), fp12(7));
+    assert_eq!(a.frobenius(), a.pow(p));
+    assert_eq!(b.frobenius(), fp6_pow(&b, p));
+    assert_eq!(c.frobenius(), c.pow(p));
+
+    // the Frobenius map has order 2, 6 and 12 in each layer
+    assert_eq!(a.frobenius().frobenius(), a);
+    assert_ne!(b.frobenius().frobenius(), b);
+    assert_eq!((0..6).fold(b.clone(), |x, _| x.frobenius()), b);
+    let c6 = (0..6).fold(c.clone(), |x, _| x.frobenius());
+    assert_eq!(c6, c.conjugate());
+    assert_eq!((0..6).fold(c6, |x, _| x.frobenius()), c);
+
+    // F_p is fixed, and the map is a homomorphism
+    let k = Fp12::from_fp(&fp(3));
+    assert_eq!(k.frobenius(), k);
+    assert_eq!(c.mul(&fp12(8)).frobenius(), c.frobenius().mul(&fp12(8).frobenius()));
+  }
+
+  #[test]
+  fn nonresidues() {
+    let p = Bls12381::p();
+    // Fp2: u^2 = -1 and ξ = u + 1, neither a square nor a cube
+    let u = Fp2::new(Fp::zero(), Fp::one());
+    assert_eq!(u.square(), Fp2::one().neg());
+    let xi = Fp2::new(Fp::one(), Fp::one());
+    assert_eq!(Fp2::one().mul_by_nonresidue(), xi);
+    assert_eq!(xi.sqrt(), None);
+    let p2_minus_1 = p * p - BigInt::one();
+    assert_ne!(xi.pow(&(&p2_minus_1 / 3u8)), Fp2::one());
+    assert_eq!(xi.pow(&p2_minus_1), Fp2::one());
+
+    // Fp6: v^3 = ξ
+    let v = Fp6::new(Fp2::zero(), Fp2::one(), Fp2::zero());
+    assert_eq!(v.mul(&v).mul(&v), Fp6::from_fp2(&xi));
+    assert_eq!(Fp6::one().mul_by_nonresidue(), v);
+
+    // Fp12: w^2 = v
+    let w = Fp12::new(Fp6::zero(), Fp6::one());
+    assert_eq!(w.square(), Fp12::new(v.clone(), Fp6::zero()));
+
+    for seed in 1..4 {
+      let (a, b) = (fp2(seed), fp6(seed));
+      assert_eq!(a.mul_by_nonresidue(), a.mul(&xi));
+      assert_eq!(b.mul_by_nonresidue(), b.mul(&v));
+      // times w moves c1 v into c0 and c0 into c1
+      let c = fp12(seed);
+      assert_eq!(c.mul(&w), Fp12::new(c.c1.mul_by_nonresidue(), c.c0.clone()));
+    }
+  }
+}
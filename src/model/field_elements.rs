@@ -1,6 +1,7 @@
-use num_bigint::BigInt;
+use std::{fmt, marker::PhantomData, ops};
+
+use num_bigint::{BigInt, RandBigInt};
 use num_traits::{One, Zero, Euclid};
-use num_integer::Integer;
 
 pub trait FieldOperation {
   fn get_num(&self) -> BigInt;
@@ -25,101 +26,477 @@ pub trait FieldOperation {
   fn rmul(&self, coef: Self) -> Self;
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub struct FieldElement {
-  /// inner value
-  num: BigInt, 
-  /// finite field
-  prime: BigInt 
+/// Miller–Rabin with the first twelve prime bases, deterministic below 3.3 * 10^24,
+/// and 20 random bases more for larger n
+pub fn is_prime(n: &BigInt) -> bool {
+  const BASES: [u8; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+  if n < &BigInt::from(2u8) {
+    return false
+  }
+  for b in BASES {
+    if n == &BigInt::from(b) {
+      return true
+    }
+    if (n % b).is_zero() {
+      return false
+    }
+  }
+
+  let n_minus_1 = n - BigInt::one();
+  let s = n_minus_1.trailing_zeros().expect("n > 2");
+  let d = &n_minus_1 >> s;
+  let is_witness = |a: &BigInt| {
+    let mut x = a.modpow(&d, n);
+    if x.is_one() || x == n_minus_1 {
+      return false
+    }
+    for _ in 1..s {
+      x = (&x * &x) % n;
+      if x == n_minus_1 {
+        return false
+      }
+    }
+    true
+  };
+
+  if BASES.iter().any(|b| is_witness(&BigInt::from(*b))) {
+    return false
+  }
+  if n.bits() > 81 {
+    let mut rng = rand::thread_rng();
+    let two = BigInt::from(2u8);
+    return !(0..20).any(|_| is_witness(&rng.gen_bigint_range(&two, &n_minus_1)))
+  }
+  true
 }
 
-impl FieldOperation for FieldElement {
-  fn get_num(&self) -> BigInt {
-    self.num.clone()
+/// how square roots are taken in F_p, chosen from p when the field is built
+#[derive(Debug)]
+enum SqrtMethod {
+  /// p = 2, where every element is its own root
+  Trivial,
+  /// p ≡ 3 (mod 4): v^((p+1)/4), `exp` = (p+1)/4
+  ThreeModFour { exp: BigInt },
+  /// p ≡ 5 (mod 8), Atkin's method, `exp` = (p-5)/8
+  Atkin { exp: BigInt },
+  /// Tonelli–Shanks with p - 1 = q 2^s, q odd, and c = z^q for a non-square z
+  TonelliShanks { s: u64, q: BigInt, c: BigInt },
 }
-fn get_prime(&self) -> BigInt {
-    self.prime.clone()
+
+/// the prime field F_p; building one checks that p is prime and prepares its square roots,
+/// and fields are kept in `Lazy` statics, so this is done once per field
+#[derive(Debug)]
+pub struct PrimeField {
+  pub name: &'static str,
+  pub p: BigInt,
+  sqrt: SqrtMethod,
 }
-fn set_num(&mut self, num: BigInt) {
-  self.num = num;
+
+impl PrimeField {
+  /// panics unless `p` is prime
+  pub fn new(name: &'static str, p: BigInt) -> Self {
+    if !is_prime(&p) {
+      panic!("{} is not a prime, Z/{}Z is not a field", p, p)
+    }
+    let sqrt = if p == BigInt::from(2u8) {
+      SqrtMethod::Trivial
+    } else if &p % 4u8 == BigInt::from(3u8) {
+      SqrtMethod::ThreeModFour { exp: (&p + BigInt::one()) >> 2 }
+    } else if &p % 8u8 == BigInt::from(5u8) {
+      SqrtMethod::Atkin { exp: (&p - BigInt::from(5u8)) >> 3 }
+    } else {
+      let p_minus_1 = &p - BigInt::one();
+      let s = p_minus_1.trailing_zeros().expect("p > 2");
+      let q = &p_minus_1 >> s;
+      // any non-square gives a generator of the 2-Sylow subgroup
+      let mut z = BigInt::from(2u8);
+      while z.modpow(&(&p_minus_1 >> 1), &p) != p_minus_1 {
+        z += 1u8;
+      }
+      let c = z.modpow(&q, &p);
+      SqrtMethod::TonelliShanks { s, q, c }
+    };
+    Self { name, p, sqrt }
+  }
+
+  /// length of an encoded element
+  pub fn bytes(&self) -> usize {
+    self.p.bits().div_ceil(8) as usize
+  }
+}
+
+/// a prime field as a type, so that its elements need not carry the modulus;
+/// every `Curve` is the `Field` of its coordinates
+pub trait Field: fmt::Debug + Clone + Copy + PartialEq + Eq + 'static {
+  fn field() -> &'static PrimeField;
 }
 
-fn new(num:BigInt, prime:BigInt) -> Self {
-  if num >= prime {
-    panic!("Num {} not in field range 0 to {}", num, prime)
+/// element of the prime field `F`
+#[derive(Eq, PartialEq, Clone)]
+pub struct Fe<F: Field> {
+  num: BigInt,
+  field: PhantomData<F>,
+}
+
+impl<F: Field> fmt::Debug for Fe<F> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Fe<{}>({})", F::field().name, FieldOperation::to_string(self))
   }
-  FieldElement {num,prime}
 }
 
-fn to_string(&self) -> String {
-  format!("FieldElement_{}({:0>64x})", self.prime, self.num)
+impl<F: Field> Fe<F> {
+  /// `num` reduced mod p
+  pub fn from_int(num: &BigInt) -> Self {
+    Self { num: Euclid::rem_euclid(num, &F::field().p), field: PhantomData }
+  }
+
+  pub fn zero() -> Self {
+    Self::from_int(&BigInt::zero())
+  }
+
+  pub fn one() -> Self {
+    Self::from_int(&BigInt::one())
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.num.is_zero()
+  }
+
+  pub fn add(&self, other: &Self) -> Self {
+    Self::from_int(&(&self.num + &other.num))
+  }
+
+  pub fn sub(&self, other: &Self) -> Self {
+    Self::from_int(&(&self.num - &other.num))
+  }
+
+  pub fn mul(&self, other: &Self) -> Self {
+    Self::from_int(&(&self.num * &other.num))
+  }
+
+  pub fn neg(&self) -> Self {
+    Self::zero().sub(self)
+  }
+
+  /// multiplicative inverse by the extended Euclidean algorithm, `None` for zero
+  pub fn inverse(&self) -> Option<Self> {
+    let p = &F::field().p;
+    let (mut r0, mut r1) = (p.clone(), self.num.clone());
+    let (mut t0, mut t1) = (BigInt::zero(), BigInt::one());
+    while !r1.is_zero() {
+      let q = &r0 / &r1;
+      (r0, r1) = (r1.clone(), &r0 - &q * &r1);
+      (t0, t1) = (t1.clone(), &t0 - &q * &t1);
+    }
+    // r0 = gcd(num, p), 1 unless num is 0
+    if !r0.is_one() {
+      return None
+    }
+    Some(Self::from_int(&t0))
+  }
+
+  /// Legendre symbol (num / p) by Euler's criterion: 1 for a nonzero square, -1 for a
+  /// non-square, 0 for zero
+  pub fn legendre(&self) -> i8 {
+    let p = &F::field().p;
+    if self.num.is_zero() {
+      return 0
+    }
+    if *p == BigInt::from(2u8) {
+      return 1
+    }
+    if self.num.modpow(&((p - BigInt::one()) >> 1), p).is_one() { 1 } else { -1 }
+  }
+
+  /// a square root, `None` if there is none; the other root is its negation
+  ///
+  /// * p ≡ 3 (mod 4): v^((p+1)/4)
+  /// * p ≡ 5 (mod 8): Atkin's method, b = (2v)^((p-5)/8), i = 2vb^2, root = vb(i - 1)
+  /// * otherwise Tonelli–Shanks
+  pub fn sqrt(&self) -> Option<Self> {
+    let field = F::field();
+    let p = &field.p;
+    if self.num.is_zero() {
+      return Some(self.clone())
+    }
+    let root = match &field.sqrt {
+      SqrtMethod::Trivial => return Some(self.clone()),
+      SqrtMethod::ThreeModFour { exp } => self.num.modpow(exp, p),
+      SqrtMethod::Atkin { exp } => {
+        let two_v: BigInt = (&self.num << 1u8) % p;
+        let b = two_v.modpow(exp, p);
+        let i = (&two_v * &b * &b) % p;
+        &self.num * &b * (i - BigInt::one())
+      }
+      SqrtMethod::TonelliShanks { s, q, c } => {
+        // the loop below only ends for a square
+        if self.legendre() != 1 {
+          return None
+        }
+        self.sqrt_tonelli_shanks(*s, q, c)
+      }
+    };
+    let root = Self::from_int(&root);
+    if root.mul(&root) == *self { Some(root) } else { None }
+  }
+
+  /// r with r^2 = `self`, a nonzero square
+  fn sqrt_tonelli_shanks(&self, s: u64, q: &BigInt, c: &BigInt) -> BigInt {
+    let p = &F::field().p;
+    let mut m = s;
+    let mut c = c.clone();
+    let mut t = self.num.modpow(q, p);
+    let mut r = self.num.modpow(&((q + BigInt::one()) >> 1), p);
+    while !t.is_one() {
+      // least i with t^(2^i) = 1
+      let mut i = 0;
+      let mut t2 = t.clone();
+      while !t2.is_one() {
+        t2 = (&t2 * &t2) % p;
+        i += 1;
+      }
+      let b = c.modpow(&(BigInt::one() << (m - i - 1)), p);
+      m = i;
+      c = (&b * &b) % p;
+      t = (&t * &c) % p;
+      r = (&r * &b) % p;
+    }
+    r
+  }
 }
 
-fn add(&self, other: &Self) -> Self {
-  if self.get_prime() != other.get_prime() {
-    panic!("Cannot add two numbers in different Fields")
+impl<F: Field> FieldOperation for Fe<F> {
+  fn get_num(&self) -> BigInt {
+    self.num.clone()
+  }
+
+  fn get_prime(&self) -> BigInt {
+    F::field().p.clone()
+  }
+
+  fn set_num(&mut self, num: BigInt) {
+    self.num = num;
+  }
+
+  /// `prime` must be the modulus of `F`, kept for the `FieldOperation` signature
+  fn new(num: BigInt, prime: BigInt) -> Self {
+    if prime != F::field().p {
+      panic!("{} is not the {} field modulus", prime, F::field().name)
+    }
+    if num < BigInt::zero() || num >= prime {
+      panic!("Num {} not in field range 0 to {}", num, prime)
+    }
+    Self { num, field: PhantomData }
+  }
+
+  /// fixed-width hex, two digits per byte of p
+  fn to_string(&self) -> String {
+    format!("{:0>width$x}", self.num, width = 2 * F::field().bytes())
   }
 
-  let result = Euclid::rem_euclid(&(self.get_num() + other.get_num()), &self.get_prime());
-  Self::new(result, self.get_prime().clone())
+  fn add(&self, other: &Self) -> Self {
+    Fe::add(self, other)
+  }
+
+  fn sub(&self, other: &Self) -> Self {
+    Fe::sub(self, other)
+  }
+
+  fn mul(&self, other: &Self) -> Self {
+    Fe::mul(self, other)
+  }
+
+  /// panics on division by zero, as integer division does
+  fn truediv(&self, other: &Self) -> Self {
+    self.mul(&other.inverse().expect("division by zero"))
+  }
+
+  fn pow(&self, exp: BigInt) -> Self {
+    let p = &F::field().p;
+    let n = Euclid::rem_euclid(&exp, &(p - BigInt::one()));
+    Self { num: Self::mod_exp(&self.num, &n, p), field: PhantomData }
+  }
+
+  fn mod_exp(base: &BigInt, exponent: &BigInt, modulus: &BigInt) -> BigInt {
+    base.modpow(exponent, modulus)
+  }
+
+  fn double(&self) -> Self {
+    self.add(self)
+  }
+
+  fn rmul(&self, coef: Self) -> Self {
+    self.mul(&coef)
+  }
 }
 
-fn sub(&self, other: &Self) -> Self {
-  if self.get_prime() != other.get_prime() {
-    panic!("Cannot sub two numbers in different Fields")
-  };
-  let result = Euclid::rem_euclid(&(&self.get_num() - &other.get_num()), &self.get_prime());
-  Self::new(result, self.get_prime().clone())
+impl<F: Field> ops::Add for Fe<F> {
+  type Output = Self;
+
+  fn add(self, other: Self) -> Self {
+    FieldOperation::add(&self, &other)
+  }
 }
 
-fn mul(&self, other: &Self) -> Self {
-  if self.get_prime() != other.get_prime() {
-    panic!("Cannot add two numbers in different Fields")
+impl<F: Field> ops::Add for &Fe<F> {
+  type Output = Fe<F>;
+
+  fn add(self, other: Self) -> Fe<F> {
+    FieldOperation::add(self, other)
   }
-  let result = Euclid::rem_euclid(&(&self.get_num() * &other.get_num()), &self.get_prime());
-  Self::new(result, self.get_prime().clone())
 }
 
-fn truediv(&self, other: &Self) -> Self {
-  if self.get_prime() != other.get_prime() {
-    panic!("Cannot add two numbers in different Fields")
+impl<F: Field> ops::Sub for Fe<F> {
+  type Output = Self;
+
+  fn sub(self, other: Self) -> Self {
+    FieldOperation::sub(&self, &other)
   }
-  let other_inverse = other.pow(&self.get_prime() - &BigInt::from(2u128));
-  let result = Euclid::rem_euclid(&(&self.get_num() * &other_inverse.get_num()), &self.get_prime());
+}
 
-  Self::new(result, self.get_prime().clone())
+impl<F: Field> ops::Sub for &Fe<F> {
+  type Output = Fe<F>;
+
+  fn sub(self, other: Self) -> Fe<F> {
+    FieldOperation::sub(self, other)
+  }
 }
 
-fn pow(&self, exp: BigInt) -> Self {
-  let n = Euclid::rem_euclid(&exp, &(&self.get_prime() - &BigInt::from(1u128)));
+impl<F: Field> ops::Mul for Fe<F> {
+  type Output = Self;
 
-  let result = Self::mod_exp(&self.get_num(), &n, &self.get_prime());
+  fn mul(self, other: Self) -> Self {
+    FieldOperation::mul(&self, &other)
+  }
+}
 
-  Self::new(result, self.get_prime().clone())
+impl<F: Field> ops::Mul for &Fe<F> {
+  type Output = Fe<F>;
+
+  fn mul(self, other: Self) -> Fe<F> {
+    FieldOperation::mul(self, other)
+  }
 }
 
-/// modular exponentiation using the square-and-multiply algorithm:
-fn mod_exp(base: &BigInt, exponent: &BigInt, modulus: &BigInt) -> BigInt {
-  let mut result = BigInt::one();
-  let mut base = base.clone();
-  let mut exponent = exponent.clone();
+/// panics on division by zero, as integer division does
+impl<F: Field> ops::Div for Fe<F> {
+  type Output = Self;
 
-  while exponent > BigInt::zero() {
-      if exponent.is_odd() {
-          result = Euclid::rem_euclid(&(&result * &base), modulus);
-      }
-      base = Euclid::rem_euclid(&(&base * &base), modulus);
-      exponent >>= 1;
+  fn div(self, other: Self) -> Self {
+    FieldOperation::truediv(&self, &other)
   }
-  result
 }
 
-fn double(&self) -> Self {
-  todo!()
+impl<F: Field> ops::Div for &Fe<F> {
+  type Output = Fe<F>;
+
+  fn div(self, other: Self) -> Fe<F> {
+    FieldOperation::truediv(self, other)
+  }
 }
 
-fn rmul(&self, coef: Self) -> Self {
-  todo!()
+impl<F: Field> ops::Neg for Fe<F> {
+  type Output = Self;
+
+  fn neg(self) -> Self {
+    Fe::neg(&self)
+  }
+}
+
+impl<F: Field> ops::Neg for &Fe<F> {
+  type Output = Fe<F>;
+
+  fn neg(self) -> Fe<F> {
+    Fe::neg(self)
+  }
 }
 
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use once_cell::sync::Lazy;
+
+  use crate::model::curve::Secp256k1;
+
+  macro_rules! small_field {
+    ($name:ident, $p:expr) => {
+      #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+      struct $name;
+
+      impl Field for $name {
+        fn field() -> &'static PrimeField {
+          static FIELD: Lazy<PrimeField> = Lazy::new(|| PrimeField::new(stringify!($name), BigInt::from($p)));
+          &FIELD
+        }
+      }
+    };
+  }
+
+  // one field for each square root method
+  small_field!(F2, 2u8);
+  small_field!(F1019, 1019u16);
+  small_field!(F1013, 1013u16);
+  small_field!(F1009, 1009u16);
+
+  /// every square has its roots found, every non-square has none
+  fn check_sqrt<F: Field>(p: u32) {
+    let mut squares = 0;
+    for n in 0..p {
+      let v = Fe::<F>::from_int(&BigInt::from(n));
+      match v.sqrt() {
+        Some(root) => {
+          assert_eq!(root.mul(&root), v);
+          assert_ne!(v.legendre(), -1);
+          squares += 1;
+        }
+        None => assert_eq!(v.legendre(), -1),
+      }
+    }
+    // zero and (p - 1) / 2 nonzero squares, both elements of F_2
+    assert_eq!(squares, if p == 2 { 2 } else { p.div_ceil(2) });
+  }
+
+  #[test]
+  fn sqrt_in_every_kind_of_field() {
+    check_sqrt::<F2>(2);
+    check_sqrt::<F1019>(1019);
+    check_sqrt::<F1013>(1013);
+    check_sqrt::<F1009>(1009);
+  }
+
+  #[test]
+  fn inverse_and_operators() {
+    let a = Fe::<F1009>::from_int(&BigInt::from(123));
+    let b = Fe::<F1009>::from_int(&BigInt::from(-45));
+    assert_eq!(a.inverse().unwrap().mul(&a), Fe::one());
+    assert_eq!(Fe::<F1009>::zero().inverse(), None);
+    assert_eq!(&(&a / &b) * &b, a);
+    assert_eq!(&a - &a, Fe::zero());
+    assert_eq!(-&b, Fe::from_int(&BigInt::from(45)));
+    assert_eq!(b.get_num(), BigInt::from(1009 - 45));
+  }
+
+  #[test]
+  fn secp256k1_sqrt() {
+    let two = Fe::<Secp256k1>::from_int(&BigInt::from(2u8));
+    let root = two.sqrt().unwrap();
+    assert_eq!(root.mul(&root), two);
+    // -1 is not a square when p ≡ 3 (mod 4)
+    assert_eq!(Fe::<Secp256k1>::one().neg().sqrt(), None);
+  }
+
+  #[test]
+  #[should_panic(expected = "is not a prime")]
+  fn composite_modulus_is_rejected() {
+    PrimeField::new("F1001", BigInt::from(1001u16));
+  }
+
+  #[test]
+  fn primality() {
+    assert!(is_prime(&BigInt::from(2u8)));
+    assert!(!is_prime(&BigInt::from(1u8)));
+    // a strong pseudoprime to the bases 2 and 3
+    assert!(!is_prime(&BigInt::from(1_373_653u32)));
+    assert!(is_prime(&((BigInt::one() << 127) - 1u8)));
+  }
+}
@@ -0,0 +1,668 @@
+//! FROST t-of-n threshold Schnorr signatures
+//! https://datatracker.ietf.org/doc/html/rfc9591
+//!
+//! H1, H3, H4 and H5 follow the FROST(secp256k1, SHA-256) ciphersuite. The
+//! challenge is the BIP340 one and the group commitment and group key are
+//! taken with even y, so `aggregate` outputs a BIP340 signature that
+//! `schnorr::verify` accepts.
+
+use std::collections::BTreeMap;
+
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+use super::{
+  constants::{Gs, N, PRIME},
+  field_elements::FieldOperation,
+  helper::{bytes_to_int, hash_to_field, int_to_bytes32, sha256, tagged_hash, SecretScalar},
+  private_key::PrivateKey,
+  schnorr::{self, xonly, SchnorrSignature},
+  s256_field::S256Field,
+  s256_point::S256Point,
+  shamir::{evaluate_polynomial, lagrange_coefficient, mod_n, random_scalar},
+};
+
+const CONTEXT_STRING: &[u8] = b"FROST-secp256k1-SHA256-v1";
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FrostError {
+  /// need 2 <= min_signers <= max_signers
+  InvalidThreshold,
+  /// identifiers are non-zero, and a participant gets no round 1 package under its own
+  InvalidIdentifier,
+  /// the share from this participant does not match its VSS commitment
+  InvalidSecretShare(u16),
+  /// the DKG proof of knowledge from this participant does not verify, or uses the point at infinity
+  InvalidProofOfKnowledge(u16),
+  /// the signature share from this participant does not verify
+  InvalidSignatureShare(u16),
+  /// this participant is missing from the commitments or the key package
+  UnknownParticipant(u16),
+  /// fewer than min_signers participants
+  NotEnoughSigners,
+  /// DKG round 2 needs one round 1 package from each of the other max_signers - 1 participants
+  WrongPackageCount,
+  /// a signer's commitment, the group commitment R or the group public key is the point at infinity
+  InvalidGroupCommitment,
+  /// every share verifies but their sum does not, the key packages disagree
+  InvalidSignature,
+}
+
+fn scalar_hash(msg: &[u8], tag: &[u8]) -> BigInt {
+  let mut dst = CONTEXT_STRING.to_vec();
+  dst.extend_from_slice(tag);
+  hash_to_field(msg, &dst, 1, &N, 48).expect("48 bytes are within expand_message_xmd's limit").remove(0)
+}
+
+/// H1: binding factors
+fn h1(msg: &[u8]) -> BigInt {
+  scalar_hash(msg, b"rho")
+}
+
+/// H3: nonces
+fn h3(msg: &[u8]) -> BigInt {
+  scalar_hash(msg, b"nonce")
+}
+
+/// H4: message digest
+fn h4(msg: &[u8]) -> [u8; 32] {
+  let mut data = CONTEXT_STRING.to_vec();
+  data.extend_from_slice(b"msg");
+  data.extend_from_slice(msg);
+  sha256(&data)
+}
+
+/// H5: commitment list digest
+fn h5(msg: &[u8]) -> [u8; 32] {
+  let mut data = CONTEXT_STRING.to_vec();
+  data.extend_from_slice(b"com");
+  data.extend_from_slice(msg);
+  sha256(&data)
+}
+
+/// HDKG: challenge of the DKG proof of knowledge
+fn h_dkg(msg: &[u8]) -> BigInt {
+  scalar_hash(msg, b"dkg")
+}
+
+/// Feldman VSS commitment: `[a_0 G, a_1 G, ..., a_{t-1} G]`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct VssCommitment(pub Vec<S256Point>);
+
+impl VssCommitment {
+  fn from_coefficients(coefficients: &[SecretScalar]) -> Self {
+    VssCommitment(coefficients.iter().map(|a| Gs.rmul(a.clone_int())).collect())
+  }
+
+  /// f(id) G computed from the commitment alone
+  pub fn evaluate(&self, identifier: u16) -> S256Point {
+    let x = BigInt::from(identifier);
+    let mut power = BigInt::one();
+    let mut terms = Vec::with_capacity(self.0.len());
+    for point in &self.0 {
+      terms.push((power.clone(), point.clone()));
+      power = mod_n(&(power * &x));
+    }
+    S256Point::multi_mul(&terms)
+  }
+
+  pub fn verify_share(&self, identifier: u16, share: &PrivateKey) -> bool {
+    self.evaluate(identifier) == share.point
+  }
+
+  /// a_0 G
+  pub fn group_public_key(&self) -> &S256Point {
+    &self.0[0]
+  }
+}
+
+/// the secret share a participant keeps after key generation
+#[derive(Debug, Clone)]
+pub struct KeyPackage {
+  pub identifier: u16,
+  pub signing_share: PrivateKey,
+  pub group_public_key: S256Point,
+  pub min_signers: u16,
+}
+
+/// public data every participant and the aggregator agree on after key generation
+#[derive(Debug, Clone)]
+pub struct PublicKeyPackage {
+  pub verifying_shares: BTreeMap<u16, S256Point>,
+  pub group_public_key: S256Point,
+}
+
+fn check_threshold(max_signers: u16, min_signers: u16) -> Result<(), FrostError> {
+  if min_signers < 2 || min_signers > max_signers {
+    return Err(FrostError::InvalidThreshold)
+  }
+  Ok(())
+}
+
+/// trusted dealer key generation: Shamir shares of `secret` for identifiers 1..=max_signers
+/// with the Feldman VSS commitment each participant checks its share against
+pub fn trusted_dealer_keygen(
+  secret: &PrivateKey,
+  max_signers: u16,
+  min_signers: u16,
+) -> Result<(Vec<KeyPackage>, PublicKeyPackage, VssCommitment), FrostError> {
+  check_threshold(max_signers, min_signers)?;
+  let mut coefficients = vec![secret.secret()];
+  coefficients.extend((1..min_signers).map(|_| SecretScalar::new(random_scalar())));
+  let commitment = VssCommitment::from_coefficients(&coefficients);
+
+  let key_packages: Vec<KeyPackage> = (1..=max_signers)
+    .map(|id| KeyPackage {
+      identifier: id,
+      signing_share: PrivateKey::new(evaluate_polynomial(&coefficients, id)),
+      group_public_key: commitment.group_public_key().clone(),
+      min_signers,
+    })
+    .collect();
+  let public_key_package = PublicKeyPackage {
+    verifying_shares: key_packages.iter()
+      .map(|k| (k.identifier, k.signing_share.point.clone()))
+      .collect(),
+    group_public_key: commitment.group_public_key().clone(),
+  };
+  Ok((key_packages, public_key_package, commitment))
+}
+
+/// DKG round 1 state; consumed by `dkg_part2`, the coefficients are wiped on drop
+#[derive(Debug)]
+pub struct DkgRound1Secret {
+  identifier: u16,
+  coefficients: Vec<SecretScalar>,
+  max_signers: u16,
+  min_signers: u16,
+}
+
+/// DKG round 1 broadcast: VSS commitment and a proof of knowledge of a_0
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DkgRound1Package {
+  pub commitment: VssCommitment,
+  pub proof_r: S256Point,
+  pub proof_mu: BigInt,
+}
+
+/// DKG round 2 state; used by `dkg_part3`, the own share is wiped on drop
+#[derive(Debug)]
+pub struct DkgRound2Secret {
+  identifier: u16,
+  own_share: SecretScalar,
+  commitments: BTreeMap<u16, VssCommitment>,
+  min_signers: u16,
+}
+
+/// c = HDKG(id || a_0 G || R)
+fn dkg_challenge(identifier: u16, verifying_key: &S256Point, r: &S256Point) -> BigInt {
+  let mut data = int_to_bytes32(&BigInt::from(identifier)).to_vec();
+  data.extend(verifying_key.sec(true));
+  data.extend(r.sec(true));
+  h_dkg(&data)
+}
+
+/// DKG round 1: random polynomial, its commitment and a Schnorr proof for a_0
+pub fn dkg_part1(
+  identifier: u16,
+  max_signers: u16,
+  min_signers: u16,
+) -> Result<(DkgRound1Secret, DkgRound1Package), FrostError> {
+  check_threshold(max_signers, min_signers)?;
+  if identifier == 0 {
+    return Err(FrostError::InvalidIdentifier)
+  }
+  let coefficients: Vec<SecretScalar> = (0..min_signers).map(|_| SecretScalar::new(random_scalar())).collect();
+  let commitment = VssCommitment::from_coefficients(&coefficients);
+
+  let k = SecretScalar::new(random_scalar());
+  let proof_r = Gs.rmul(k.clone_int());
+  let c = dkg_challenge(identifier, commitment.group_public_key(), &proof_r);
+  let proof_mu = mod_n(&(&*k + &*coefficients[0] * c));
+
+  let secret = DkgRound1Secret { identifier, coefficients, max_signers, min_signers };
+  Ok((secret, DkgRound1Package { commitment, proof_r, proof_mu }))
+}
+
+/// DKG round 2: check everyone's proof of knowledge and compute the share f_i(l)
+/// to send privately to each other participant l
+pub fn dkg_part2(
+  secret: DkgRound1Secret,
+  round1_packages: &BTreeMap<u16, DkgRound1Package>,
+) -> Result<(DkgRound2Secret, BTreeMap<u16, BigInt>), FrostError> {
+  if round1_packages.len() != secret.max_signers as usize - 1 {
+    return Err(FrostError::WrongPackageCount)
+  }
+  let mut shares = BTreeMap::new();
+  let mut commitments = BTreeMap::new();
+  for (id, package) in round1_packages {
+    if *id == 0 || *id == secret.identifier {
+      return Err(FrostError::InvalidIdentifier)
+    }
+    // a_0 G and R are hashed in their SEC encoding, which infinity does not have
+    if package.commitment.0.len() != secret.min_signers as usize
+      || package.commitment.group_public_key().is_infinity() || package.proof_r.is_infinity() {
+      return Err(FrostError::InvalidProofOfKnowledge(*id))
+    }
+    // mu G == R + c (a_0 G)
+    let c = dkg_challenge(*id, package.commitment.group_public_key(), &package.proof_r);
+    let expected = package.proof_r.add(&package.commitment.group_public_key().rmul(c));
+    if Gs.rmul(package.proof_mu.clone()) != expected {
+      return Err(FrostError::InvalidProofOfKnowledge(*id))
+    }
+    shares.insert(*id, evaluate_polynomial(&secret.coefficients, *id));
+    commitments.insert(*id, package.commitment.clone());
+  }
+  commitments.insert(secret.identifier, VssCommitment::from_coefficients(&secret.coefficients));
+
+  let round2_secret = DkgRound2Secret {
+    identifier: secret.identifier,
+    own_share: SecretScalar::new(evaluate_polynomial(&secret.coefficients, secret.identifier)),
+    commitments,
+    min_signers: secret.min_signers,
+  };
+  Ok((round2_secret, shares))
+}
+
+/// DKG round 3: verify the received shares and sum them into the signing share
+pub fn dkg_part3(
+  secret: &DkgRound2Secret,
+  round2_shares: &BTreeMap<u16, BigInt>,
+) -> Result<(KeyPackage, PublicKeyPackage), FrostError> {
+  let mut signing_share = secret.own_share.clone();
+  for (id, commitment) in &secret.commitments {
+    if *id == secret.identifier {
+      continue
+    }
+    let share = round2_shares.get(id).ok_or(FrostError::UnknownParticipant(*id))?;
+    if share <= &BigInt::zero() || share >= &*N
+      || !commitment.verify_share(secret.identifier, &PrivateKey::new(share.clone())) {
+      return Err(FrostError::InvalidSecretShare(*id))
+    }
+    signing_share = SecretScalar::new(mod_n(&(&*signing_share + share)));
+  }
+
+  let group_public_key = secret.commitments.values()
+    .fold(S256Point::infinity(), |acc, c| acc.add(c.group_public_key()));
+  if group_public_key.is_infinity() {
+    return Err(FrostError::InvalidGroupCommitment)
+  }
+  // Y_l = sum_i f_i(l) G, from the commitments alone
+  let verifying_shares = secret.commitments.keys()
+    .map(|l| {
+      let y = secret.commitments.values()
+        .fold(S256Point::infinity(), |acc, c| acc.add(&c.evaluate(*l)));
+      (*l, y)
+    })
+    .collect();
+
+  let key_package = KeyPackage {
+    identifier: secret.identifier,
+    signing_share: PrivateKey::new(signing_share.clone_int()),
+    group_public_key: group_public_key.clone(),
+    min_signers: secret.min_signers,
+  };
+  Ok((key_package, PublicKeyPackage { verifying_shares, group_public_key }))
+}
+
+/// round one commitments (D, E) = (d G, e G)
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SigningCommitments {
+  pub hiding: S256Point,
+  pub binding: S256Point,
+}
+
+/// round one nonces (d, e); `sign` takes them by value so they are used once,
+/// and they are wiped on drop
+#[derive(Debug)]
+pub struct SigningNonces {
+  hiding: SecretScalar,
+  binding: SecretScalar,
+  commitments: SigningCommitments,
+}
+
+/// nonce_generate: H3(random_bytes || SerializeScalar(secret))
+fn nonce_generate(signing_share: &PrivateKey) -> SecretScalar {
+  let mut data = Zeroizing::new([0u8; 32].to_vec());
+  rand::thread_rng().fill_bytes(&mut data);
+  data.extend_from_slice(signing_share.to_bytes().as_ref());
+  SecretScalar::new(h3(&data))
+}
+
+/// round one: fresh nonces and their commitments
+pub fn commit(signing_share: &PrivateKey) -> (SigningNonces, SigningCommitments) {
+  let hiding = nonce_generate(signing_share);
+  let binding = nonce_generate(signing_share);
+  let commitments = SigningCommitments {
+    hiding: Gs.rmul(hiding.clone_int()),
+    binding: Gs.rmul(binding.clone_int()),
+  };
+  (SigningNonces { hiding, binding, commitments: commitments.clone() }, commitments)
+}
+
+/// commitments of the chosen signers and the message
+#[derive(Debug, Clone)]
+pub struct SigningPackage {
+  pub commitments: BTreeMap<u16, SigningCommitments>,
+  pub message: Vec<u8>,
+}
+
+/// z_i of a signer, less than n
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SignatureShare {
+  s: BigInt,
+}
+
+impl SignatureShare {
+  pub fn parse(bytes: &[u8; 32]) -> Option<Self> {
+    let s = bytes_to_int(bytes);
+    if s >= *N {
+      return None
+    }
+    Some(Self { s })
+  }
+
+  pub fn serialize(&self) -> [u8; 32] {
+    int_to_bytes32(&self.s)
+  }
+}
+
+/// values every signer and the aggregator derive from the signing package
+struct SigningValues {
+  binding_factors: BTreeMap<u16, BigInt>,
+  group_commitment: S256Point,
+  challenge: BigInt,
+}
+
+impl SigningPackage {
+  fn participants(&self) -> Vec<u16> {
+    self.commitments.keys().cloned().collect()
+  }
+
+  /// compute_binding_factors, compute_group_commitment and the BIP340 challenge
+  fn values(&self, group_public_key: &S256Point) -> Result<SigningValues, FrostError> {
+    if group_public_key.is_infinity()
+      || self.commitments.values().any(|c| c.hiding.is_infinity() || c.binding.is_infinity()) {
+      return Err(FrostError::InvalidGroupCommitment)
+    }
+    let mut encoded_commitments = Vec::new();
+    for (id, c) in &self.commitments {
+      encoded_commitments.extend_from_slice(&int_to_bytes32(&BigInt::from(*id)));
+      encoded_commitments.extend(c.hiding.sec(true));
+      encoded_commitments.extend(c.binding.sec(true));
+    }
+    let mut prefix = group_public_key.sec(true);
+    prefix.extend_from_slice(&h4(&self.message));
+    prefix.extend_from_slice(&h5(&encoded_commitments));
+
+    let binding_factors: BTreeMap<u16, BigInt> = self.commitments.keys()
+      .map(|id| {
+        let mut rho_input = prefix.clone();
+        rho_input.extend_from_slice(&int_to_bytes32(&BigInt::from(*id)));
+        (*id, h1(&rho_input))
+      })
+      .collect();
+
+    let terms: Vec<(BigInt, S256Point)> = self.commitments.iter()
+      .flat_map(|(id, c)| [
+        (BigInt::one(), c.hiding.clone()),
+        (binding_factors[id].clone(), c.binding.clone()),
+      ])
+      .collect();
+    let group_commitment = S256Point::multi_mul(&terms);
+    if group_commitment.is_infinity() {
+      return Err(FrostError::InvalidGroupCommitment)
+    }
+
+    let mut data = xonly(&group_commitment).to_vec();
+    data.extend_from_slice(&xonly(group_public_key));
+    data.extend_from_slice(&self.message);
+    let challenge = mod_n(&bytes_to_int(&tagged_hash("BIP0340/challenge", &data)));
+
+    Ok(SigningValues { binding_factors, group_commitment, challenge })
+  }
+}
+
+/// -1 mod n for an odd y point, 1 otherwise
+fn parity_factor(point: &S256Point) -> BigInt {
+  if point.has_even_y() { BigInt::one() } else { N.clone() - BigInt::one() }
+}
+
+/// round two: z_i = (d_i + e_i rho_i) + lambda_i s_i c, with the nonce and key
+/// negated to match the even-y R and group key of BIP340
+pub fn sign(
+  package: &SigningPackage,
+  nonces: SigningNonces,
+  key_package: &KeyPackage,
+) -> Result<SignatureShare, FrostError> {
+  let id = key_package.identifier;
+  if package.commitments.get(&id) != Some(&nonces.commitments) {
+    return Err(FrostError::UnknownParticipant(id))
+  }
+  if package.commitments.len() < key_package.min_signers as usize {
+    return Err(FrostError::NotEnoughSigners)
+  }
+  let values = package.values(&key_package.group_public_key)?;
+  let lambda = lagrange_coefficient(id, &package.participants());
+
+  let nonce = SecretScalar::new(&*nonces.hiding + &*nonces.binding * &values.binding_factors[&id]);
+  let nonce = SecretScalar::new(&*nonce * parity_factor(&values.group_commitment));
+  let secret = SecretScalar::new(&*key_package.signing_share.secret() * parity_factor(&key_package.group_public_key));
+  let s = mod_n(&(&*nonce + lambda * &*secret * values.challenge));
+  Ok(SignatureShare { s })
+}
+
+/// z_i G == (D_i + rho_i E_i) + c lambda_i Y_i, up to the parity negations
+pub fn verify_signature_share(
+  identifier: u16,
+  verifying_share: &S256Point,
+  share: &SignatureShare,
+  package: &SigningPackage,
+  group_public_key: &S256Point,
+) -> bool {
+  let commitments = match package.commitments.get(&identifier) {
+    Some(c_) => c_,
+    None => return false
+  };
+  let values = match package.values(group_public_key) {
+    Ok(values_) => values_,
+    Err(_) => return false
+  };
+  let lambda = lagrange_coefficient(identifier, &package.participants());
+
+  let r_parity = parity_factor(&values.group_commitment);
+  let rho = &values.binding_factors[&identifier];
+  let expected = S256Point::multi_mul(&[
+    (r_parity.clone(), commitments.hiding.clone()),
+    (r_parity * rho, commitments.binding.clone()),
+    (values.challenge * lambda * parity_factor(group_public_key), verifying_share.clone()),
+  ]);
+  Gs.rmul(share.s.clone()) == expected
+}
+
+/// sum the signature shares into a BIP340 signature (R.x, z); if it does not
+/// verify, the shares are checked to find the misbehaving signer
+pub fn aggregate(
+  package: &SigningPackage,
+  shares: &BTreeMap<u16, SignatureShare>,
+  public_key_package: &PublicKeyPackage,
+) -> Result<SchnorrSignature, FrostError> {
+  if let Some(id) = package.commitments.keys().find(|id| !shares.contains_key(id)) {
+    return Err(FrostError::UnknownParticipant(*id))
+  }
+  if let Some(id) = shares.keys().find(|id| !package.commitments.contains_key(id)) {
+    return Err(FrostError::UnknownParticipant(*id))
+  }
+  let values = package.values(&public_key_package.group_public_key)?;
+  let z = shares.values().fold(BigInt::zero(), |acc, share| mod_n(&(acc + &share.s)));
+  let r = values.group_commitment.x.clone().ok_or(FrostError::InvalidGroupCommitment)?;
+  let signature = SchnorrSignature::new(r, S256Field::new(z, PRIME.clone()));
+
+  if schnorr::verify(&public_key_package.group_public_key, &package.message, &signature) {
+    return Ok(signature)
+  }
+  for (id, share) in shares {
+    let verifying_share = public_key_package.verifying_shares.get(id)
+      .ok_or(FrostError::UnknownParticipant(*id))?;
+    if !verify_signature_share(*id, verifying_share, share, package, &public_key_package.group_public_key) {
+      return Err(FrostError::InvalidSignatureShare(*id))
+    }
+  }
+  Err(FrostError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sign_with(
+    key_packages: &[KeyPackage],
+    public_key_package: &PublicKeyPackage,
+    signers: &[u16],
+    message: &[u8],
+  ) -> Result<SchnorrSignature, FrostError> {
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for id in signers {
+      let (nonce, commitment) = commit(&key_packages[*id as usize - 1].signing_share);
+      nonces.insert(*id, nonce);
+      commitments.insert(*id, commitment);
+    }
+    let package = SigningPackage { commitments, message: message.to_vec() };
+    let mut shares = BTreeMap::new();
+    for (id, nonce) in nonces {
+      shares.insert(id, sign(&package, nonce, &key_packages[id as usize - 1])?);
+    }
+    aggregate(&package, &shares, public_key_package)
+  }
+
+  #[test]
+  fn trusted_dealer_signing() {
+    let secret = PrivateKey::from_bytes(&[7u8; 32]).unwrap();
+    let (key_packages, public_key_package, commitment) = trusted_dealer_keygen(&secret, 3, 2).unwrap();
+    assert_eq!(public_key_package.group_public_key, secret.point);
+    for key_package in &key_packages {
+      assert!(commitment.verify_share(key_package.identifier, &key_package.signing_share));
+    }
+    let signature = sign_with(&key_packages, &public_key_package, &[1, 3], b"FROST").unwrap();
+    assert!(schnorr::verify(&secret.point, b"FROST", &signature));
+    assert_eq!(
+      sign_with(&key_packages, &public_key_package, &[2], b"FROST").unwrap_err(),
+      FrostError::NotEnoughSigners
+    );
+  }
+
+  #[test]
+  fn dkg_round_two_checks_the_packages() {
+    let round1: BTreeMap<u16, (DkgRound1Secret, DkgRound1Package)> = (1..=3)
+      .map(|id| (id, dkg_part1(id, 3, 2).unwrap()))
+      .collect();
+    let packages_for = |id: u16, ids: &[u16]| -> BTreeMap<u16, DkgRound1Package> {
+      ids.iter().filter(|l| **l != id).map(|l| (*l, round1[l].1.clone())).collect()
+    };
+    let fresh_secret = || dkg_part1(1, 3, 2).unwrap().0;
+
+    assert_eq!(dkg_part2(fresh_secret(), &packages_for(1, &[2])).unwrap_err(), FrostError::WrongPackageCount);
+    let mut own = packages_for(1, &[2]);
+    own.insert(1, round1[&1].1.clone());
+    assert_eq!(dkg_part2(fresh_secret(), &own).unwrap_err(), FrostError::InvalidIdentifier);
+    let mut zero = packages_for(1, &[2]);
+    zero.insert(0, round1[&3].1.clone());
+    assert_eq!(dkg_part2(fresh_secret(), &zero).unwrap_err(), FrostError::InvalidIdentifier);
+
+    let mut infinite_r = packages_for(1, &[2, 3]);
+    infinite_r.get_mut(&3).unwrap().proof_r = S256Point::infinity();
+    assert_eq!(dkg_part2(fresh_secret(), &infinite_r).unwrap_err(), FrostError::InvalidProofOfKnowledge(3));
+    let mut infinite_key = packages_for(1, &[2, 3]);
+    infinite_key.get_mut(&2).unwrap().commitment.0[0] = S256Point::infinity();
+    assert_eq!(dkg_part2(fresh_secret(), &infinite_key).unwrap_err(), FrostError::InvalidProofOfKnowledge(2));
+  }
+
+  #[test]
+  fn infinite_signer_commitments_are_rejected() {
+    let secret = PrivateKey::from_bytes(&[7u8; 32]).unwrap();
+    let (key_packages, public_key_package, _) = trusted_dealer_keygen(&secret, 3, 2).unwrap();
+    let (nonces_1, commitments_1) = commit(&key_packages[0].signing_share);
+    let (_, commitments_2) = commit(&key_packages[1].signing_share);
+    let share = SignatureShare { s: BigInt::one() };
+
+    for infinite_binding in [false, true] {
+      let mut bad = commitments_2.clone();
+      if infinite_binding { bad.binding = S256Point::infinity() } else { bad.hiding = S256Point::infinity() }
+      let package = SigningPackage {
+        commitments: BTreeMap::from([(1, commitments_1.clone()), (2, bad)]),
+        message: b"FROST".to_vec(),
+      };
+      assert!(!verify_signature_share(
+        2, &public_key_package.verifying_shares[&2], &share, &package, &public_key_package.group_public_key
+      ));
+      let shares = BTreeMap::from([(1, share.clone()), (2, share.clone())]);
+      assert_eq!(aggregate(&package, &shares, &public_key_package).unwrap_err(), FrostError::InvalidGroupCommitment);
+    }
+    let package = SigningPackage {
+      commitments: BTreeMap::from([
+        (1, commitments_1),
+        (2, SigningCommitments { hiding: S256Point::infinity(), binding: commitments_2.binding }),
+      ]),
+      message: b"FROST".to_vec(),
+    };
+    assert_eq!(sign(&package, nonces_1, &key_packages[0]).unwrap_err(), FrostError::InvalidGroupCommitment);
+  }
+
+  #[test]
+  fn aggregate_names_the_bad_share() {
+    let secret = PrivateKey::from_bytes(&[7u8; 32]).unwrap();
+    let (key_packages, public_key_package, _) = trusted_dealer_keygen(&secret, 3, 3).unwrap();
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for key_package in &key_packages {
+      let (nonce, commitment) = commit(&key_package.signing_share);
+      nonces.insert(key_package.identifier, nonce);
+      commitments.insert(key_package.identifier, commitment);
+    }
+    let package = SigningPackage { commitments, message: b"FROST".to_vec() };
+    let mut shares: BTreeMap<u16, SignatureShare> = nonces.into_iter()
+      .map(|(id, nonce)| (id, sign(&package, nonce, &key_packages[id as usize - 1]).unwrap()))
+      .collect();
+    assert!(aggregate(&package, &shares, &public_key_package).is_ok());
+
+    shares.get_mut(&2).unwrap().s += 1;
+    assert_eq!(aggregate(&package, &shares, &public_key_package).unwrap_err(), FrostError::InvalidSignatureShare(2));
+  }
+
+  #[test]
+  fn dkg_signing() {
+    let mut round1_secrets = BTreeMap::new();
+    let mut round1_packages = BTreeMap::new();
+    for id in 1..=3u16 {
+      let (secret, package) = dkg_part1(id, 3, 2).unwrap();
+      round1_secrets.insert(id, secret);
+      round1_packages.insert(id, package);
+    }
+    let mut round2_secrets = BTreeMap::new();
+    let mut inboxes: BTreeMap<u16, BTreeMap<u16, BigInt>> = BTreeMap::new();
+    for (id, secret) in round1_secrets {
+      let others = round1_packages.iter()
+        .filter(|(l, _)| **l != id)
+        .map(|(l, package)| (*l, package.clone()))
+        .collect();
+      let (round2_secret, shares) = dkg_part2(secret, &others).unwrap();
+      round2_secrets.insert(id, round2_secret);
+      for (to, share) in shares {
+        inboxes.entry(to).or_default().insert(id, share);
+      }
+    }
+
+    let mut bad = inboxes[&1].clone();
+    *bad.get_mut(&2).unwrap() += 1;
+    assert_eq!(dkg_part3(&round2_secrets[&1], &bad).unwrap_err(), FrostError::InvalidSecretShare(2));
+
+    let (key_packages, public_key_packages): (Vec<_>, Vec<_>) = round2_secrets.iter()
+      .map(|(id, secret)| dkg_part3(secret, &inboxes[id]).unwrap())
+      .unzip();
+    let signature = sign_with(&key_packages, &public_key_packages[0], &[2, 3], b"DKG").unwrap();
+    assert!(schnorr::verify(&public_key_packages[1].group_public_key, b"DKG", &signature));
+  }
+}
@@ -0,0 +1,287 @@
+//! Hashing to short Weierstrass curves with the simplified SWU map
+//! https://datatracker.ietf.org/doc/html/rfc9380
+//!
+//! Suites: secp256k1_XMD:SHA-256_SSWU_RO_ / _NU_, P256_XMD:SHA-256_SSWU_RO_ / _NU_, and
+//! BLS12381G2_XMD:SHA-256_SSWU_RO_ / _NU_ through the `SswuCurve` impl in `bls12_381`.
+//! The map and the isogeny are written once over `CurveField`, so they serve F_p and F_p^2.
+//! secp256k1 and G2 have A = 0, so the map lands on an isogenous curve E' first (section 6.6.3).
+//! secp256k1 and P-256 have cofactor 1, so clearing the cofactor is the identity, and
+//! `hash_to_curve::<Secp256k1>` gives an `S256Point`.
+
+use num_bigint::BigInt;
+use num_traits::One;
+use once_cell::sync::Lazy;
+
+use super::{
+  curve::{Curve, P256, Secp256k1},
+  field_elements::Fe,
+  helper::hash_to_field,
+  point::{AffinePoint, CurveField, WeierstrassCurve},
+};
+
+/// the simplified SWU parameters of a suite
+pub struct SswuParams<F> {
+  /// A and B of the curve the map lands on
+  pub a: F,
+  pub b: F,
+  pub z: F,
+  /// x_num, x_den, y_num, y_den of the isogeny onto the target curve, lowest degree first;
+  /// empty when the map lands on the curve itself
+  pub isogeny: Vec<Vec<F>>,
+  /// h_eff, the scalar that clears the cofactor
+  pub h_eff: BigInt,
+}
+
+/// a curve with RFC 9380 simplified SWU suites
+pub trait SswuCurve: WeierstrassCurve {
+  fn sswu_params() -> &'static SswuParams<Self::Coordinate>;
+
+  /// hash_to_field for the coordinate field, `count` elements
+  fn hash_to_field(msg: &[u8], dst: &[u8], count: usize) -> Vec<Self::Coordinate>;
+}
+
+fn fe<C: Curve>(s: &str) -> Fe<C> {
+  Fe::from_int(&BigInt::parse_bytes(s.as_bytes(), 16).expect("hex"))
+}
+
+fn fe_coefs<C: Curve>(coefs: &[&str]) -> Vec<Fe<C>> {
+  coefs.iter().map(|k| fe(k)).collect()
+}
+
+/// hash_to_field over F_p: L = ceil((ceil(log2(p)) + 128) / 8) bytes per element
+fn hash_to_fe<C: Curve>(msg: &[u8], dst: &[u8], count: usize) -> Vec<Fe<C>> {
+  let l = (C::p().bits() + 128).div_ceil(8) as usize;
+  hash_to_field(msg, dst, count, C::p(), l)
+    .expect("the suites hash at most two elements")
+    .iter()
+    .map(Fe::from_int)
+    .collect()
+}
+
+impl SswuCurve for Secp256k1 {
+  /// section 8.7 and appendix E.1
+  fn sswu_params() -> &'static SswuParams<Fe<Secp256k1>> {
+    static PARAMS: Lazy<SswuParams<Fe<Secp256k1>>> = Lazy::new(|| SswuParams {
+      a: fe("3f8731abdd661adca08a5558f0f5d272e953d363cb6f0e5d405447c01a444533"),
+      b: Fe::from_int(&BigInt::from(1771u16)),
+      z: Fe::from_int(&(Secp256k1::p() - 11u8)),
+      isogeny: vec![
+        fe_coefs(&[
+          "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa8c7",
+          "07d3d4c80bc321d5b9f315cea7fd44c5d595d2fc0bf63b92dfff1044f17c6581",
+          "534c328d23f234e6e2a413deca25caece4506144037c40314ecbd0b53d9dd262",
+          "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa88c",
+        ]),
+        fe_coefs(&[
+          "d35771193d94918a9ca34ccbb7b640dd86cd409542f8487d9fe6b745781eb49b",
+          "edadc6f64383dc1df7c4b2d51b54225406d36b641f5e41bbc52a56612a8c6d14",
+          "1",
+        ]),
+        fe_coefs(&[
+          "4bda12f684bda12f684bda12f684bda12f684bda12f684bda12f684b8e38e23c",
+          "c75e0c32d5cb7c0fa9d0a54b12a0a6d5647ab046d686da6fdffc90fc201d71a3",
+          "29a6194691f91a73715209ef6512e576722830a201be2018a765e85a9ecee931",
+          "2f684bda12f684bda12f684bda12f684bda12f684bda12f684bda12f38e38d84",
+        ]),
+        fe_coefs(&[
+          "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffff93b",
+          "7a06534bb8bdb49fd5e9e6632722c2989467c1bfc8e8d978dfb425d2685c2573",
+          "6484aa716545ca2cf3a70c3fa8fe337e0a3d21162f0d6299a7bf8192bfd2a76f",
+          "1",
+        ]),
+      ],
+      h_eff: BigInt::one(),
+    });
+    &PARAMS
+  }
+
+  fn hash_to_field(msg: &[u8], dst: &[u8], count: usize) -> Vec<Fe<Secp256k1>> {
+    hash_to_fe(msg, dst, count)
+  }
+}
+
+impl SswuCurve for P256 {
+  /// section 8.2
+  fn sswu_params() -> &'static SswuParams<Fe<P256>> {
+    static PARAMS: Lazy<SswuParams<Fe<P256>>> = Lazy::new(|| SswuParams {
+      a: Fe::from_int(P256::a()),
+      b: Fe::from_int(P256::b()),
+      z: Fe::from_int(&(P256::p() - 10u8)),
+      isogeny: Vec::new(),
+      h_eff: BigInt::one(),
+    });
+    &PARAMS
+  }
+
+  fn hash_to_field(msg: &[u8], dst: &[u8], count: usize) -> Vec<Fe<P256>> {
+    hash_to_fe(msg, dst, count)
+  }
+}
+
+/// simplified SWU, section 6.6.2, in its straightforward form (appendix F.2)
+fn map_to_curve_sswu<F: CurveField>(params: &SswuParams<F>, u: &F) -> (F, F) {
+  let SswuParams { a, b, z, .. } = params;
+  let g = |x: &F| x.mul(x).mul(x).add(&a.mul(x)).add(b);
+
+  let zu2 = z.mul(&u.mul(u));
+  // x1 = (-B / A)(1 + 1 / tv1), or B / (Z A) when tv1 = 0
+  let x1 = match zu2.mul(&zu2).add(&zu2).inverse() {
+    Some(tv1) => b.neg().mul(&a.inverse().expect("A != 0")).mul(&F::one().add(&tv1)),
+    None => b.mul(&z.mul(a).inverse().expect("Z A != 0")),
+  };
+  let (x, y) = match g(&x1).sqrt() {
+    Some(y) => (x1, y),
+    None => {
+      let x2 = zu2.mul(&x1);
+      let y = g(&x2).sqrt().expect("g(x1) or g(x2) is a square");
+      (x2, y)
+    }
+  };
+  let y = if u.sgn0() == y.sgn0() { y } else { y.neg() };
+  (x, y)
+}
+
+/// Horner evaluation, `coefs` lowest degree first
+fn polynomial<F: CurveField>(coefs: &[F], x: &F) -> F {
+  coefs.iter().rev().fold(F::zero(), |acc, k| acc.mul(x).add(k))
+}
+
+/// map_to_curve: simplified SWU, then the isogeny if the suite has one;
+/// infinity where an isogeny denominator vanishes
+fn map_to_curve<C: SswuCurve>(u: &C::Coordinate) -> AffinePoint<C> {
+  let params = C::sswu_params();
+  let (x, y) = map_to_curve_sswu(params, u);
+  let [x_num, x_den, y_num, y_den] = match params.isogeny.as_slice() {
+    [] => return AffinePoint::new(Some(x), Some(y)),
+    [x_num, x_den, y_num, y_den] => [x_num, x_den, y_num, y_den],
+    _ => panic!("an isogeny is x_num, x_den, y_num, y_den"),
+  };
+  match (polynomial(x_den, &x).inverse(), polynomial(y_den, &x).inverse()) {
+    (Some(x_den), Some(y_den)) => AffinePoint::new(
+      Some(polynomial(x_num, &x).mul(&x_den)),
+      Some(y.mul(&polynomial(y_num, &x)).mul(&y_den)),
+    ),
+    _ => AffinePoint::infinity(),
+  }
+}
+
+fn clear_cofactor<C: SswuCurve>(point: &AffinePoint<C>) -> AffinePoint<C> {
+  let h_eff = &C::sswu_params().h_eff;
+  if h_eff.is_one() { point.clone() } else { point.rmul(h_eff.clone()) }
+}
+
+/// hash_to_curve, the random-oracle encoding (`_RO_` suites): two field elements, two maps, one sum
+pub fn hash_to_curve<C: SswuCurve>(msg: &[u8], dst: &[u8]) -> AffinePoint<C> {
+  let u = C::hash_to_field(msg, dst, 2);
+  clear_cofactor(&map_to_curve::<C>(&u[0]).add(&map_to_curve(&u[1])))
+}
+
+/// encode_to_curve, the nonuniform encoding (`_NU_` suites): one field element, one map
+pub fn encode_to_curve<C: SswuCurve>(msg: &[u8], dst: &[u8]) -> AffinePoint<C> {
+  let u = C::hash_to_field(msg, dst, 1);
+  clear_cofactor(&map_to_curve::<C>(&u[0]))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{bls12_381::Bls12381G2, field_elements::FieldOperation};
+
+  fn xy<C: Curve>(point: &AffinePoint<C>) -> (String, String) {
+    let hex = |c: &Option<Fe<C>>| format!("{:064x}", c.as_ref().unwrap().get_num());
+    (hex(&point.x), hex(&point.y))
+  }
+
+  fn a512() -> Vec<u8> {
+    let mut msg = b"a512_".to_vec();
+    msg.extend([b'a'; 512]);
+    msg
+  }
+
+  /// RFC 9380 appendix J.8.1
+  #[test]
+  fn p256_ro() {
+    let dst = b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_RO_";
+    let cases: [(&[u8], &str, &str); 3] = [
+      (b"", "2c15230b26dbc6fc9a37051158c95b79656e17a1a920b11394ca91c44247d3e4",
+        "8a7a74985cc5c776cdfe4b1f19884970453912e9d31528c060be9ab5c43e8415"),
+      (b"abc", "0bb8b87485551aa43ed54f009230450b492fead5f1cc91658775dac4a3388a0f",
+        "5c41b3d0731a27a7b14bc0bf0ccded2d8751f83493404c84a88e71ffd424212e"),
+      (&a512(), "457ae2981f70ca85d8e24c308b14db22f3e3862c5ea0f652ca38b5e49cd64bc5",
+        "ecb9f0eadc9aeed232dabc53235368c1394c78de05dd96893eefa62b0f4757dc"),
+    ];
+    for (msg, x, y) in cases {
+      assert_eq!(xy(&hash_to_curve::<P256>(msg, dst)), (x.to_string(), y.to_string()));
+    }
+  }
+
+  /// RFC 9380 appendix J.8.2
+  #[test]
+  fn p256_nu() {
+    let dst = b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_NU_";
+    assert_eq!(xy(&encode_to_curve::<P256>(b"", dst)), (
+      "f871caad25ea3b59c16cf87c1894902f7e7b2c822c3d3f73596c5ace8ddd14d1".to_string(),
+      "87b9ae23335bee057b99bac1e68588b18b5691af476234b8971bc4f011ddc99b".to_string(),
+    ));
+    assert_eq!(xy(&encode_to_curve::<P256>(b"abc", dst)), (
+      "fc3f5d734e8dce41ddac49f47dd2b8a57257522a865c124ed02b92b5237befa4".to_string(),
+      "fe4d197ecf5a62645b9690599e1d80e82c500b22ac705a0b421fac7b47157866".to_string(),
+    ));
+  }
+
+  /// RFC 9380 appendix J.9.1
+  #[test]
+  fn secp256k1_ro() {
+    let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+    let cases: [(&[u8], &str, &str); 3] = [
+      (b"", "c1cae290e291aee617ebaef1be6d73861479c48b841eaba9b7b5852ddfeb1346",
+        "64fa678e07ae116126f08b022a94af6de15985c996c3a91b64c406a960e51067"),
+      (b"abc", "3377e01eab42db296b512293120c6cee72b6ecf9f9205760bd9ff11fb3cb2c4b",
+        "7f95890f33efebd1044d382a01b1bee0900fb6116f94688d487c6c7b9c8371f6"),
+      (&a512(), "e3c8d35aaaf0b9b647e88a0a0a7ee5d5bed5ad38238152e4e6fd8c1f8cb7c998",
+        "8446eeb6181bf12f56a9d24e262221cc2f0c4725c7e3803024b5888ee5823aa6"),
+    ];
+    for (msg, x, y) in cases {
+      assert_eq!(xy(&hash_to_curve::<Secp256k1>(msg, dst)), (x.to_string(), y.to_string()));
+    }
+  }
+
+  /// RFC 9380 appendix J.9.2
+  #[test]
+  fn secp256k1_nu() {
+    let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_NU_";
+    assert_eq!(xy(&encode_to_curve::<Secp256k1>(b"", dst)), (
+      "a4792346075feae77ac3b30026f99c1441b4ecf666ded19b7522cf65c4c55c5b".to_string(),
+      "62c59e2a6aeed1b23be5883e833912b08ba06be7f57c0e9cdc663f31639ff3a7".to_string(),
+    ));
+    assert_eq!(xy(&encode_to_curve::<Secp256k1>(b"abc", dst)), (
+      "3f3b5842033fff837d504bb4ce2a372bfeadbdbd84a1d2b678b6e1d7ee426b9d".to_string(),
+      "902910d1fef15d8ae2006fc84f2a5a7bda0e0407dc913062c3a493c4f5d876a5".to_string(),
+    ));
+  }
+
+  /// RFC 9380 appendix J.10.1, x.c1 || x.c0 || y.c1 || y.c0
+  #[test]
+  fn bls12_381_g2_ro() {
+    let dst = b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+    let cases: [(&[u8], &str); 2] = [
+      (b"", "05cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d\
+             0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a\
+             12424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6\
+             0503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92"),
+      (&a512(), "11fca2ff525572795a801eed17eb12785887c7b63fb77a42be46ce4a34131d71f7a73e95fee3f812aea3de78b4d01569\
+                 01a6ba2f9a11fa5598b2d8ace0fbe0a0eacb65deceb476fbbcb64fd24557c2f4b18ecfc5663e54ae16a84f5ab7f62534\
+                 03a47f8e6d1763ba0cad63d6114c0accbef65707825a511b251a660a9b3994249ae4e63fac38b23da0c398689ee2ab52\
+                 0b6798718c8aed24bc19cb27f866f1c9effcdbf92397ad6448b5c9db90d2b9da6cbabf48adc1adf59a1a28344e79d57e"),
+    ];
+    for (msg, expected) in cases {
+      let point = hash_to_curve::<Bls12381G2>(msg, dst);
+      let (x, y) = (point.x.as_ref().unwrap(), point.y.as_ref().unwrap());
+      let hex = format!(
+        "{:096x}{:096x}{:096x}{:096x}", x.c1.get_num(), x.c0.get_num(), y.c1.get_num(), y.c0.get_num()
+      );
+      assert_eq!(hex, expected);
+      assert!(point.is_in_subgroup());
+    }
+  }
+}
@@ -0,0 +1,393 @@
+use std::{fmt, ops::Deref, sync::atomic};
+
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer;
+use num_traits::Zero;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// overwrites the limbs of `n` with zeros, as `Zeroize` would; num-bigint has no `Zeroize`
+pub(crate) fn wipe_int(n: &mut BigInt) {
+  let limbs = n.bits().div_ceil(32) as usize;
+  n.assign_from_slice(Sign::Plus, &vec![0u32; limbs]);
+  atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// a secret scalar, wiped by `wipe_int` on drop. Arithmetic on it still leaves
+/// unwiped temporaries, so every result that stays secret is wrapped again at once.
+#[derive(Clone)]
+pub(crate) struct SecretScalar(BigInt);
+
+impl SecretScalar {
+  pub(crate) fn new(n: BigInt) -> Self {
+    Self(n)
+  }
+
+  /// big-endian bytes, without the unwiped copy that `BigInt::from_bytes_be` makes
+  pub(crate) fn from_bytes_be(bytes: &[u8]) -> Self {
+    let mut digits = Zeroizing::new(vec![0u32; bytes.len().div_ceil(4)]);
+    for (i, b) in bytes.iter().rev().enumerate() {
+      digits[i / 4] |= (*b as u32) << (8 * (i % 4));
+    }
+    Self(BigInt::from_slice(Sign::Plus, &digits))
+  }
+
+  /// a copy for APIs that consume a `BigInt`, such as `rmul`, which wipes it when done
+  pub(crate) fn clone_int(&self) -> BigInt {
+    self.0.clone()
+  }
+
+  /// big-endian 32 bytes in a buffer that is wiped on drop
+  pub(crate) fn to_bytes32(&self) -> Zeroizing<[u8; 32]> {
+    if self.0.bits() > 256 {
+      panic!("secret does not fit in 32 bytes")
+    }
+    let mut out = Zeroizing::new([0u8; 32]);
+    for (i, digit) in self.0.iter_u32_digits().enumerate() {
+      out[28 - 4 * i..32 - 4 * i].copy_from_slice(&digit.to_be_bytes());
+    }
+    out
+  }
+}
+
+impl Deref for SecretScalar {
+  type Target = BigInt;
+
+  fn deref(&self) -> &BigInt {
+    &self.0
+  }
+}
+
+impl Drop for SecretScalar {
+  fn drop(&mut self) {
+    wipe_int(&mut self.0);
+  }
+}
+
+impl fmt::Debug for SecretScalar {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("SecretScalar([REDACTED])")
+  }
+}
+
+/// big-endian 32 bytes, zero padded on the left
+pub fn int_to_bytes32(n: &BigInt) -> [u8; 32] {
+  let (_, bytes) = n.to_bytes_be();
+  if bytes.len() > 32 {
+    panic!("{} does not fit in 32 bytes", n);
+  }
+  let mut out = [0u8; 32];
+  out[32 - bytes.len()..].copy_from_slice(&bytes);
+  out
+}
+
+/// big-endian `len` bytes, zero padded on the left
+pub fn int_to_bytes(n: &BigInt, len: usize) -> Vec<u8> {
+  let (_, bytes) = n.to_bytes_be();
+  if bytes.len() > len {
+    panic!("{} does not fit in {} bytes", n, len);
+  }
+  let mut out = vec![0u8; len - bytes.len()];
+  out.extend(bytes);
+  out
+}
+
+pub fn bytes_to_int(bytes: &[u8]) -> BigInt {
+  BigInt::from_bytes_be(Sign::Plus, bytes)
+}
+
+/// little-endian 32 bytes, as RFC 7748 and RFC 8032 encode field elements and scalars
+pub fn int_to_le_bytes32(n: &BigInt) -> [u8; 32] {
+  let (_, bytes) = n.to_bytes_le();
+  if bytes.len() > 32 {
+    panic!("{} does not fit in 32 bytes", n);
+  }
+  let mut out = [0u8; 32];
+  out[..bytes.len()].copy_from_slice(&bytes);
+  out
+}
+
+pub fn le_bytes_to_int(bytes: &[u8]) -> BigInt {
+  BigInt::from_bytes_le(Sign::Plus, bytes)
+}
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+  Sha256::digest(data).into()
+}
+
+/// BIP340 tagged hash: SHA256(SHA256(tag) || SHA256(tag) || msg)
+pub fn tagged_hash(tag: &str, msg: &[u8]) -> [u8; 32] {
+  let tag_hash = sha256(tag.as_bytes());
+  let mut hasher = Sha256::new();
+  hasher.update(tag_hash);
+  hasher.update(tag_hash);
+  hasher.update(msg);
+  hasher.finalize().into()
+}
+
+/// Bitcoin CompactSize (varint) encoding
+pub fn encode_varint(i: u64) -> Vec<u8> {
+  if i < 0xfd {
+    vec![i as u8]
+  } else if i <= 0xffff {
+    let mut out = vec![0xfd];
+    out.extend_from_slice(&(i as u16).to_le_bytes());
+    out
+  } else if i <= 0xffff_ffff {
+    let mut out = vec![0xfe];
+    out.extend_from_slice(&(i as u32).to_le_bytes());
+    out
+  } else {
+    let mut out = vec![0xff];
+    out.extend_from_slice(&i.to_le_bytes());
+    out
+  }
+}
+
+/// expand_message_xmd with SHA-256
+/// https://datatracker.ietf.org/doc/html/rfc9380#section-5.3.1
+///
+/// `None` if `len_in_bytes` needs more than 255 SHA-256 blocks (8160 bytes)
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Option<Vec<u8>> {
+  let ell = len_in_bytes.div_ceil(32);
+  if ell > 255 {
+    return None
+  }
+  // DST longer than 255 bytes is hashed first (section 5.3.3)
+  let dst = if dst.len() > 255 {
+    let mut long_dst = b"H2C-OVERSIZE-DST-".to_vec();
+    long_dst.extend_from_slice(dst);
+    sha256(&long_dst).to_vec()
+  } else {
+    dst.to_vec()
+  };
+  let mut dst_prime = dst.clone();
+  dst_prime.push(dst.len() as u8);
+
+  let mut msg_prime = vec![0u8; 64];
+  msg_prime.extend_from_slice(msg);
+  msg_prime.extend_from_slice(&(len_in_bytes as u16).to_be_bytes());
+  msg_prime.push(0);
+  msg_prime.extend_from_slice(&dst_prime);
+  let b_0 = sha256(&msg_prime);
+
+  let mut uniform_bytes = Vec::with_capacity(ell * 32);
+  let mut b_i = [0u8; 32];
+  for i in 1..=ell {
+    let mut data: Vec<u8> = b_0.iter().zip(b_i).map(|(b0_, bi_)| b0_ ^ bi_).collect();
+    data.push(i as u8);
+    data.extend_from_slice(&dst_prime);
+    b_i = sha256(&data);
+    uniform_bytes.extend_from_slice(&b_i);
+  }
+  uniform_bytes.truncate(len_in_bytes);
+  Some(uniform_bytes)
+}
+
+/// hash_to_field: `count` elements mod `modulus`, `l` bytes each;
+/// `None` when `count * l` is too long for `expand_message_xmd`
+/// https://datatracker.ietf.org/doc/html/rfc9380#section-5.2
+pub fn hash_to_field(msg: &[u8], dst: &[u8], count: usize, modulus: &BigInt, l: usize) -> Option<Vec<BigInt>> {
+  let uniform_bytes = expand_message_xmd(msg, dst, count * l)?;
+  Some(uniform_bytes.chunks(l)
+    .map(|chunk| bytes_to_int(chunk) % modulus)
+    .collect())
+}
+
+/// RIPEMD160(SHA256(data))
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+  Ripemd160::digest(sha256(data)).into()
+}
+
+/// SHA256(SHA256(data))
+pub fn hash256(data: &[u8]) -> [u8; 32] {
+  sha256(&sha256(data))
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Bitcoin base58, leading zero bytes become '1's
+pub fn encode_base58(data: &[u8]) -> String {
+  let zeros = data.iter().take_while(|b| **b == 0).count();
+  let mut num = bytes_to_int(data);
+  let base = BigInt::from(58u8);
+  let mut out = Vec::new();
+  while num > BigInt::zero() {
+    let (q, r) = num.div_rem(&base);
+    let (_, digits) = r.to_u32_digits();
+    out.push(BASE58_ALPHABET[digits.first().copied().unwrap_or(0) as usize]);
+    num = q;
+  }
+  out.extend(std::iter::repeat_n(b'1', zeros));
+  out.reverse();
+  String::from_utf8(out).expect("base58 alphabet is ascii")
+}
+
+/// `None` on characters outside the base58 alphabet
+pub fn decode_base58(s: &str) -> Option<Vec<u8>> {
+  let zeros = s.bytes().take_while(|c| *c == b'1').count();
+  let mut num = BigInt::zero();
+  for c in s.bytes() {
+    let digit = BASE58_ALPHABET.iter().position(|a| *a == c)?;
+    num = num * 58u8 + digit;
+  }
+  let mut out = vec![0u8; zeros];
+  if num > BigInt::zero() {
+    out.extend(num.to_bytes_be().1);
+  }
+  Some(out)
+}
+
+/// base58 of `data || hash256(data)[..4]`
+pub fn encode_base58_checksum(data: &[u8]) -> String {
+  let mut payload = data.to_vec();
+  payload.extend_from_slice(&hash256(data)[..4]);
+  encode_base58(&payload)
+}
+
+/// `None` if the string is not base58 or the checksum does not match
+pub fn decode_base58_checksum(s: &str) -> Option<Vec<u8>> {
+  let mut payload = decode_base58(s)?;
+  if payload.len() < 4 {
+    return None
+  }
+  let checksum = payload.split_off(payload.len() - 4);
+  if hash256(&payload)[..4] != checksum[..] {
+    return None
+  }
+  Some(payload)
+}
+
+/// lowercase hex, no prefix
+pub fn encode_hex(data: &[u8]) -> String {
+  data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `None` on odd length or non-hex characters, an optional `0x` prefix is allowed
+pub fn decode_hex(s: &str) -> Option<Vec<u8>> {
+  let s = s.strip_prefix("0x").unwrap_or(s);
+  if !s.len().is_multiple_of(2) || !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+    return None
+  }
+  (0..s.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+    .collect()
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64_with(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+  let mut out = Vec::with_capacity(data.len().div_ceil(3) * 4);
+  for chunk in data.chunks(3) {
+    let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+    let chars = chunk.len() + 1;
+    for i in 0..4 {
+      if i < chars {
+        out.push(alphabet[(n >> (18 - 6 * i)) as usize & 0x3f]);
+      } else if pad {
+        out.push(b'=');
+      }
+    }
+  }
+  String::from_utf8(out).expect("base64 alphabet is ascii")
+}
+
+/// `None` on characters outside the alphabet, bad padding or non-zero trailing bits
+fn decode_base64_with(s: &str, alphabet: &[u8; 64], pad: bool) -> Option<Vec<u8>> {
+  let s = s.as_bytes();
+  let s = if pad {
+    if !s.len().is_multiple_of(4) {
+      return None
+    }
+    let padding = s.iter().rev().take_while(|c| **c == b'=').count();
+    if padding > 2 {
+      return None
+    }
+    &s[..s.len() - padding]
+  } else {
+    s
+  };
+  if s.len() % 4 == 1 {
+    return None
+  }
+  let mut out = Vec::with_capacity(s.len() * 3 / 4);
+  for chunk in s.chunks(4) {
+    let mut n = 0u32;
+    for (i, c) in chunk.iter().enumerate() {
+      let digit = alphabet.iter().position(|a| a == c)? as u32;
+      n |= digit << (18 - 6 * i);
+    }
+    let bytes = chunk.len() * 6 / 8;
+    if n & (0x00ff_ffff >> (8 * bytes)) != 0 {
+      return None
+    }
+    out.extend_from_slice(&n.to_be_bytes()[1..1 + bytes]);
+  }
+  Some(out)
+}
+
+/// RFC 4648 base64 with padding
+pub fn encode_base64(data: &[u8]) -> String {
+  encode_base64_with(data, BASE64_ALPHABET, true)
+}
+
+pub fn decode_base64(s: &str) -> Option<Vec<u8>> {
+  decode_base64_with(s, BASE64_ALPHABET, true)
+}
+
+const BASE64URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// RFC 4648 section 5 base64url without padding, as JOSE uses it
+pub fn encode_base64url(data: &[u8]) -> String {
+  encode_base64_with(data, BASE64URL_ALPHABET, false)
+}
+
+pub fn decode_base64url(s: &str) -> Option<Vec<u8>> {
+  decode_base64_with(s, BASE64URL_ALPHABET, false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn secret_scalar_round_trip() {
+    let bytes: [u8; 32] = core::array::from_fn(|i| i as u8 + 1);
+    let secret = SecretScalar::from_bytes_be(&bytes);
+    assert_eq!(*secret, BigInt::from_bytes_be(Sign::Plus, &bytes));
+    assert_eq!(*secret.to_bytes32(), bytes);
+    assert_eq!(*SecretScalar::new(BigInt::from(7)).to_bytes32(), int_to_bytes32(&BigInt::from(7)));
+  }
+
+  #[test]
+  fn wipe_int_zeroes_the_value() {
+    let mut n = BigInt::from_bytes_be(Sign::Plus, &[0xff; 40]);
+    wipe_int(&mut n);
+    assert!(n.is_zero());
+  }
+
+  /// RFC 9380 appendix K.1
+  #[test]
+  fn expand_message_xmd_vectors() {
+    let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+    let expand = |msg: &[u8], dst: &[u8], len| encode_hex(&expand_message_xmd(msg, dst, len).unwrap());
+    assert_eq!(expand(b"", dst, 32), "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235");
+    assert_eq!(expand(b"abc", dst, 32), "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615");
+    assert_eq!(
+      expand(b"abc", dst, 128),
+      "abba86a6129e366fc877aab32fc4ffc70120d8996c88aee2fe4b32d6c7b6437a647e6c3163d40b76a73cf6a5674ef1d8\
+       90f95b664ee0afa5359a5c4e07985635bbecbac65d747d3d2da7ec2b8221b17b0ca9dc8a1ac1c07ea6a1e60583e2cb00\
+       058e77b7b72a298425cd1b941ad4ec65e8afc50303a22c0f99b0509b4c895f40"
+    );
+    // a DST over 255 bytes is hashed first (appendix K.2)
+    let mut long_dst = b"QUUX-V01-CS02-with-expander-SHA256-128-long-DST-".to_vec();
+    long_dst.extend([b'1'; 208]);
+    assert_eq!(expand(b"abc", &long_dst, 32), "52dbf4f36cf560fca57dedec2ad924ee9c266341d8f3d6afe5171733b16bbb12");
+
+    assert_eq!(expand_message_xmd(b"abc", dst, 255 * 32).map(|out| out.len()), Some(8160));
+    assert_eq!(expand_message_xmd(b"abc", dst, 255 * 32 + 1), None);
+    assert_eq!(hash_to_field(b"abc", dst, 200, &BigInt::from(7u8), 48), None);
+  }
+}
@@ -0,0 +1,289 @@
+//! JSON Web Keys and compact JSON Web Signatures with secp256k1
+//!
+//! * JWK `{"kty":"EC","crv":"secp256k1",...}`: https://datatracker.ietf.org/doc/html/rfc7518#section-6.2
+//! * JWS `ES256K`, ECDSA over SHA-256 with a raw 64-byte `r || s`: https://datatracker.ietf.org/doc/html/rfc8812
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+use serde_json::{Map, Value};
+
+use super::{
+  constants::{N, PRIME},
+  field_elements::FieldOperation,
+  helper::{bytes_to_int, decode_base64url, encode_base64url, int_to_bytes32, sha256},
+  private_key::PrivateKey,
+  s256_field::S256Field,
+  s256_point::S256Point,
+  signature::Signature,
+};
+
+pub const ALGORITHM: &str = "ES256K";
+const KEY_TYPE: &str = "EC";
+const CURVE: &str = "secp256k1";
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum JoseError {
+  /// not JSON, not an object, or a member is missing or of the wrong type
+  InvalidJson,
+  /// a member or token part is not unpadded base64url
+  InvalidBase64,
+  /// `kty` is not `EC`
+  UnsupportedKeyType,
+  /// `crv` is not `secp256k1`
+  UnsupportedCurve,
+  /// coordinates or `d` are not 32 bytes, or not a point on the curve and a scalar in [1, n-1]
+  InvalidKey,
+  /// `d` is not the private key of `x`, `y`
+  KeyMismatch,
+  /// not three dot-separated parts
+  InvalidToken,
+  /// `alg` is not `ES256K`
+  UnsupportedAlgorithm,
+  /// the header has `crit` extensions, none of which are understood here
+  UnsupportedCritical,
+  /// not a 64-byte `r || s` that verifies with the key
+  InvalidSignature,
+}
+
+fn member_bytes(jwk: &Value, name: &str) -> Result<Vec<u8>, JoseError> {
+  let value = jwk[name].as_str().ok_or(JoseError::InvalidJson)?;
+  decode_base64url(value).ok_or(JoseError::InvalidBase64)
+}
+
+fn member_bytes32(jwk: &Value, name: &str) -> Result<[u8; 32], JoseError> {
+  member_bytes(jwk, name)?.try_into().map_err(|_| JoseError::InvalidKey)
+}
+
+fn public_members(point: &S256Point) -> Map<String, Value> {
+  let sec = point.sec(false);
+  let mut jwk = Map::new();
+  jwk.insert("kty".into(), KEY_TYPE.into());
+  jwk.insert("crv".into(), CURVE.into());
+  jwk.insert("x".into(), encode_base64url(&sec[1..33]).into());
+  jwk.insert("y".into(), encode_base64url(&sec[33..]).into());
+  jwk
+}
+
+fn parse_public_members(jwk: &Value) -> Result<S256Point, JoseError> {
+  if !jwk.is_object() {
+    return Err(JoseError::InvalidJson)
+  }
+  if jwk["kty"].as_str() != Some(KEY_TYPE) {
+    return Err(JoseError::UnsupportedKeyType)
+  }
+  if jwk["crv"].as_str() != Some(CURVE) {
+    return Err(JoseError::UnsupportedCurve)
+  }
+  let mut sec = vec![4u8];
+  sec.extend_from_slice(&member_bytes32(jwk, "x")?);
+  sec.extend_from_slice(&member_bytes32(jwk, "y")?);
+  S256Point::parse(&sec).ok_or(JoseError::InvalidKey)
+}
+
+pub fn public_key_to_jwk(point: &S256Point) -> String {
+  Value::Object(public_members(point)).to_string()
+}
+
+/// a private JWK is accepted too, its `d` is ignored
+pub fn public_key_from_jwk(data: &str) -> Result<S256Point, JoseError> {
+  let jwk: Value = serde_json::from_str(data).map_err(|_| JoseError::InvalidJson)?;
+  parse_public_members(&jwk)
+}
+
+pub fn private_key_to_jwk(key: &PrivateKey) -> String {
+  let mut jwk = public_members(&key.point);
+  jwk.insert("d".into(), encode_base64url(key.to_bytes().as_ref()).into());
+  Value::Object(jwk).to_string()
+}
+
+pub fn private_key_from_jwk(data: &str) -> Result<PrivateKey, JoseError> {
+  let jwk: Value = serde_json::from_str(data).map_err(|_| JoseError::InvalidJson)?;
+  let point = parse_public_members(&jwk)?;
+  let key = PrivateKey::from_bytes(&member_bytes32(&jwk, "d")?).map_err(|_| JoseError::InvalidKey)?;
+  if key.point != point {
+    return Err(JoseError::KeyMismatch)
+  }
+  Ok(key)
+}
+
+/// `z` for ECDSA: SHA-256 of the JWS signing input
+fn signing_hash(signing_input: &str) -> BigInt {
+  bytes_to_int(&sha256(signing_input.as_bytes()))
+}
+
+/// compact JWS of `payload`; `header` members are kept, `alg` is set to `ES256K`
+pub fn sign(key: &PrivateKey, header: &Map<String, Value>, payload: &[u8]) -> Result<String, JoseError> {
+  let mut header = header.clone();
+  match header.get("alg") {
+    None => {
+      header.insert("alg".into(), ALGORITHM.into());
+    }
+    Some(alg) if alg == ALGORITHM => {}
+    Some(_) => return Err(JoseError::UnsupportedAlgorithm),
+  }
+  let signing_input = format!(
+    "{}.{}",
+    encode_base64url(Value::Object(header).to_string().as_bytes()),
+    encode_base64url(payload)
+  );
+  let sig = key.sign(signing_hash(&signing_input));
+  let mut rs = int_to_bytes32(&sig.r.get_num()).to_vec();
+  rs.extend_from_slice(&int_to_bytes32(&sig.s.get_num()));
+  Ok(format!("{}.{}", signing_input, encode_base64url(&rs)))
+}
+
+/// the protected header of a compact JWS, unverified, e.g. to look up its `kid`
+pub fn decode_header(token: &str) -> Result<Value, JoseError> {
+  let header = token.split('.').next().ok_or(JoseError::InvalidToken)?;
+  let header = decode_base64url(header).ok_or(JoseError::InvalidBase64)?;
+  let header: Value = serde_json::from_slice(&header).map_err(|_| JoseError::InvalidJson)?;
+  if !header.is_object() {
+    return Err(JoseError::InvalidJson)
+  }
+  Ok(header)
+}
+
+/// the payload of an `ES256K` compact JWS if `point` signed it
+pub fn verify(point: &S256Point, token: &str) -> Result<Vec<u8>, JoseError> {
+  let parts: Vec<&str> = token.split('.').collect();
+  let [header, payload, signature] = parts[..] else {
+    return Err(JoseError::InvalidToken)
+  };
+  let header_json = decode_header(token)?;
+  if header_json["alg"].as_str() != Some(ALGORITHM) {
+    return Err(JoseError::UnsupportedAlgorithm)
+  }
+  if header_json.get("crit").is_some() {
+    return Err(JoseError::UnsupportedCritical)
+  }
+  let signing_input = format!("{}.{}", header, payload);
+  let payload = decode_base64url(payload).ok_or(JoseError::InvalidBase64)?;
+  let signature = decode_base64url(signature).ok_or(JoseError::InvalidBase64)?;
+  if signature.len() != 64 {
+    return Err(JoseError::InvalidSignature)
+  }
+
+  let (r, s) = (bytes_to_int(&signature[..32]), bytes_to_int(&signature[32..]));
+  if r.is_zero() || r >= *N || s.is_zero() || s >= *N {
+    return Err(JoseError::InvalidSignature)
+  }
+  let sig = Signature::new(S256Field::new(r, PRIME.clone()), S256Field::new(s, PRIME.clone()));
+  if !point.verify(signing_hash(&signing_input), sig) {
+    return Err(JoseError::InvalidSignature)
+  }
+  Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // key and tokens from the Python cryptography package
+  const JWK: &str = r#"{"kty": "EC", "crv": "secp256k1", "x": "_wCJuwaeJsaeydJx1SLI0VDXq6dO22wSTsaK8evXKw0", "y": "R-TOyM6bDKetYsyrFLJUHOzjj9qeKUVLV9pJqAc7W8U", "d": "ax4_DSxKW45_nRw6XnudHzpcfpsdP1p8nhs9X3qcHjs"}"#;
+  const SIGNING_INPUT: &str = "eyJhbGciOiJFUzI1NksiLCJ0eXAiOiJKV1QifQ.eyJzdWIiOiIxMjM0NTY3ODkwIiwiaWF0IjoxNTE2MjM5MDIyfQ";
+  const SIGNATURE: &str = "lIYy9fz94F-QY6DRve6oHQvmh0Ip4xO8eSbk_OETiYPPAXCmzVN9olGbq7BcCrnF2jWBpcIDPU-lFc2qSQWs6A";
+  const DER_SIGNATURE: &str = "MEYCIQCUhjL1_P3gX5BjoNG97qgdC-aHQinjE7x5JuT84ROJgwIhAM8BcKbNU32iUZursFwKucXaNYGlwgM9T6UVzapJBazo";
+
+  fn with_member(jwk: &str, name: &str, value: &str) -> String {
+    let mut jwk: Value = serde_json::from_str(jwk).unwrap();
+    jwk[name] = value.into();
+    jwk.to_string()
+  }
+
+  #[test]
+  fn jwk_round_trip() {
+    let key = private_key_from_jwk(JWK).unwrap();
+    assert_eq!(encode_base64url(key.to_bytes().as_ref()), "ax4_DSxKW45_nRw6XnudHzpcfpsdP1p8nhs9X3qcHjs");
+    assert_eq!(public_key_from_jwk(JWK).unwrap(), key.point);
+    assert_eq!(private_key_from_jwk(&private_key_to_jwk(&key)).unwrap().point, key.point);
+    let public = public_key_to_jwk(&key.point);
+    assert_eq!(public_key_from_jwk(&public).unwrap(), key.point);
+    assert!(serde_json::from_str::<Value>(&public).unwrap().get("d").is_none());
+    assert_eq!(private_key_from_jwk(&public).unwrap_err(), JoseError::InvalidJson);
+  }
+
+  #[test]
+  fn invalid_jwks_are_rejected() {
+    assert_eq!(public_key_from_jwk(&with_member(JWK, "kty", "OKP")).unwrap_err(), JoseError::UnsupportedKeyType);
+    assert_eq!(public_key_from_jwk(&with_member(JWK, "crv", "P-256")).unwrap_err(), JoseError::UnsupportedCurve);
+    // last byte of y and of x changed, so the point is off the curve
+    let y = with_member(JWK, "y", "R-TOyM6bDKetYsyrFLJUHOzjj9qeKUVLV9pJqAc7W8Q");
+    let x = with_member(JWK, "x", "_wCJuwaeJsaeydJx1SLI0VDXq6dO22wSTsaK8evXKww");
+    for jwk in [&y, &x] {
+      assert_eq!(public_key_from_jwk(jwk).unwrap_err(), JoseError::InvalidKey);
+      assert_eq!(private_key_from_jwk(jwk).unwrap_err(), JoseError::InvalidKey);
+    }
+    // 31 byte coordinate, bad base64url, missing member, not an object
+    assert_eq!(public_key_from_jwk(&with_member(JWK, "x", "AAAA")).unwrap_err(), JoseError::InvalidKey);
+    assert_eq!(public_key_from_jwk(&with_member(JWK, "x", "a+b/")).unwrap_err(), JoseError::InvalidBase64);
+    assert_eq!(public_key_from_jwk(r#"{"kty":"EC","crv":"secp256k1"}"#).unwrap_err(), JoseError::InvalidJson);
+    assert_eq!(public_key_from_jwk("[]").unwrap_err(), JoseError::InvalidJson);
+
+    let other = PrivateKey::try_new(&BigInt::from(7)).unwrap();
+    let d = encode_base64url(other.to_bytes().as_ref());
+    assert_eq!(private_key_from_jwk(&with_member(JWK, "d", &d)).unwrap_err(), JoseError::KeyMismatch);
+    assert_eq!(
+      private_key_from_jwk(&with_member(JWK, "d", &encode_base64url(&[0; 32]))).unwrap_err(),
+      JoseError::InvalidKey
+    );
+  }
+
+  #[test]
+  fn sign_and_verify() {
+    let key = private_key_from_jwk(JWK).unwrap();
+    let mut header = Map::new();
+    header.insert("kid".into(), "k1".into());
+    let token = sign(&key, &header, b"payload").unwrap();
+    assert_eq!(verify(&key.point, &token).unwrap(), b"payload");
+    let header = decode_header(&token).unwrap();
+    assert_eq!((header["alg"].as_str(), header["kid"].as_str()), (Some(ALGORITHM), Some("k1")));
+    assert_eq!(token.rsplit('.').next().map(|s| decode_base64url(s).unwrap().len()), Some(64));
+
+    let other = PrivateKey::try_new(&BigInt::from(7)).unwrap();
+    assert_eq!(verify(&other.point, &token).unwrap_err(), JoseError::InvalidSignature);
+    let mut es256 = Map::new();
+    es256.insert("alg".into(), "ES256".into());
+    assert_eq!(sign(&key, &es256, b"payload").unwrap_err(), JoseError::UnsupportedAlgorithm);
+  }
+
+  #[test]
+  fn verifies_foreign_token() {
+    let point = public_key_from_jwk(JWK).unwrap();
+    let token = format!("{}.{}", SIGNING_INPUT, SIGNATURE);
+    assert_eq!(verify(&point, &token).unwrap(), br#"{"sub":"1234567890","iat":1516239022}"#);
+    // the same signature as DER is not a JWS signature
+    let der = format!("{}.{}", SIGNING_INPUT, DER_SIGNATURE);
+    assert_eq!(verify(&point, &der).unwrap_err(), JoseError::InvalidSignature);
+  }
+
+  #[test]
+  fn invalid_tokens_are_rejected() {
+    let point = public_key_from_jwk(JWK).unwrap();
+    let (_, payload) = SIGNING_INPUT.split_once('.').unwrap();
+    let signature = decode_base64url(SIGNATURE).unwrap();
+
+    let none = encode_base64url(br#"{"alg":"none"}"#);
+    assert_eq!(verify(&point, &format!("{}.{}.", none, payload)).unwrap_err(), JoseError::UnsupportedAlgorithm);
+    assert_eq!(
+      verify(&point, &format!("{}.{}.{}", none, payload, SIGNATURE)).unwrap_err(),
+      JoseError::UnsupportedAlgorithm
+    );
+    let crit = encode_base64url(br#"{"alg":"ES256K","crit":["exp"],"exp":1}"#);
+    assert_eq!(
+      verify(&point, &format!("{}.{}.{}", crit, payload, SIGNATURE)).unwrap_err(),
+      JoseError::UnsupportedCritical
+    );
+
+    let with_signature = |sig: &[u8]| format!("{}.{}", SIGNING_INPUT, encode_base64url(sig));
+    assert_eq!(verify(&point, &with_signature(&signature[..63])).unwrap_err(), JoseError::InvalidSignature);
+    let mut long = signature.clone();
+    long.push(0);
+    assert_eq!(verify(&point, &with_signature(&long)).unwrap_err(), JoseError::InvalidSignature);
+    let mut zero_r = signature.clone();
+    zero_r[..32].fill(0);
+    assert_eq!(verify(&point, &with_signature(&zero_r)).unwrap_err(), JoseError::InvalidSignature);
+
+    assert_eq!(verify(&point, SIGNING_INPUT).unwrap_err(), JoseError::InvalidToken);
+    assert_eq!(verify(&point, &format!("{}.{}.", SIGNING_INPUT, SIGNATURE)).unwrap_err(), JoseError::InvalidToken);
+  }
+}
@@ -0,0 +1,538 @@
+//! Passphrase-encrypted private keys
+//!
+//! * BIP38 `6P…` strings: https://github.com/bitcoin/bips/blob/master/bip-0038.mediawiki
+//!   Encryption uses the non-EC-multiply mode; decryption also takes EC-multiplied
+//!   keys made from an intermediate code, with or without lot and sequence numbers.
+//! * JSON keystore, the Ethereum Web3 Secret Storage (V3) format:
+//!   https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/
+
+use aes::{
+  cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher},
+  Aes128, Aes256,
+};
+use num_bigint::BigInt;
+use num_traits::{Euclid, Zero};
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use salsa20::{cipher::{typenum::U4, StreamCipherCore}, SalsaCore};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use subtle::ConstantTimeEq;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use super::{
+  constants::{Gs, N},
+  helper::{bytes_to_int, decode_base58_checksum, decode_hex, encode_base58_checksum, encode_hex, hash160, hash256},
+  private_key::PrivateKey,
+  s256_point::S256Point,
+};
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+const BIP38_LENGTH: usize = 39;
+const BIP38_PREFIX: [u8; 2] = [0x01, 0x42];
+const BIP38_EC_PREFIX: [u8; 2] = [0x01, 0x43];
+const BIP38_FLAG: u8 = 0xc0;
+const BIP38_COMPRESSED: u8 = 0x20;
+const BIP38_LOT_SEQUENCE: u8 = 0x04;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum KeystoreError {
+  /// neither a BIP38 string nor a keystore JSON document, or a field is missing or malformed
+  InvalidFormat,
+  /// a kdf other than scrypt or pbkdf2 with hmac-sha256, or parameters out of range
+  UnsupportedKdf,
+  /// a cipher other than aes-128-ctr
+  UnsupportedCipher,
+  /// the BIP38 address hash or the keystore MAC does not match
+  WrongPassphrase,
+  /// the decrypted secret is not in [1, n-1]
+  InvalidKey,
+}
+
+/// key derivation for the JSON keystore
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Kdf {
+  /// n = 2^log_n
+  Scrypt { log_n: u8, r: u32, p: u32 },
+  /// PBKDF2-HMAC-SHA256 with `c` iterations
+  Pbkdf2 { c: u32 },
+}
+
+impl Default for Kdf {
+  /// geth's standard scrypt parameters
+  fn default() -> Self {
+    Kdf::Scrypt { log_n: 18, r: 8, p: 1 }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EncryptionFormat {
+  /// `6P…` string for the P2PKH address of the compressed or uncompressed key
+  Bip38 { compressed: bool },
+  /// V3 keystore JSON
+  Keystore(Kdf),
+}
+
+/// upper bound on scrypt's 128·r·n byte working buffer, against hostile keystore files
+const SCRYPT_MAX_MEMORY: usize = 1 << 30;
+/// upper bound on scrypt's p, each unit repeats the whole memory-hard mix
+const SCRYPT_MAX_P: u32 = 16;
+/// upper bound on PBKDF2 iterations, a few seconds of work
+const PBKDF2_MAX_ITERATIONS: u32 = 1 << 22;
+
+/// scryptBlockMix over 2r 64-byte blocks, even outputs first, then odd
+fn block_mix(input: &[[u32; 16]], output: &mut [[u32; 16]]) {
+  let half = input.len() / 2;
+  let mut x = input[input.len() - 1];
+  let mut block_bytes = [0u8; 64];
+  for (i, block) in input.iter().enumerate() {
+    for (x, b) in x.iter_mut().zip(block) {
+      *x ^= b;
+    }
+    // Salsa20/8 core: the keystream block of the raw state is the hash of the state
+    SalsaCore::<U4>::from_raw_state(x).write_keystream_block((&mut block_bytes).into());
+    for (x, bytes) in x.iter_mut().zip(block_bytes.chunks(4)) {
+      *x = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    output[i / 2 + (i % 2) * half] = x;
+  }
+}
+
+/// scrypt with the memory and p capped; unlike the `scrypt` crate it allows n >= 2^(16r),
+/// which RFC 7914 does not forbid and the Web3 Secret Storage example (n = 2^18, r = 1) uses
+/// https://datatracker.ietf.org/doc/html/rfc7914
+fn scrypt(password: &[u8], salt: &[u8], log_n: u8, r: u32, p: u32, out: &mut [u8]) -> Result<(), KeystoreError> {
+  let (r, p) = (r as usize, p as usize);
+  let n = 1usize.checked_shl(log_n as u32).ok_or(KeystoreError::UnsupportedKdf)?;
+  let memory = n.checked_mul(128).and_then(|m| m.checked_mul(r)).ok_or(KeystoreError::UnsupportedKdf)?;
+  if log_n == 0 || r == 0 || p == 0 || memory > SCRYPT_MAX_MEMORY || p > SCRYPT_MAX_P as usize {
+    return Err(KeystoreError::UnsupportedKdf)
+  }
+
+  let mut b = Zeroizing::new(vec![0u8; 128 * r * p]);
+  pbkdf2_hmac::<Sha256>(password, salt, 1, &mut b);
+
+  let mut x = Zeroizing::new(vec![[0u32; 16]; 2 * r]);
+  let mut y = Zeroizing::new(vec![[0u32; 16]; 2 * r]);
+  let mut v = Zeroizing::new(vec![[0u32; 16]; 2 * r * n]);
+  for chunk in b.chunks_mut(128 * r) {
+    for (word, bytes) in x.iter_mut().flatten().zip(chunk.chunks(4)) {
+      *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    // ROMix
+    for i in 0..n {
+      v[i * 2 * r..(i + 1) * 2 * r].copy_from_slice(&x);
+      block_mix(&x, &mut y);
+      std::mem::swap(&mut x, &mut y);
+    }
+    for _ in 0..n {
+      let j = x[2 * r - 1][0] as usize & (n - 1);
+      for (x, v) in x.iter_mut().flatten().zip(v[j * 2 * r..(j + 1) * 2 * r].iter().flatten()) {
+        *x ^= v;
+      }
+      block_mix(&x, &mut y);
+      std::mem::swap(&mut x, &mut y);
+    }
+    for (bytes, word) in chunk.chunks_mut(4).zip(x.iter().flatten()) {
+      bytes.copy_from_slice(&word.to_le_bytes());
+    }
+  }
+  pbkdf2_hmac::<Sha256>(password, &b, 1, out);
+  Ok(())
+}
+
+/// PBKDF2-HMAC-SHA256 with the iteration count capped
+fn pbkdf2(password: &[u8], salt: &[u8], c: u32, out: &mut [u8]) -> Result<(), KeystoreError> {
+  if c == 0 || c > PBKDF2_MAX_ITERATIONS {
+    return Err(KeystoreError::UnsupportedKdf)
+  }
+  pbkdf2_hmac::<Sha256>(password, salt, c, out);
+  Ok(())
+}
+
+/// mainnet P2PKH address
+fn p2pkh_address(point: &S256Point, compressed: bool) -> String {
+  let mut payload = vec![0x00];
+  payload.extend_from_slice(&hash160(&point.sec(compressed)));
+  encode_base58_checksum(&payload)
+}
+
+fn address_hash(point: &S256Point, compressed: bool) -> [u8; 4] {
+  let hash = hash256(p2pkh_address(point, compressed).as_bytes());
+  [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// AES-256 of `block ^ mask`, one block, no chaining
+fn aes256_encrypt_block(key: &[u8], block: &[u8], mask: &[u8]) -> [u8; 16] {
+  let mut out = [0u8; 16];
+  for i in 0..16 {
+    out[i] = block[i] ^ mask[i];
+  }
+  let cipher = Aes256::new(GenericArray::from_slice(key));
+  cipher.encrypt_block(GenericArray::from_mut_slice(&mut out));
+  out
+}
+
+/// inverse of `aes256_encrypt_block`
+fn aes256_decrypt_block(key: &[u8], block: &[u8], mask: &[u8]) -> [u8; 16] {
+  let mut out = [0u8; 16];
+  out.copy_from_slice(block);
+  let cipher = Aes256::new(GenericArray::from_slice(key));
+  cipher.decrypt_block(GenericArray::from_mut_slice(&mut out));
+  for i in 0..16 {
+    out[i] ^= mask[i];
+  }
+  out
+}
+
+fn normalize(passphrase: &str) -> Zeroizing<Vec<u8>> {
+  Zeroizing::new(passphrase.nfc().collect::<String>().into_bytes())
+}
+
+fn to_private_key(secret: BigInt) -> Result<PrivateKey, KeystoreError> {
+  if secret.is_zero() || secret >= *N {
+    return Err(KeystoreError::InvalidKey)
+  }
+  Ok(PrivateKey::new(secret))
+}
+
+pub fn encrypt_bip38(key: &PrivateKey, passphrase: &str, compressed: bool) -> String {
+  let addresshash = address_hash(&key.point, compressed);
+  let mut derived = Zeroizing::new([0u8; 64]);
+  scrypt(&normalize(passphrase), &addresshash, 14, 8, 8, derived.as_mut())
+    .expect("BIP38 scrypt parameters are valid");
+  let (derivedhalf1, derivedhalf2) = derived.split_at(32);
+  let secret = key.to_bytes();
+
+  let mut out = BIP38_PREFIX.to_vec();
+  out.push(if compressed { BIP38_FLAG | BIP38_COMPRESSED } else { BIP38_FLAG });
+  out.extend_from_slice(&addresshash);
+  out.extend_from_slice(&aes256_encrypt_block(derivedhalf2, &secret[..16], &derivedhalf1[..16]));
+  out.extend_from_slice(&aes256_encrypt_block(derivedhalf2, &secret[16..], &derivedhalf1[16..]));
+  encode_base58_checksum(&out)
+}
+
+pub fn decrypt_bip38(data: &str, passphrase: &str) -> Result<PrivateKey, KeystoreError> {
+  let data = decode_base58_checksum(data).ok_or(KeystoreError::InvalidFormat)?;
+  if data.len() != BIP38_LENGTH {
+    return Err(KeystoreError::InvalidFormat)
+  }
+  let flag = data[2];
+  let compressed = flag & BIP38_COMPRESSED != 0;
+  let addresshash = &data[3..7];
+  let passphrase = normalize(passphrase);
+
+  let key = match [data[0], data[1]] {
+    BIP38_PREFIX => {
+      if flag & !BIP38_COMPRESSED != BIP38_FLAG {
+        return Err(KeystoreError::InvalidFormat)
+      }
+      let mut derived = Zeroizing::new([0u8; 64]);
+      scrypt(&passphrase, addresshash, 14, 8, 8, derived.as_mut())?;
+      let (derivedhalf1, derivedhalf2) = derived.split_at(32);
+      let mut secret = Zeroizing::new([0u8; 32]);
+      secret[..16].copy_from_slice(&aes256_decrypt_block(derivedhalf2, &data[7..23], &derivedhalf1[..16]));
+      secret[16..].copy_from_slice(&aes256_decrypt_block(derivedhalf2, &data[23..39], &derivedhalf1[16..]));
+      to_private_key(bytes_to_int(secret.as_ref()))?
+    }
+    BIP38_EC_PREFIX => {
+      if flag & !(BIP38_COMPRESSED | BIP38_LOT_SEQUENCE) != 0 {
+        return Err(KeystoreError::InvalidFormat)
+      }
+      let ownerentropy = &data[7..15];
+      let lot_sequence = flag & BIP38_LOT_SEQUENCE != 0;
+      let ownersalt = if lot_sequence { &ownerentropy[..4] } else { ownerentropy };
+
+      let mut prefactor = Zeroizing::new([0u8; 32]);
+      scrypt(&passphrase, ownersalt, 14, 8, 8, prefactor.as_mut())?;
+      let passfactor = if lot_sequence {
+        let mut preimage = Zeroizing::new(prefactor.to_vec());
+        preimage.extend_from_slice(ownerentropy);
+        bytes_to_int(&hash256(&preimage))
+      } else {
+        bytes_to_int(prefactor.as_ref())
+      };
+      if passfactor.is_zero() || passfactor >= *N {
+        return Err(KeystoreError::InvalidKey)
+      }
+      let passpoint = Gs.rmul(passfactor.clone()).sec(true);
+
+      let mut salt = addresshash.to_vec();
+      salt.extend_from_slice(ownerentropy);
+      let mut derived = Zeroizing::new([0u8; 64]);
+      scrypt(&passpoint, &salt, 10, 1, 1, derived.as_mut())?;
+      let (derivedhalf1, derivedhalf2) = derived.split_at(32);
+
+      // encryptedpart2 = AES(encryptedpart1[8..16] || seedb[16..24] ^ derivedhalf1[16..32])
+      let part2 = aes256_decrypt_block(derivedhalf2, &data[23..39], &derivedhalf1[16..]);
+      let mut encryptedpart1 = [0u8; 16];
+      encryptedpart1[..8].copy_from_slice(&data[15..23]);
+      encryptedpart1[8..].copy_from_slice(&part2[..8]);
+      let mut seedb = Zeroizing::new([0u8; 24]);
+      seedb[..16].copy_from_slice(&aes256_decrypt_block(derivedhalf2, &encryptedpart1, &derivedhalf1[..16]));
+      seedb[16..].copy_from_slice(&part2[8..]);
+
+      let factorb = bytes_to_int(&hash256(seedb.as_ref()));
+      to_private_key(Euclid::rem_euclid(&(passfactor * factorb), &N))?
+    }
+    _ => return Err(KeystoreError::InvalidFormat),
+  };
+
+  if address_hash(&key.point, compressed) != addresshash {
+    return Err(KeystoreError::WrongPassphrase)
+  }
+  Ok(key)
+}
+
+/// Ethereum address, the last 20 bytes of keccak256 of the uncompressed key without its prefix
+fn ethereum_address(point: &S256Point) -> [u8; 20] {
+  let hash = Keccak256::digest(&point.sec(false)[1..]);
+  let mut address = [0u8; 20];
+  address.copy_from_slice(&hash[12..]);
+  address
+}
+
+fn keystore_mac(derived_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+  let mut hasher = Keccak256::new();
+  hasher.update(&derived_key[16..32]);
+  hasher.update(ciphertext);
+  hasher.finalize().into()
+}
+
+/// random (version 4) UUID
+fn uuid_v4() -> String {
+  let mut bytes = [0u8; 16];
+  rand::thread_rng().fill_bytes(&mut bytes);
+  bytes[6] = (bytes[6] & 0x0f) | 0x40;
+  bytes[8] = (bytes[8] & 0x3f) | 0x80;
+  let hex = encode_hex(&bytes);
+  format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// fails with `UnsupportedKdf` when the parameters are out of range
+pub fn encrypt_keystore(key: &PrivateKey, passphrase: &str, kdf: Kdf) -> Result<String, KeystoreError> {
+  let mut rng = rand::thread_rng();
+  let (mut salt, mut iv) = ([0u8; 32], [0u8; 16]);
+  rng.fill_bytes(&mut salt);
+  rng.fill_bytes(&mut iv);
+
+  let mut derived_key = Zeroizing::new([0u8; 32]);
+  let kdfparams = match kdf {
+    Kdf::Scrypt { log_n, r, p } => {
+      scrypt(passphrase.as_bytes(), &salt, log_n, r, p, derived_key.as_mut())?;
+      json!({ "dklen": 32, "n": 1u64 << log_n, "r": r, "p": p, "salt": encode_hex(&salt) })
+    }
+    Kdf::Pbkdf2 { c } => {
+      pbkdf2(passphrase.as_bytes(), &salt, c, derived_key.as_mut())?;
+      json!({ "dklen": 32, "c": c, "prf": "hmac-sha256", "salt": encode_hex(&salt) })
+    }
+  };
+
+  let mut ciphertext = *key.to_bytes();
+  Aes128Ctr::new(GenericArray::from_slice(&derived_key[..16]), GenericArray::from_slice(&iv))
+    .apply_keystream(&mut ciphertext);
+  let mac = keystore_mac(derived_key.as_ref(), &ciphertext);
+
+  Ok(json!({
+    "version": 3,
+    "id": uuid_v4(),
+    "address": encode_hex(&ethereum_address(&key.point)),
+    "crypto": {
+      "cipher": "aes-128-ctr",
+      "cipherparams": { "iv": encode_hex(&iv) },
+      "ciphertext": encode_hex(&ciphertext),
+      "kdf": match kdf { Kdf::Scrypt { .. } => "scrypt", Kdf::Pbkdf2 { .. } => "pbkdf2" },
+      "kdfparams": kdfparams,
+      "mac": encode_hex(&mac),
+    },
+  })
+  .to_string())
+}
+
+fn hex_field(value: &Value, field: &str) -> Result<Vec<u8>, KeystoreError> {
+  value[field].as_str().and_then(decode_hex).ok_or(KeystoreError::InvalidFormat)
+}
+
+fn u32_field(value: &Value, field: &str) -> Result<u32, KeystoreError> {
+  value[field]
+    .as_u64()
+    .and_then(|v| u32::try_from(v).ok())
+    .ok_or(KeystoreError::InvalidFormat)
+}
+
+pub fn decrypt_keystore(data: &str, passphrase: &str) -> Result<PrivateKey, KeystoreError> {
+  let keystore: Value = serde_json::from_str(data).map_err(|_| KeystoreError::InvalidFormat)?;
+  if keystore["version"].as_u64() != Some(3) {
+    return Err(KeystoreError::InvalidFormat)
+  }
+  // geth wrote "Crypto" before settling on "crypto"
+  let crypto = match (&keystore["crypto"], &keystore["Crypto"]) {
+    (Value::Object(_), _) => &keystore["crypto"],
+    (_, Value::Object(_)) => &keystore["Crypto"],
+    _ => return Err(KeystoreError::InvalidFormat),
+  };
+  if crypto["cipher"].as_str() != Some("aes-128-ctr") {
+    return Err(KeystoreError::UnsupportedCipher)
+  }
+  let kdfparams = &crypto["kdfparams"];
+  let salt = hex_field(kdfparams, "salt")?;
+  let dklen = u32_field(kdfparams, "dklen")?;
+  if dklen != 32 {
+    return Err(KeystoreError::UnsupportedKdf)
+  }
+
+  let mut derived_key = Zeroizing::new([0u8; 32]);
+  match crypto["kdf"].as_str() {
+    Some("scrypt") => {
+      let n = kdfparams["n"].as_u64().ok_or(KeystoreError::InvalidFormat)?;
+      if !n.is_power_of_two() || n < 2 {
+        return Err(KeystoreError::UnsupportedKdf)
+      }
+      let (r, p) = (u32_field(kdfparams, "r")?, u32_field(kdfparams, "p")?);
+      scrypt(passphrase.as_bytes(), &salt, n.trailing_zeros() as u8, r, p, derived_key.as_mut())?;
+    }
+    Some("pbkdf2") => {
+      if kdfparams["prf"].as_str() != Some("hmac-sha256") {
+        return Err(KeystoreError::UnsupportedKdf)
+      }
+      pbkdf2(passphrase.as_bytes(), &salt, u32_field(kdfparams, "c")?, derived_key.as_mut())?;
+    }
+    _ => return Err(KeystoreError::UnsupportedKdf),
+  }
+
+  let ciphertext = hex_field(crypto, "ciphertext")?;
+  let iv = hex_field(&crypto["cipherparams"], "iv")?;
+  let mac = hex_field(crypto, "mac")?;
+  if ciphertext.len() != 32 || iv.len() != 16 {
+    return Err(KeystoreError::InvalidFormat)
+  }
+  if !bool::from(keystore_mac(derived_key.as_ref(), &ciphertext)[..].ct_eq(&mac)) {
+    return Err(KeystoreError::WrongPassphrase)
+  }
+
+  let mut secret = Zeroizing::new([0u8; 32]);
+  secret.copy_from_slice(&ciphertext);
+  Aes128Ctr::new(GenericArray::from_slice(&derived_key[..16]), GenericArray::from_slice(&iv))
+    .apply_keystream(secret.as_mut());
+  to_private_key(bytes_to_int(secret.as_ref()))
+}
+
+pub fn encrypt(key: &PrivateKey, passphrase: &str, format: EncryptionFormat) -> Result<String, KeystoreError> {
+  match format {
+    EncryptionFormat::Bip38 { compressed } => Ok(encrypt_bip38(key, passphrase, compressed)),
+    EncryptionFormat::Keystore(kdf) => encrypt_keystore(key, passphrase, kdf),
+  }
+}
+
+/// JSON documents are read as keystores, anything else as BIP38
+pub fn decrypt(data: &str, passphrase: &str) -> Result<PrivateKey, KeystoreError> {
+  let data = data.trim();
+  if data.starts_with('{') {
+    decrypt_keystore(data, passphrase)
+  } else {
+    decrypt_bip38(data, passphrase)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(encrypted: &str, passphrase: &str, secret: &str) {
+    let key = decrypt(encrypted, passphrase).unwrap();
+    assert_eq!(encode_hex(key.to_bytes().as_ref()), secret.to_lowercase());
+  }
+
+  /// RFC 7914 section 12
+  #[test]
+  fn scrypt_test_vectors() {
+    let mut out = [0u8; 64];
+    scrypt(b"", b"", 4, 1, 1, &mut out).unwrap();
+    assert_eq!(encode_hex(&out), "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+      fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906");
+    scrypt(b"password", b"NaCl", 10, 8, 16, &mut out).unwrap();
+    assert_eq!(encode_hex(&out), "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+      2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640");
+  }
+
+  /// BIP38 test vectors, non-EC-multiplied
+  #[test]
+  fn bip38_round_trip() {
+    let vectors = [
+      ("6PRVWUbkzzsbcVac2qwfssoUJAN1Xhrg6bNk8J7Nzm5H7kxEbn2Nh2ZoGg", "TestingOneTwoThree",
+        "CBF4B9F70470856BB4F40F80B87EDB90865997FFEE6DF315AB166D713AF433A5", false),
+      ("6PRW5o9FLp4gJDDVqJQKJFTpMvdsSGJxMYHtHaQBF3ooa8mwD69bapcDQn", "\u{03D2}\u{0301}\u{0000}\u{10400}\u{1F4A9}",
+        "64EEAB5F9BE2A01A8365A579511EB3373C87C40DA6D2A25F05BDA68FE077B66E", false),
+      ("6PYLtMnXvfG3oJde97zRyLYFZCYizPU5T3LwgdYJz1fRhh16bU7u6PPmY7", "Satoshi",
+        "09C2686880095B1A4C249EE3AC4EEA8A014F11E6F986D0B5025AC1F39AFBD9AE", true),
+    ];
+    for (encrypted, passphrase, secret, compressed) in vectors {
+      check(encrypted, passphrase, secret);
+      let key = decrypt(encrypted, passphrase).unwrap();
+      assert_eq!(encrypt_bip38(&key, passphrase, compressed), encrypted);
+    }
+    assert_eq!(decrypt(vectors[0].0, "wrong").unwrap_err(), KeystoreError::WrongPassphrase);
+  }
+
+  /// BIP38 test vectors, EC-multiplied, without and with lot and sequence numbers
+  #[test]
+  fn bip38_ec_multiplied() {
+    check("6PfQu77ygVyJLZjfvMLyhLMQbYnu5uguoJJ4kMCLqWwPEdfpwANVS76gTX", "TestingOneTwoThree",
+      "A43A940577F4E97F5C4D39EB14FF083A98187C64EA7C99EF7CE460833959A519");
+    check("6PgNBNNzDkKdhkT6uJntUXwwzQV8Rr2tZcbkDcuC9DZRsS6AtHts4Ypo1j", "MOLON LABE",
+      "44EA95AFBF138356A05EA32110DFD627232D0F2991AD221187BE356F19FA8190");
+  }
+
+  const SECRET: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+  /// the PBKDF2 and scrypt examples of the Web3 Secret Storage definition
+  #[test]
+  fn web3_secret_storage_examples() {
+    let pbkdf2 = r#"{"crypto":{"cipher":"aes-128-ctr","cipherparams":{"iv":"6087dab2f9fdbbfaddc31a909735c1e6"},
+      "ciphertext":"5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46","kdf":"pbkdf2",
+      "kdfparams":{"c":262144,"dklen":32,"prf":"hmac-sha256","salt":"ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"},
+      "mac":"517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"},
+      "id":"3198bc9c-6672-5ab3-d995-4942343ae5b6","version":3}"#;
+    check(pbkdf2, "testpassword", SECRET);
+    assert_eq!(decrypt(pbkdf2, "nope").unwrap_err(), KeystoreError::WrongPassphrase);
+
+    // n = 2^18 with r = 1, beyond the n < 2^(16r) some scrypt implementations impose
+    let scrypt = r#"{"crypto":{"cipher":"aes-128-ctr","cipherparams":{"iv":"83dbcc02d8ccb40e466191a123791e0e"},
+      "ciphertext":"d172bf743a674da9cdad04534d56926ef8358534d458fffccd4e6ad2fbde479c","kdf":"scrypt",
+      "kdfparams":{"dklen":32,"n":262144,"p":8,"r":1,"salt":"ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"},
+      "mac":"2103ac29920d71da29f15d75b4a16dbe95cfd7ff8faea1056c33131d846e3097"},
+      "id":"3198bc9c-6672-5ab3-d995-4942343ae5b6","version":3}"#;
+    check(scrypt, "testpassword", SECRET);
+  }
+
+  #[test]
+  fn keystore_round_trip() {
+    let key = PrivateKey::from_bytes(&decode_hex(SECRET).unwrap().try_into().unwrap()).unwrap();
+    for kdf in [Kdf::Pbkdf2 { c: 1000 }, Kdf::Scrypt { log_n: 12, r: 8, p: 1 }] {
+      let json = encrypt_keystore(&key, "pw", kdf).unwrap();
+      check(&json, "pw", SECRET);
+    }
+  }
+
+  #[test]
+  fn rejects_costly_kdf_parameters() {
+    let key = PrivateKey::from_bytes(&[1u8; 32]).unwrap();
+    for kdf in [
+      Kdf::Pbkdf2 { c: 0 },
+      Kdf::Pbkdf2 { c: PBKDF2_MAX_ITERATIONS + 1 },
+      Kdf::Scrypt { log_n: 0, r: 8, p: 1 },
+      Kdf::Scrypt { log_n: 21, r: 8, p: 1 },
+      Kdf::Scrypt { log_n: 10, r: 8, p: SCRYPT_MAX_P + 1 },
+    ] {
+      assert_eq!(encrypt_keystore(&key, "pw", kdf).unwrap_err(), KeystoreError::UnsupportedKdf);
+    }
+
+    let json = encrypt_keystore(&key, "pw", Kdf::Pbkdf2 { c: 1 }).unwrap();
+    let hostile = json.replace(r#""c":1,"#, r#""c":4294967295,"#);
+    assert_ne!(hostile, json);
+    assert_eq!(decrypt(&hostile, "pw").unwrap_err(), KeystoreError::UnsupportedKdf);
+  }
+}
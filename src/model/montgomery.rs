@@ -0,0 +1,276 @@
+//! X25519 and X448 Diffie-Hellman on the Montgomery curves curve25519 and curve448
+//! https://datatracker.ietf.org/doc/html/rfc7748
+//!
+//! Only u coordinates are used, with the Montgomery ladder of RFC 7748 section 5.
+//! The `BigInt` field arithmetic is variable-time, so secret scalars can leak through timing.
+
+use std::marker::PhantomData;
+
+use num_bigint::{BigInt, Sign};
+use num_traits::One;
+use once_cell::sync::Lazy;
+use zeroize::Zeroizing;
+
+use super::{
+  edwards::Fp25519,
+  field_elements::{Fe, Field, FieldOperation, PrimeField},
+  helper::le_bytes_to_int,
+};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum MontgomeryError {
+  /// the peer key is a point of small order, so the shared secret is all zeros
+  LowOrderPoint,
+}
+
+/// F_p, p = 2^448 - 2^224 - 1, the field of curve448
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fp448;
+
+impl Field for Fp448 {
+  fn field() -> &'static PrimeField {
+    static FIELD: Lazy<PrimeField> = Lazy::new(|| PrimeField::new(
+      "2^448 - 2^224 - 1",
+      (BigInt::one() << 448) - (BigInt::one() << 224) - 1,
+    ));
+    &FIELD
+  }
+}
+
+struct MontgomeryCurve<F: Field> {
+  /// (A - 2) / 4
+  a24: u32,
+  /// scalar bits, the number of ladder steps
+  bits: u64,
+  /// encoded length of scalars and u coordinates
+  len: usize,
+  field: PhantomData<F>,
+}
+
+const CURVE25519: MontgomeryCurve<Fp25519> = MontgomeryCurve {
+  a24: 121665,
+  bits: 255,
+  len: 32,
+  field: PhantomData,
+};
+
+const CURVE448: MontgomeryCurve<Fp448> = MontgomeryCurve {
+  a24: 39081,
+  bits: 448,
+  len: 56,
+  field: PhantomData,
+};
+
+pub const X25519_BASEPOINT: [u8; 32] = {
+  let mut u = [0u8; 32];
+  u[0] = 9;
+  u
+};
+
+pub const X448_BASEPOINT: [u8; 56] = {
+  let mut u = [0u8; 56];
+  u[0] = 5;
+  u
+};
+
+impl<F: Field> MontgomeryCurve<F> {
+  fn fe(&self, n: u32) -> Fe<F> {
+    Fe::from_int(&BigInt::from(n))
+  }
+
+  /// little-endian u, the unused top bit of a 255-bit field ignored; values >= p are reduced
+  fn decode_u(&self, u: &[u8]) -> Fe<F> {
+    let mut u = u.to_vec();
+    if !self.bits.is_multiple_of(8) {
+      u[self.len - 1] &= (1u8 << (self.bits % 8)) - 1;
+    }
+    Fe::from_int(&le_bytes_to_int(&u))
+  }
+
+  fn encode_u(&self, u: &Fe<F>) -> Vec<u8> {
+    let mut out = u.get_num().to_bytes_le().1;
+    out.resize(self.len, 0);
+    out
+  }
+
+  /// RFC 7748 decodeScalar25519 / decodeScalar448: cofactor bits cleared, top bit set
+  fn decode_scalar(&self, k: &[u8]) -> BigInt {
+    let mut k = Zeroizing::new(k.to_vec());
+    if self.len == 32 {
+      k[0] &= 248;
+      k[31] &= 127;
+      k[31] |= 64;
+    } else {
+      k[0] &= 252;
+      k[55] |= 128;
+    }
+    le_bytes_to_int(&k)
+  }
+
+  /// `a` and `b` exchanged when `swap` is 1, as RFC 7748 section 5 does it:
+  /// `dummy = mask(swap) AND (a XOR b)` is XORed into both, limb by limb
+  fn cswap(&self, swap: u8, a: Fe<F>, b: Fe<F>) -> (Fe<F>, Fe<F>) {
+    let mask = 0u32.wrapping_sub(swap as u32);
+    let limbs = |x: &Fe<F>| {
+      let mut limbs = Zeroizing::new(x.get_num().iter_u32_digits().collect::<Vec<u32>>());
+      limbs.resize(self.len / 4, 0);
+      limbs
+    };
+    let (mut a, mut b) = (limbs(&a), limbs(&b));
+    for (a_i, b_i) in a.iter_mut().zip(b.iter_mut()) {
+      let dummy = mask & (*a_i ^ *b_i);
+      *a_i ^= dummy;
+      *b_i ^= dummy;
+    }
+    (Fe::from_int(&BigInt::from_slice(Sign::Plus, &a)), Fe::from_int(&BigInt::from_slice(Sign::Plus, &b)))
+  }
+
+  /// RFC 7748 section 5 Montgomery ladder
+  fn scalar_mult(&self, k: &[u8], u: &[u8]) -> Vec<u8> {
+    let k = self.decode_scalar(k);
+    let x1 = self.decode_u(u);
+    let a24 = self.fe(self.a24);
+    let (mut x2, mut z2) = (self.fe(1), self.fe(0));
+    let (mut x3, mut z3) = (x1.clone(), self.fe(1));
+    let mut swap = 0u8;
+
+    for t in (0..self.bits).rev() {
+      let k_t = k.bit(t) as u8;
+      swap ^= k_t;
+      (x2, x3) = self.cswap(swap, x2, x3);
+      (z2, z3) = self.cswap(swap, z2, z3);
+      swap = k_t;
+
+      let a = x2.add(&z2);
+      let aa = a.mul(&a);
+      let b = x2.sub(&z2);
+      let bb = b.mul(&b);
+      let e = aa.sub(&bb);
+      let c = x3.add(&z3);
+      let d = x3.sub(&z3);
+      let da = d.mul(&a);
+      let cb = c.mul(&b);
+      let sum = da.add(&cb);
+      let diff = da.sub(&cb);
+      x3 = sum.mul(&sum);
+      z3 = x1.mul(&diff.mul(&diff));
+      x2 = aa.mul(&bb);
+      z2 = e.mul(&aa.add(&a24.mul(&e)));
+    }
+    (x2, _) = self.cswap(swap, x2, x3);
+    (z2, _) = self.cswap(swap, z2, z3);
+
+    // z2^(p-2) is 0 for z2 = 0, so the point at infinity encodes as u = 0
+    self.encode_u(&x2.mul(&z2.pow(&F::field().p - BigInt::from(2u8))))
+  }
+
+  fn shared_secret(&self, secret: &[u8], peer: &[u8]) -> Result<Zeroizing<Vec<u8>>, MontgomeryError> {
+    let shared = Zeroizing::new(self.scalar_mult(secret, peer));
+    if shared.iter().all(|b| *b == 0) {
+      return Err(MontgomeryError::LowOrderPoint)
+    }
+    Ok(shared)
+  }
+}
+
+/// the X25519 function: scalar `k` times the point with u coordinate `u`
+pub fn x25519(k: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+  CURVE25519.scalar_mult(k, u).try_into().expect("32 bytes")
+}
+
+pub fn x25519_public_key(secret: &[u8; 32]) -> [u8; 32] {
+  x25519(secret, &X25519_BASEPOINT)
+}
+
+/// X25519(secret, peer), rejected if it is all zeros (RFC 7748 section 6.1)
+pub fn x25519_shared_secret(secret: &[u8; 32], peer: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>, MontgomeryError> {
+  let shared = CURVE25519.shared_secret(secret, peer)?;
+  let mut out = Zeroizing::new([0u8; 32]);
+  out.copy_from_slice(&shared);
+  Ok(out)
+}
+
+/// the X448 function: scalar `k` times the point with u coordinate `u`
+pub fn x448(k: &[u8; 56], u: &[u8; 56]) -> [u8; 56] {
+  CURVE448.scalar_mult(k, u).try_into().expect("56 bytes")
+}
+
+pub fn x448_public_key(secret: &[u8; 56]) -> [u8; 56] {
+  x448(secret, &X448_BASEPOINT)
+}
+
+/// X448(secret, peer), rejected if it is all zeros (RFC 7748 section 6.2)
+pub fn x448_shared_secret(secret: &[u8; 56], peer: &[u8; 56]) -> Result<Zeroizing<[u8; 56]>, MontgomeryError> {
+  let shared = CURVE448.shared_secret(secret, peer)?;
+  let mut out = Zeroizing::new([0u8; 56]);
+  out.copy_from_slice(&shared);
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::helper::{decode_hex, encode_hex};
+
+  fn bytes<const N: usize>(hex: &str) -> [u8; N] {
+    decode_hex(hex).unwrap().try_into().unwrap()
+  }
+
+  #[test]
+  fn cswap_swaps_only_when_set() {
+    // zero, one limb and a full width value
+    let values = [
+      Fe::<Fp448>::zero(),
+      CURVE448.fe(7),
+      Fe::from_int(&(&Fp448::field().p - BigInt::one())),
+    ];
+    for a in &values {
+      for b in &values {
+        assert_eq!(CURVE448.cswap(0, a.clone(), b.clone()), (a.clone(), b.clone()));
+        assert_eq!(CURVE448.cswap(1, a.clone(), b.clone()), (b.clone(), a.clone()));
+      }
+    }
+    let (a, b) = (CURVE25519.fe(1), Fe::from_int(&(BigInt::one() << 254)));
+    assert_eq!(CURVE25519.cswap(1, a.clone(), b.clone()), (b, a));
+  }
+
+  /// RFC 7748 section 5.2
+  #[test]
+  fn rfc7748_scalar_mult() {
+    let out = x25519(
+      &bytes("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+      &bytes("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c"),
+    );
+    assert_eq!(encode_hex(&out), "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552");
+    let out = x448(
+      &bytes("3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3"),
+      &bytes("06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086"),
+    );
+    assert_eq!(
+      encode_hex(&out),
+      "ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaadeb445fc66a01b0779d98223961111e21766282f73dd96b6f"
+    );
+  }
+
+  /// RFC 7748 section 6.1
+  #[test]
+  fn rfc7748_x25519_key_exchange() {
+    let alice: [u8; 32] = bytes("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+    let bob: [u8; 32] = bytes("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+    let alice_public = x25519_public_key(&alice);
+    let bob_public = x25519_public_key(&bob);
+    assert_eq!(encode_hex(&alice_public), "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+    assert_eq!(encode_hex(&bob_public), "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+    let shared = x25519_shared_secret(&alice, &bob_public).unwrap();
+    assert_eq!(encode_hex(shared.as_ref()), "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+    assert_eq!(*x25519_shared_secret(&bob, &alice_public).unwrap(), *shared);
+  }
+
+  #[test]
+  fn low_order_points_are_rejected() {
+    assert_eq!(x25519_shared_secret(&[1u8; 32], &[0u8; 32]), Err(MontgomeryError::LowOrderPoint));
+    let mut one = [0u8; 56];
+    one[0] = 1;
+    assert_eq!(x448_shared_secret(&[1u8; 56], &one), Err(MontgomeryError::LowOrderPoint));
+  }
+}
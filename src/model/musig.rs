@@ -0,0 +1,541 @@
+//! MuSig2 n-of-n multi-signatures
+//! https://github.com/bitcoin/bips/blob/master/bip-0327.mediawiki
+//!
+//! round 1: every signer calls `nonce_gen` and shares the `PublicNonce`
+//! round 2: every signer builds the same `Session` and calls `Session::sign`,
+//!          which consumes the `SecretNonce` so it can never be used twice
+
+use num_bigint::BigInt;
+use num_traits::{Euclid, One, Zero};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+use super::{
+  constants::{Gs, N, PRIME},
+  field_elements::FieldOperation,
+  helper::{bytes_to_int, int_to_bytes32, tagged_hash, SecretScalar},
+  private_key::PrivateKey,
+  schnorr::{xonly, SchnorrSignature},
+  s256_field::S256Field,
+  s256_point::S256Point,
+};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum MusigError {
+  /// a public key is the point at infinity
+  InvalidPublicKey,
+  /// the keys aggregate to the point at infinity
+  InfiniteAggregateKey,
+  /// tweak is not less than n or makes the aggregate key infinity
+  InvalidTweak,
+  /// the signing key is not one of the aggregated keys or does not match the nonce
+  UnknownSigner,
+  /// the partial signature just produced does not verify against the session
+  InvalidPartialSignature,
+}
+
+/// KeySort: sort public keys by their compressed encoding, infinity (33 zero bytes) first
+pub fn key_sort(pubkeys: &[S256Point]) -> Vec<S256Point> {
+  let mut sorted = pubkeys.to_vec();
+  sorted.sort_by_key(cbytes_ext);
+  sorted
+}
+
+fn neg_one_if_odd(point: &S256Point) -> BigInt {
+  if point.has_even_y() { BigInt::one() } else { N.clone() - BigInt::one() }
+}
+
+/// KeyAgg context: aggregate key Q with the accumulated tweak state (gacc, tacc)
+#[derive(Debug, Clone)]
+pub struct KeyAggContext {
+  pubkeys: Vec<Vec<u8>>,
+  pk_list_hash: [u8; 32],
+  second_key: Vec<u8>,
+  q: S256Point,
+  gacc: BigInt,
+  tacc: BigInt,
+}
+
+impl KeyAggContext {
+  pub fn new(points: &[S256Point]) -> Result<Self, MusigError> {
+    if points.iter().any(|p| p.is_infinity()) {
+      return Err(MusigError::InvalidPublicKey)
+    }
+    let pubkeys: Vec<Vec<u8>> = points.iter().map(|p| p.sec(true)).collect();
+    let pk_list_hash = tagged_hash("KeyAgg list", &pubkeys.concat());
+    let second_key = pubkeys.iter()
+      .find(|pk| Some(*pk) != pubkeys.first())
+      .cloned()
+      .unwrap_or_else(|| vec![0u8; 33]);
+
+    let mut ctx = Self {
+      pubkeys, pk_list_hash, second_key,
+      q: S256Point::infinity(),
+      gacc: BigInt::one(),
+      tacc: BigInt::zero(),
+    };
+    let terms: Vec<(BigInt, S256Point)> = ctx.pubkeys.iter()
+      .map(|pk| ctx.coefficient(pk))
+      .zip(points.iter().cloned())
+      .collect();
+    ctx.q = S256Point::multi_mul(&terms);
+    if ctx.q == S256Point::infinity() {
+      return Err(MusigError::InfiniteAggregateKey)
+    }
+    Ok(ctx)
+  }
+
+  /// KeyAggCoeff: 1 for the second distinct key, hash_KeyAgg coefficient(L || pk) otherwise
+  fn coefficient(&self, pk: &[u8]) -> BigInt {
+    if pk == self.second_key.as_slice() {
+      return BigInt::one()
+    }
+    let mut data = self.pk_list_hash.to_vec();
+    data.extend_from_slice(pk);
+    Euclid::rem_euclid(&bytes_to_int(&tagged_hash("KeyAgg coefficient", &data)), &N)
+  }
+
+  /// plain tweak (BIP32) or x-only tweak (BIP341) of the aggregate key
+  pub fn apply_tweak(&self, tweak: &[u8; 32], is_xonly: bool) -> Result<Self, MusigError> {
+    let g = if is_xonly { neg_one_if_odd(&self.q) } else { BigInt::one() };
+    let t = bytes_to_int(tweak);
+    if t >= *N {
+      return Err(MusigError::InvalidTweak)
+    }
+    let q = S256Point::multi_mul(&[(g.clone(), self.q.clone()), (t.clone(), Gs.clone())]);
+    if q == S256Point::infinity() {
+      return Err(MusigError::InvalidTweak)
+    }
+    Ok(Self {
+      q,
+      gacc: Euclid::rem_euclid(&(&g * &self.gacc), &N),
+      tacc: Euclid::rem_euclid(&(t + &g * &self.tacc), &N),
+      ..self.clone()
+    })
+  }
+
+  /// aggregate public key Q
+  pub fn aggregated_pubkey(&self) -> &S256Point {
+    &self.q
+  }
+}
+
+/// secnonce: k1, k2 and the signer's public key. Deliberately neither `Clone` nor
+/// serializable; `Session::sign` takes it by value and k1, k2 are wiped on drop.
+#[derive(Debug)]
+pub struct SecretNonce {
+  k1: SecretScalar,
+  k2: SecretScalar,
+  pubkey: S256Point,
+}
+
+/// pubnonce: cbytes(k1 G) || cbytes(k2 G)
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PublicNonce {
+  pub r1: S256Point,
+  pub r2: S256Point,
+}
+
+impl PublicNonce {
+  pub fn parse(bytes: &[u8; 66]) -> Option<Self> {
+    Some(Self { r1: S256Point::parse(&bytes[..33])?, r2: S256Point::parse(&bytes[33..])? })
+  }
+
+  pub fn serialize(&self) -> [u8; 66] {
+    let mut out = [0u8; 66];
+    out[..33].copy_from_slice(&self.r1.sec(true));
+    out[33..].copy_from_slice(&self.r2.sec(true));
+    out
+  }
+}
+
+/// aggnonce: sum of the public nonces, either half may be infinity
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AggregateNonce {
+  pub r1: S256Point,
+  pub r2: S256Point,
+}
+
+/// cbytes_ext: 33 zero bytes for infinity
+fn cbytes_ext(point: &S256Point) -> Vec<u8> {
+  if *point == S256Point::infinity() { vec![0u8; 33] } else { point.sec(true) }
+}
+
+fn cpoint_ext(bytes: &[u8]) -> Option<S256Point> {
+  if bytes.iter().all(|b| *b == 0) { Some(S256Point::infinity()) } else { S256Point::parse(bytes) }
+}
+
+impl AggregateNonce {
+  pub fn parse(bytes: &[u8; 66]) -> Option<Self> {
+    Some(Self { r1: cpoint_ext(&bytes[..33])?, r2: cpoint_ext(&bytes[33..])? })
+  }
+
+  pub fn serialize(&self) -> [u8; 66] {
+    let mut out = [0u8; 66];
+    out[..33].copy_from_slice(&cbytes_ext(&self.r1));
+    out[33..].copy_from_slice(&cbytes_ext(&self.r2));
+    out
+  }
+}
+
+/// NonceGen with fresh randomness from the thread rng
+pub fn nonce_gen(
+  key: Option<&PrivateKey>,
+  pubkey: &S256Point,
+  aggpk: Option<&[u8; 32]>,
+  msg: Option<&[u8]>,
+  extra_in: Option<&[u8]>,
+) -> (SecretNonce, PublicNonce) {
+  let mut rand_ = [0u8; 32];
+  rand::thread_rng().fill_bytes(&mut rand_);
+  nonce_gen_internal(&rand_, key, pubkey, aggpk, msg, extra_in)
+}
+
+/// NonceGenInternal: `rand_` must never repeat, so only `nonce_gen` and the
+/// test vectors call it
+pub(crate) fn nonce_gen_internal(
+  rand_: &[u8; 32],
+  key: Option<&PrivateKey>,
+  pubkey: &S256Point,
+  aggpk: Option<&[u8; 32]>,
+  msg: Option<&[u8]>,
+  extra_in: Option<&[u8]>,
+) -> (SecretNonce, PublicNonce) {
+  let rand = match key {
+    Some(key_) => {
+      let mut rand = key_.to_bytes();
+      for (r_, a_) in rand.iter_mut().zip(tagged_hash("MuSig/aux", rand_)) {
+        *r_ ^= a_;
+      }
+      rand
+    },
+    None => Zeroizing::new(*rand_)
+  };
+  let pk = pubkey.sec(true);
+  let aggpk: &[u8] = match aggpk { Some(aggpk_) => aggpk_, None => &[] };
+  let msg_prefixed = match msg {
+    Some(msg_) => {
+      let mut out = vec![1u8];
+      out.extend_from_slice(&(msg_.len() as u64).to_be_bytes());
+      out.extend_from_slice(msg_);
+      out
+    },
+    None => vec![0u8]
+  };
+  let extra_in = extra_in.unwrap_or(&[]);
+
+  let nonce_hash = |i: u8| {
+    let mut data = rand.to_vec();
+    data.push(pk.len() as u8);
+    data.extend_from_slice(&pk);
+    data.push(aggpk.len() as u8);
+    data.extend_from_slice(aggpk);
+    data.extend_from_slice(&msg_prefixed);
+    data.extend_from_slice(&(extra_in.len() as u32).to_be_bytes());
+    data.extend_from_slice(extra_in);
+    data.push(i);
+    let k = SecretScalar::from_bytes_be(&tagged_hash("MuSig/nonce", &data));
+    SecretScalar::new(Euclid::rem_euclid(&*k, &N))
+  };
+  let (k1, k2) = (nonce_hash(0), nonce_hash(1));
+  if k1.is_zero() || k2.is_zero() {
+    panic!("nonce is zero")
+  }
+
+  let pubnonce = PublicNonce { r1: Gs.rmul(k1.clone_int()), r2: Gs.rmul(k2.clone_int()) };
+  (SecretNonce { k1, k2, pubkey: pubkey.clone() }, pubnonce)
+}
+
+/// NonceAgg
+pub fn nonce_agg(pubnonces: &[PublicNonce]) -> AggregateNonce {
+  let mut aggnonce = AggregateNonce { r1: S256Point::infinity(), r2: S256Point::infinity() };
+  for pubnonce in pubnonces {
+    aggnonce.r1 = aggnonce.r1.add(&pubnonce.r1);
+    aggnonce.r2 = aggnonce.r2.add(&pubnonce.r2);
+  }
+  aggnonce
+}
+
+/// s of a partial signature, less than n
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PartialSignature {
+  s: BigInt,
+}
+
+impl PartialSignature {
+  pub fn parse(bytes: &[u8; 32]) -> Option<Self> {
+    let s = bytes_to_int(bytes);
+    if s >= *N {
+      return None
+    }
+    Some(Self { s })
+  }
+
+  pub fn serialize(&self) -> [u8; 32] {
+    int_to_bytes32(&self.s)
+  }
+}
+
+/// session context with the values every signer derives from it (GetSessionValues)
+#[derive(Debug, Clone)]
+pub struct Session {
+  key_agg: KeyAggContext,
+  b: BigInt,
+  r: S256Point,
+  e: BigInt,
+}
+
+impl Session {
+  pub fn new(key_agg: &KeyAggContext, aggnonce: &AggregateNonce, msg: &[u8]) -> Self {
+    let mut data = aggnonce.serialize().to_vec();
+    data.extend_from_slice(&xonly(&key_agg.q));
+    data.extend_from_slice(msg);
+    let b = Euclid::rem_euclid(&bytes_to_int(&tagged_hash("MuSig/noncecoef", &data)), &N);
+
+    let r = aggnonce.r1.add(&aggnonce.r2.rmul(b.clone()));
+    let r = if r == S256Point::infinity() { Gs.clone() } else { r };
+
+    let mut data = xonly(&r).to_vec();
+    data.extend_from_slice(&xonly(&key_agg.q));
+    data.extend_from_slice(msg);
+    let e = Euclid::rem_euclid(&bytes_to_int(&tagged_hash("BIP0340/challenge", &data)), &N);
+
+    Self { key_agg: key_agg.clone(), b, r, e }
+  }
+
+  /// None for a key that was not aggregated, infinity among them
+  fn signer_coefficient(&self, pubkey: &S256Point) -> Option<BigInt> {
+    if pubkey.is_infinity() {
+      return None
+    }
+    let pk = pubkey.sec(true);
+    if !self.key_agg.pubkeys.contains(&pk) {
+      return None
+    }
+    Some(self.key_agg.coefficient(&pk))
+  }
+
+  /// Sign: s = k1 + b k2 + e a d
+  pub fn sign(&self, secnonce: SecretNonce, key: &PrivateKey) -> Result<PartialSignature, MusigError> {
+    if secnonce.pubkey != key.point {
+      return Err(MusigError::UnknownSigner)
+    }
+    let a = self.signer_coefficient(&key.point).ok_or(MusigError::UnknownSigner)?;
+
+    let (k1, k2) = if self.r.has_even_y() {
+      (secnonce.k1.clone(), secnonce.k2.clone())
+    } else {
+      (SecretScalar::new(N.clone() - &*secnonce.k1), SecretScalar::new(N.clone() - &*secnonce.k2))
+    };
+    let g = neg_one_if_odd(&self.key_agg.q);
+    let d = SecretScalar::new(Euclid::rem_euclid(&(g * &self.key_agg.gacc * &*key.secret()), &N));
+    let s = Euclid::rem_euclid(&(&*k1 + &self.b * &*k2 + &self.e * a * &*d), &N);
+
+    let psig = PartialSignature { s };
+    let pubnonce = PublicNonce { r1: Gs.rmul(secnonce.k1.clone_int()), r2: Gs.rmul(secnonce.k2.clone_int()) };
+    if !self.partial_sig_verify(&psig, &pubnonce, &key.point) {
+      return Err(MusigError::InvalidPartialSignature)
+    }
+    Ok(psig)
+  }
+
+  /// PartialSigVerifyInternal: s G = Re + e a g gacc P
+  pub fn partial_sig_verify(&self, psig: &PartialSignature, pubnonce: &PublicNonce, pubkey: &S256Point) -> bool {
+    let a = match self.signer_coefficient(pubkey) {
+      Some(a_) => a_,
+      None => return false
+    };
+    let re = pubnonce.r1.add(&pubnonce.r2.rmul(self.b.clone()));
+    let re = if self.r.has_even_y() { re } else { re.negate() };
+    let g = neg_one_if_odd(&self.key_agg.q) * &self.key_agg.gacc;
+
+    Gs.rmul(psig.s.clone()) == re.add(&pubkey.rmul(&self.e * a * g))
+  }
+
+  /// PartialSigAgg: s = s_1 + ... + s_u + e g tacc
+  pub fn partial_sig_agg(&self, psigs: &[PartialSignature]) -> SchnorrSignature {
+    let g = neg_one_if_odd(&self.key_agg.q);
+    let s = psigs.iter().fold(&self.e * g * &self.key_agg.tacc, |acc, psig| acc + &psig.s);
+    // `Session::new` replaces an infinite R by G
+    let r = self.r.x.clone().expect("R is never infinity");
+    SchnorrSignature::new(r, S256Field::new(Euclid::rem_euclid(&s, &N), PRIME.clone()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{helper::decode_hex, schnorr};
+
+  fn point(s: &str) -> S256Point {
+    S256Point::parse(&decode_hex(s).unwrap()).unwrap()
+  }
+
+  fn pubnonce(s: &str) -> PublicNonce {
+    PublicNonce::parse(&decode_hex(s).unwrap().try_into().unwrap()).unwrap()
+  }
+
+  fn secnonce(pubkey: &S256Point) -> SecretNonce {
+    let bytes = decode_hex(SECNONCE).unwrap();
+    SecretNonce {
+      k1: SecretScalar::from_bytes_be(&bytes[..32]),
+      k2: SecretScalar::from_bytes_be(&bytes[32..]),
+      pubkey: pubkey.clone(),
+    }
+  }
+
+  fn signing_key() -> PrivateKey {
+    let bytes = decode_hex("7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671").unwrap();
+    PrivateKey::from_bytes(&bytes.try_into().unwrap()).unwrap()
+  }
+
+  const SECNONCE: &str = "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61\
+    FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7";
+
+  const PUBNONCES: [&str; 4] = [
+    "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+    "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+    "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+    "0237C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0387BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+  ];
+
+  const MSG: &str = "F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF";
+
+  /// BIP327 key_agg_vectors.json
+  #[test]
+  fn key_agg_vectors() {
+    let pubkeys = [
+      "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+      "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+      "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+    ];
+    let cases: [(&[usize], &str); 4] = [
+      (&[0, 1, 2], "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C"),
+      (&[2, 1, 0], "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B"),
+      (&[0, 0, 0], "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935"),
+      (&[0, 0, 1, 1], "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E"),
+    ];
+    for (indices, expected) in cases {
+      let points: Vec<S256Point> = indices.iter().map(|i| point(pubkeys[*i])).collect();
+      let ctx = KeyAggContext::new(&points).unwrap();
+      assert_eq!(xonly(ctx.aggregated_pubkey()).to_vec(), decode_hex(expected).unwrap());
+    }
+
+    // not on the curve, x not less than p, bad prefix
+    for invalid in [
+      "020000000000000000000000000000000000000000000000000000000000000005",
+      "02FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+      "04F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+    ] {
+      assert_eq!(S256Point::parse(&decode_hex(invalid).unwrap()), None);
+    }
+    // a tweak equal to n
+    let ctx = KeyAggContext::new(&[point(pubkeys[0]), point(pubkeys[1])]).unwrap();
+    let n = decode_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141").unwrap();
+    assert_eq!(ctx.apply_tweak(&n.try_into().unwrap(), true).unwrap_err(), MusigError::InvalidTweak);
+  }
+
+  /// BIP327 sign_verify_vectors.json, valid_test_cases
+  #[test]
+  fn sign_vectors() {
+    let key = signing_key();
+    let pubkeys = [
+      "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+      "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+      "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661",
+    ];
+    assert_eq!(key.point, point(pubkeys[0]));
+    let msgs = [decode_hex(MSG).unwrap(), vec![], vec![0x26; 38]];
+    let cases: [(&[usize], &[usize], usize, &str); 6] = [
+      (&[0, 1, 2], &[0, 1, 2], 0, "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB"),
+      (&[1, 0, 2], &[1, 0, 2], 0, "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52"),
+      (&[1, 2, 0], &[1, 2, 0], 0, "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900"),
+      // the aggregate nonce is infinity in both halves
+      (&[0, 1], &[0, 3], 0, "AE386064B26105404798F75DE2EB9AF5EDA5387B064B83D049CB7C5E08879531"),
+      (&[0, 1, 2], &[0, 1, 2], 1, "D7D63FFD644CCDA4E62BC2BC0B1D02DD32A1DC3030E155195810231D1037D82D"),
+      (&[0, 1, 2], &[0, 1, 2], 2, "E184351828DA5094A97C79CABDAAA0BFB87608C32E8829A4DF5340A6F243B78C"),
+    ];
+    for (key_indices, nonce_indices, msg_index, expected) in cases {
+      let points: Vec<S256Point> = key_indices.iter().map(|i| point(pubkeys[*i])).collect();
+      let nonces: Vec<PublicNonce> = nonce_indices.iter().map(|i| pubnonce(PUBNONCES[*i])).collect();
+      let ctx = KeyAggContext::new(&points).unwrap();
+      let session = Session::new(&ctx, &nonce_agg(&nonces), &msgs[msg_index]);
+      let psig = session.sign(secnonce(&key.point), &key).unwrap();
+      assert_eq!(psig.serialize().to_vec(), decode_hex(expected).unwrap());
+    }
+  }
+
+  /// BIP327 tweak_vectors.json, valid_test_cases
+  #[test]
+  fn tweak_vectors() {
+    let key = signing_key();
+    let points = [
+      "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+      "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+      "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+    ].map(point);
+    let nonces = [PUBNONCES[1], PUBNONCES[2], PUBNONCES[0]].map(pubnonce);
+    let tweaks = [
+      "E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB",
+      "AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455",
+      "F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0",
+      "1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D",
+    ];
+    let cases: [(&[bool], &str); 5] = [
+      (&[true], "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91"),
+      (&[false], "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D"),
+      (&[false, true], "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408"),
+      (&[false, false, true, true], "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435"),
+      (&[true, false, true, false], "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239"),
+    ];
+    for (is_xonly, expected) in cases {
+      let mut ctx = KeyAggContext::new(&points).unwrap();
+      for (tweak, x) in tweaks.iter().zip(is_xonly) {
+        ctx = ctx.apply_tweak(&decode_hex(tweak).unwrap().try_into().unwrap(), *x).unwrap();
+      }
+      let session = Session::new(&ctx, &nonce_agg(&nonces), &decode_hex(MSG).unwrap());
+      let psig = session.sign(secnonce(&key.point), &key).unwrap();
+      assert_eq!(psig.serialize().to_vec(), decode_hex(expected).unwrap());
+    }
+  }
+
+  #[test]
+  fn sign_and_aggregate() {
+    let keys: Vec<PrivateKey> = (1..4u8).map(|i| PrivateKey::from_bytes(&[i; 32]).unwrap()).collect();
+    let pubkeys = key_sort(&keys.iter().map(|k| k.point.clone()).collect::<Vec<_>>());
+    let ctx = KeyAggContext::new(&pubkeys).unwrap().apply_tweak(&[7u8; 32], true).unwrap();
+    let msg = b"MuSig2";
+    let nonces: Vec<_> = keys.iter()
+      .map(|k| nonce_gen(Some(k), &k.point, None, Some(msg), None))
+      .collect();
+    let pubnonces: Vec<PublicNonce> = nonces.iter().map(|n| n.1.clone()).collect();
+    let session = Session::new(&ctx, &nonce_agg(&pubnonces), msg);
+    let psigs: Vec<PartialSignature> = nonces.into_iter().zip(&keys)
+      .map(|((secnonce, _), key)| session.sign(secnonce, key).unwrap())
+      .collect();
+    assert!(schnorr::verify(ctx.aggregated_pubkey(), msg, &session.partial_sig_agg(&psigs)));
+
+    // a nonce generated for another key
+    let (secnonce, _) = nonce_gen(None, &keys[1].point, None, None, None);
+    assert_eq!(session.sign(secnonce, &keys[0]).unwrap_err(), MusigError::UnknownSigner);
+  }
+
+  #[test]
+  fn infinite_public_keys_are_rejected() {
+    let keys: Vec<PrivateKey> = (1..3u8).map(|i| PrivateKey::from_bytes(&[i; 32]).unwrap()).collect();
+    let mut pubkeys: Vec<S256Point> = keys.iter().map(|k| k.point.clone()).collect();
+    pubkeys.push(S256Point::infinity());
+    assert_eq!(key_sort(&pubkeys)[0], S256Point::infinity());
+    assert_eq!(KeyAggContext::new(&pubkeys).unwrap_err(), MusigError::InvalidPublicKey);
+
+    let ctx = KeyAggContext::new(&pubkeys[..2]).unwrap();
+    let nonces: Vec<_> = keys.iter().map(|k| nonce_gen(Some(k), &k.point, None, None, None)).collect();
+    let pubnonces: Vec<PublicNonce> = nonces.iter().map(|n| n.1.clone()).collect();
+    let session = Session::new(&ctx, &nonce_agg(&pubnonces), b"MuSig2");
+    let mut nonces = nonces.into_iter();
+    let (secnonce, pubnonce) = nonces.next().unwrap();
+    let psig = session.sign(secnonce, &keys[0]).unwrap();
+    assert!(session.partial_sig_verify(&psig, &pubnonce, &keys[0].point));
+    assert!(!session.partial_sig_verify(&psig, &pubnonce, &S256Point::infinity()));
+  }
+}
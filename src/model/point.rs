@@ -1,157 +1,313 @@
+//! Points of short Weierstrass curves in affine coordinates
+//!
+//! `AffinePoint<C>` replaced the original `Point` and `S256Point` structs, which stored the
+//! curve's `a` and `b` in every point and compared them on each addition:
+//! * `Point::new(x, y, a, b)`: give the curve a `Curve` marker type with its `CurveParams`,
+//!   then `AffinePoint::<C>::new(x, y)`
+//! * `S256Point::new(x, y, a, b)`: `S256Point::new(x, y)`, as `S256Point` is
+//!   `AffinePoint<Secp256k1>`; `from_coordinates` takes the integers directly
+//!
+//! The group law only needs `WeierstrassCurve`, which every `Curve` is; the BLS12-381
+//! twist uses it with F_p2 coordinates. SEC1 encoding and `lift_x` are for `Curve`s.
+
+use std::fmt;
+
 use num_bigint::BigInt;
-use num_traits::Zero;
+use num_traits::{Euclid, Zero};
 use num_integer::Integer;
-use super::field_elements::FieldElement;
-use crate::model::field_elements::FieldOperation;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub struct Point {
-  // basic form: y^2 = x^3 + ax + b
-  x: Option<FieldElement>, 
-  y: Option<FieldElement>,
-  a: FieldElement,
-  b: FieldElement,
+use super::{
+  curve::Curve,
+  field_elements::{Fe, Field, FieldOperation},
+  helper::{bytes_to_int, int_to_bytes, wipe_int},
+};
+
+/// the field operations `AffinePoint` needs from its coordinates
+pub trait CurveField: Clone + PartialEq + Eq + fmt::Debug {
+  fn zero() -> Self;
+  fn one() -> Self;
+  fn is_zero(&self) -> bool;
+  fn add(&self, other: &Self) -> Self;
+  fn sub(&self, other: &Self) -> Self;
+  fn mul(&self, other: &Self) -> Self;
+  fn neg(&self) -> Self;
+  /// `None` for zero
+  fn inverse(&self) -> Option<Self>;
+  /// `None` for a non-square
+  fn sqrt(&self) -> Option<Self>;
+  /// sgn0 of RFC 9380 section 4.1
+  fn sgn0(&self) -> bool;
 }
 
-impl Point {
-  pub fn new(x:Option<FieldElement>, y:Option<FieldElement>, a:FieldElement, b:FieldElement) -> Self 
-  {
-    if x == None && y == None {
-      // 무한 원점, `Point(infinity)`
-      return Self { x:None, y:None, a, b };
-    }
+impl<F: Field> CurveField for Fe<F> {
+  fn zero() -> Self {
+    Fe::zero()
+  }
 
-    let y2 = y.clone().map(|y_| y_.pow(BigInt::from(2u128)));
+  fn one() -> Self {
+    Fe::one()
+  }
 
-    let result = match x.clone() {
-      Some(x_) => Some(x_.pow(BigInt::from(3u128)).add(&(x_.mul(&a))).add(&b)),
-      _ => None,
-    };
+  fn is_zero(&self) -> bool {
+    Fe::is_zero(self)
+  }
 
-    if y2 != result {
-      panic!("({:?}, {:?}) [{:?} ? {:?}] is not on the curve",x,y, y2, result);
-    }
-    
-    Self {x,y,a,b}
+  fn add(&self, other: &Self) -> Self {
+    Fe::add(self, other)
   }
 
-  pub fn to_string(&self) -> String 
-  {
-    if self.x == None {
-      format!("Point(infinity)")
-    } else {
-      format!("Point({:?},{:?})_{:?}_{:?}", self.x, self.y, self.a, self.b)
-    }
+  fn sub(&self, other: &Self) -> Self {
+    Fe::sub(self, other)
   }
 
-  pub fn eq(&self, other: &Self) -> bool 
-  {
-    self.x == other.x && self.y == other.y && 
-    self.a == other.a && self.b == other.b 
+  fn mul(&self, other: &Self) -> Self {
+    Fe::mul(self, other)
   }
 
-  pub fn ne(&self, other: &Self) -> bool 
-  {
-    !self.eq(other)
+  fn neg(&self) -> Self {
+    Fe::neg(self)
   }
 
-  pub fn add(&self, other: &Self) -> Self 
-  {
-    if self.a != other.a || self.b != other.b {
-      panic!("Point ({:?}, {:?}) is not on the same curve", self, other);
-    }
+  fn inverse(&self) -> Option<Self> {
+    Fe::inverse(self)
+  }
+
+  fn sqrt(&self) -> Option<Self> {
+    Fe::sqrt(self)
+  }
+
+  /// the parity of the integer in [0, p)
+  fn sgn0(&self) -> bool {
+    self.get_num().bit(0)
+  }
+}
+
+/// a short Weierstrass curve y^2 = x^3 + ax + b over the field `Coordinate`
+pub trait WeierstrassCurve: fmt::Debug + Clone + Copy + PartialEq + Eq + 'static {
+  type Coordinate: CurveField;
 
-    if self.x == None {
-      return other.to_owned()
-    } 
-    
-    if other.x == None {
-      return self.to_owned()
+  fn curve_name() -> &'static str;
+  fn curve_a() -> Self::Coordinate;
+  fn curve_b() -> Self::Coordinate;
+  /// a multiple of every point's order, by which scalars are reduced
+  fn group_order() -> BigInt;
+}
+
+/// a curve given by its domain parameters, scalars reduced by n·h
+impl<C: Curve> WeierstrassCurve for C {
+  type Coordinate = Fe<C>;
+
+  fn curve_name() -> &'static str {
+    C::name()
+  }
+
+  fn curve_a() -> Fe<C> {
+    Fe::from_int(C::a())
+  }
+
+  fn curve_b() -> Fe<C> {
+    Fe::from_int(C::b())
+  }
+
+  fn group_order() -> BigInt {
+    C::n() * C::cofactor()
+  }
+}
+
+/// point of the curve `C` in affine coordinates, `x` and `y` both `None` for the point at infinity
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AffinePoint<C: WeierstrassCurve> {
+  // basic form: y^2 = x^3 + ax + b
+  pub x: Option<C::Coordinate>,
+  pub y: Option<C::Coordinate>,
+}
+
+impl<C: Curve> fmt::Display for AffinePoint<C> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (&self.x, &self.y) {
+      (Some(x_), Some(y_)) => write!(f, "{}({},{})", C::name(), x_.to_string(), y_.to_string()),
+      _ => write!(f, "{}(infinity)", C::name()),
     }
+  }
+}
 
-    
-    if self.x == other.x && self.y != other.y // 두 점이 다른 경우 (y축 평행 O, x축 대칭)
-       || self == other && self.y == match &self.x { 
-          Some(x_) => Some(FieldElement::new(BigInt::from(0u128), x_.get_prime().clone())), 
-          _ => None
-        }
-    {
-      return Self::new(None, None, self.a.clone(), self.b.clone())
+impl<C: WeierstrassCurve> AffinePoint<C> {
+  /// panics unless (x, y) is on the curve or both are `None`
+  pub fn new(x: Option<C::Coordinate>, y: Option<C::Coordinate>) -> Self {
+    match (&x, &y) {
+      // 무한 원점, `Point(infinity)`
+      (None, None) => {}
+      (Some(x_), Some(y_)) if Self::satisfies_equation(x_, y_) => {}
+      _ => panic!("({:?}, {:?}) is not on the curve {}", x, y, C::curve_name()),
     }
+    Self { x, y }
+  }
 
-    if self.x != other.x // 두 점이 다른 경우 (y축 평행 X)
-    {
-      let (x1, y1) = (self.x.clone(), self.y.clone());
-      let (x2, y2) = (other.x.clone(), other.y.clone());
-      
-      let s = match (&y2, &y1, &x2, &x1) {
-        (Some(y2_), Some(y1_), Some(x2_), Some(x1_)) 
-        => Some((y2_.sub(&y1_)).truediv(&x2_.sub(&x1_))),
-        _ => None
-      };
-      
-      let x3 = match (&s, &x1, &x2) {
-        (Some(s_), Some(x1_), Some(x2_)) 
-        => Some(s_.pow(BigInt::from(2u128)).sub(&x1_).sub(&x2_)),
-        _ => None
-      };
-      let y3 = match (&s, &x1, &x3, &y1) {
-        (Some(s_), Some(x1_), Some(x3_), Some(y1_)) 
-        => Some(s_.mul(&x1_.sub(&x3_)).sub(&y1_)),
-        _ => None
-      };
-
-      return Self::new(x3, y3, self.a.clone(), self.b.clone())
+  /// 무한 원점
+  pub fn infinity() -> Self {
+    Self { x: None, y: None }
+  }
+
+  pub fn is_infinity(&self) -> bool {
+    self.x.is_none()
+  }
+
+  /// x^3 + ax + b
+  fn rhs(x: &C::Coordinate) -> C::Coordinate {
+    x.mul(x).mul(x).add(&C::curve_a().mul(x)).add(&C::curve_b())
+  }
+
+  fn satisfies_equation(x: &C::Coordinate, y: &C::Coordinate) -> bool {
+    y.mul(y) == Self::rhs(x)
+  }
+
+  /// a finite point with y^2 = x^3 + ax + b
+  pub fn is_on_curve(&self) -> bool {
+    match (&self.x, &self.y) {
+      (Some(x_), Some(y_)) => Self::satisfies_equation(x_, y_),
+      _ => false,
     }
+  }
+
+  pub fn add(&self, other: &Self) -> Self {
+    let (x1, y1, x2, y2) = match (&self.x, &self.y, &other.x, &other.y) {
+      (Some(x1), Some(y1), Some(x2), Some(y2)) => (x1, y1, x2, y2),
+      (None, _, _, _) => return other.clone(),
+      _ => return self.clone(),
+    };
 
-    if self == other // 두 점이 같은 경우 (접하는 경우)
-    {      
-      let (x1, y1) = (self.x.clone(), self.y.clone());
-      let s = match (&x1, &y1) {
-        (Some(x1_), Some(y1_)) 
-          => Some((x1_.pow(BigInt::from(2u128))
-                    .mul(&FieldElement::new(BigInt::from(3u128),x1_.get_prime().clone()))
-                    .add(&self.a))
-                    .truediv(&y1_.mul(
-                      &FieldElement::new(BigInt::from(2u128), y1_.get_prime().clone())
-                ))),
-        _ => None
-      };
-      let x3 = match (&s, &x1) {
-        (Some(s_val),Some(x1_val)) 
-          => Some(s_val.pow(BigInt::from(2u128))
-                        .sub(&x1_val.mul(
-                          &FieldElement::new(BigInt::from(2u128),x1_val.get_prime().clone()))
-                  )),
-        _ => None
-      };
-      let y3 = match (&s, &x1, &x3, &y1) {
-        (Some(s_val),Some(x1_val), Some(x3_val), Some(y1_val)) 
-        => Some(s_val.mul(&x1_val.sub(&x3_val)).sub(&y1_val)),
-        _ => None
-      };
-      return Self::new(x3, y3, self.a.clone(), self.b.clone())
+    // 두 점이 다른 경우 (y축 평행 O, x축 대칭), or a tangent at y = 0
+    if x1 == x2 && (y1 != y2 || y1.is_zero()) {
+      return Self::infinity()
     }
 
-    unreachable!("Not match")
+    let s = if x1 != x2 {
+      // 두 점이 다른 경우 (y축 평행 X)
+      y2.sub(y1).mul(&x2.sub(x1).inverse().expect("x1 != x2"))
+    } else {
+      // 두 점이 같은 경우 (접하는 경우)
+      let x1x1 = x1.mul(x1);
+      let three_x1x1 = x1x1.add(&x1x1).add(&x1x1);
+      three_x1x1.add(&C::curve_a()).mul(&y1.add(y1).inverse().expect("y1 != 0"))
+    };
+    let x3 = s.mul(&s).sub(x1).sub(x2);
+    let y3 = s.mul(&x1.sub(&x3)).sub(y1);
+    Self { x: Some(x3), y: Some(y3) }
   }
 
-  fn double(&self) -> Self {
-    self.add(&self)
+  pub fn double(&self) -> Self {
+    self.add(self)
   }
-  /// double-and-add algorithm for point multiplication:
-  pub fn rmul(&self, coef: BigInt) -> Self {
-    let mut result: Point = Self::new(None, None, self.a.clone(), self.b.clone());
+
+  /// Double-and-add algorithm; `coef` is often a secret, so it is wiped once reduced
+  /// and the shifts leave the working copy zero
+  pub fn rmul(&self, mut coef: BigInt) -> Self {
+    // group order cycle => point zero (infinity)
+    let mut c = Euclid::rem_euclid(&coef, &C::group_order());
+    wipe_int(&mut coef);
+    let mut result = Self::infinity();
     let mut base = self.clone();
-    let mut c = coef.clone();
 
     while c > BigInt::zero() {
       if c.is_odd() {
-          result = result.add(&base);
+        result = result.add(&base);
       }
       base = base.double();
-      c = c >> 1;
+      c >>= 1;
     }
     result
   }
-}
\ No newline at end of file
+
+  /// Straus' (Shamir's trick) multi-scalar multiplication:
+  /// sum(c_i * P_i) with one doubling chain shared by every term
+  pub fn multi_mul(terms: &[(BigInt, Self)]) -> Self {
+    let order = C::group_order();
+    let coefs: Vec<BigInt> = terms.iter()
+      .map(|(c, _)| Euclid::rem_euclid(c, &order))
+      .collect();
+    let bits = coefs.iter().map(|c| c.bits()).max().unwrap_or(0);
+
+    let mut result = Self::infinity();
+    for i in (0..bits).rev() {
+      result = result.double();
+      for (c, (_, point)) in coefs.iter().zip(terms) {
+        if c.bit(i) {
+          result = result.add(point);
+        }
+      }
+    }
+    result
+  }
+
+  pub fn negate(&self) -> Self {
+    Self { x: self.x.clone(), y: self.y.as_ref().map(CurveField::neg) }
+  }
+}
+
+impl<C: Curve> AffinePoint<C> {
+  /// panics unless (x, y) is on the curve, coordinates are reduced mod p
+  pub fn from_coordinates(x: &BigInt, y: &BigInt) -> Self {
+    Self::new(Some(Fe::from_int(x)), Some(Fe::from_int(y)))
+  }
+
+  pub fn has_even_y(&self) -> bool {
+    match &self.y {
+      Some(y_) => y_.get_num().is_even(),
+      None => panic!("infinity has no y")
+    }
+  }
+
+  /// the point with the given x and an even y, if any (BIP340 `lift_x`)
+  pub fn lift_x(x: &BigInt) -> Option<Self> {
+    if x < &BigInt::zero() || x >= C::p() {
+      return None
+    }
+    let x_ = Fe::from_int(x);
+    let y_ = Self::rhs(&x_).sqrt()?;
+    let y_ = if y_.get_num().is_even() { y_ } else { y_.neg() };
+    Some(Self { x: Some(x_), y: Some(y_) })
+  }
+
+  /// SEC1 encoding: `02/03 || x` compressed, `04 || x || y` uncompressed
+  pub fn sec(&self, compressed: bool) -> Vec<u8> {
+    let (x_, y_) = match (&self.x, &self.y) {
+      (Some(x_), Some(y_)) => (x_.get_num(), y_.get_num()),
+      _ => panic!("infinity has no SEC encoding")
+    };
+    let len = C::field_bytes();
+    if compressed {
+      let prefix = if y_.is_even() { 2u8 } else { 3u8 };
+      let mut out = vec![prefix];
+      out.extend(int_to_bytes(&x_, len));
+      out
+    } else {
+      let mut out = vec![4u8];
+      out.extend(int_to_bytes(&x_, len));
+      out.extend(int_to_bytes(&y_, len));
+      out
+    }
+  }
+
+  /// `None` if the bytes are not a SEC1 encoded point on the curve
+  pub fn parse(sec_bin: &[u8]) -> Option<Self> {
+    let len = C::field_bytes();
+    match sec_bin.first() {
+      Some(prefix) if (*prefix == 2 || *prefix == 3) && sec_bin.len() == 1 + len => {
+        let p = Self::lift_x(&bytes_to_int(&sec_bin[1..]))?;
+        if (*prefix == 2) == p.has_even_y() { Some(p) } else { Some(p.negate()) }
+      },
+      Some(4) if sec_bin.len() == 1 + 2 * len => {
+        let (x_, y_) = (bytes_to_int(&sec_bin[1..1 + len]), bytes_to_int(&sec_bin[1 + len..]));
+        if x_ >= *C::p() || y_ >= *C::p() {
+          return None
+        }
+        let (x_, y_) = (Fe::from_int(&x_), Fe::from_int(&y_));
+        if !Self::satisfies_equation(&x_, &y_) {
+          return None
+        }
+        Some(Self { x: Some(x_), y: Some(y_) })
+      },
+      _ => None
+    }
+  }
+}
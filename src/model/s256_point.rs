@@ -1,152 +1,78 @@
 use num_bigint::BigInt;
 use num_traits::{Euclid, Zero};
-use num_integer::Integer;
 
-use crate::model::constants::{Gs,N};
+use crate::model::constants::{Gs, N};
 
-use super::{field_elements::FieldOperation, s256_field::S256Field, signature::Signature};
+use super::{
+  curve::Secp256k1,
+  ecies::{self, EciesError},
+  field_elements::FieldOperation,
+  jose::{self, JoseError},
+  pem::{self, PemError},
+  point::AffinePoint,
+  s256_field::S256Field,
+  schnorr::xonly,
+  signature::Signature,
+  taproot::tap_tweak_hash,
+};
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub struct S256Point {
-  // basic form: y^2 = x^3 + ax + b
-  pub x: Option<S256Field>,
-  pub y: Option<S256Field>,
-  a: S256Field,
-  b: S256Field,
-}
+/// secp256k1 point; curve arithmetic and SEC1 encoding come from `AffinePoint`
+pub type S256Point = AffinePoint<Secp256k1>;
 
 impl S256Point {
-  pub fn new(x:Option<S256Field>, y:Option<S256Field>, a:S256Field, b:S256Field) -> Self
-  {
-    if x == None && y == None {
-      // 무한 원점, `Point(infinity)`
-      return Self { x:None, y:None, a, b };
-    }
-
-    let y2 = y.clone().map(|y_| y_.pow(BigInt::from(2u128)));
-
-    let result = match x.clone() {
-      Some(x_) => Some(x_.pow(BigInt::from(3u128)).add(&(x_.mul(&a))).add(&b)),
-      _ => None,
-    };
-
-    if y2 != result {
-      panic!("({:?}, {:?}) [{:?} ? {:?}] is not on the curve",x,y, y2, result);
-    }
-
-    Self {x,y,a,b}
+  /// BIP341 output key Q = P + int(hash_TapTweak(bytes(P) || merkle_root))G,
+  /// P being the even-y point with this x; None for infinity, a tweak not less
+  /// than n or Q at infinity
+  pub fn tap_tweak(&self, merkle_root: Option<[u8; 32]>) -> Option<Self> {
+    let internal_key = Self::lift_x(&self.x.as_ref()?.get_num())?;
+    let t = tap_tweak_hash(&xonly(&internal_key), merkle_root)?;
+    Some(internal_key.add(&Gs.rmul(t))).filter(|q| !q.is_infinity())
   }
 
-  pub fn to_string(&self) -> String {
-    if self.x == None {
-      format!("S256Point(infinity")
-    } else {
-      format!("S256Point({:?},{:?})", self.x, self.y)
-    }
+  /// ECIES encryption to this public key, see `ecies` for the wire format
+  pub fn encrypt(&self, msg: &[u8]) -> Result<Vec<u8>, EciesError> {
+    ecies::encrypt(self, msg)
   }
 
-  pub fn add(&self, other: &Self) -> Self
-  {
-    if self.a != other.a || self.b != other.b {
-      panic!("Point ({:?}, {:?}) is not on the same curve", self, other);
-    }
-
-    if self.x == None {
-      return other.to_owned()
-    }
-
-    if other.x == None {
-      return self.to_owned()
-    }
+  /// DER SubjectPublicKeyInfo, uncompressed point
+  pub fn to_public_key_der(&self) -> Vec<u8> {
+    pem::to_public_key_der(self)
+  }
 
-    if self.x == other.x && self.y != other.y // 두 점이 다른 경우 (y축 평행 O, x축 대칭)
-       || self == other && self.y == match &self.x {
-          Some(x_) => Some(S256Field::new(BigInt::from(0u128), x_.get_prime().clone())), 
-          _ => None
-        }
-    {
-      return Self::new(None, None, self.a.clone(), self.b.clone())
-    }
+  pub fn from_public_key_der(data: &[u8]) -> Result<Self, PemError> {
+    pem::from_public_key_der(data)
+  }
 
-    if self.x != other.x // 두 점이 다른 경우 (y축 평행 X)
-    {
-      let (x1, y1) = (self.x.clone(), self.y.clone());
-      let (x2, y2) = (other.x.clone(), other.y.clone());
-
-      let s = match (&y2, &y1, &x2, &x1) {
-        (Some(y2_), Some(y1_), Some(x2_), Some(x1_))
-        => Some((y2_.sub(&y1_)).truediv(&x2_.sub(&x1_))),
-        _ => None
-      };
-
-      let x3 = match (&s, &x1, &x2) {
-        (Some(s_), Some(x1_), Some(x2_))
-        => Some(s_.pow(BigInt::from(2u128)).sub(&x1_).sub(&x2_)),
-        _ => None
-      };
-      let y3 = match (&s, &x1, &x3, &y1) {
-        (Some(s_), Some(x1_), Some(x3_), Some(y1_))
-        => Some(s_.mul(&x1_.sub(&x3_)).sub(&y1_)),
-        _ => None
-      };
-
-      return Self::new(x3, y3, self.a.clone(), self.b.clone())
-    }
+  /// `-----BEGIN PUBLIC KEY-----`
+  pub fn to_public_key_pem(&self) -> String {
+    pem::encode_pem(pem::PUBLIC_KEY_LABEL, &self.to_public_key_der())
+  }
 
-    if self == other // 두 점이 같은 경우 (접하는 경우)
-    {
-      let (x1, y1) = (self.x.clone(), self.y.clone());
-      let s = match (&x1, &y1) {
-        (Some(x1_), Some(y1_))
-        => Some((x1_.pow(BigInt::from(2u128))
-                    .mul(&&S256Field::new(BigInt::from(3u128),x1_.get_prime().clone()))
-                    .add(&self.a))
-                    .truediv(&y1_.mul(
-                      &&S256Field::new(BigInt::from(2u128), y1_.get_prime().clone())
-                ))),
-        _ => None
-      };
-      let x3 = match (&s, &x1) {
-        (Some(s_val),Some(x1_val)) 
-          => Some(s_val.pow(BigInt::from(2u128))
-                        .sub(&x1_val.mul(
-                          &S256Field::new(BigInt::from(2u128),x1_val.get_prime().clone())
-                  ))),
-        _ => None
-      };
-      let y3 = match (&s, &x1, &x3, &y1) {
-        (Some(s_val),Some(x1_val), Some(x3_val), Some(y1_val))
-        => Some(s_val.mul(&x1_val.sub(&x3_val)).sub(&y1_val)),
-        _ => None
-      };
-      return Self::new(x3, y3, self.a.clone(), self.b.clone())
-    }
+  pub fn from_public_key_pem(data: &str) -> Result<Self, PemError> {
+    pem::public_key_from_pem(data)
+  }
 
-    unreachable!("Not match")
+  /// public JWK, `{"kty":"EC","crv":"secp256k1","x":...,"y":...}`
+  pub fn to_jwk(&self) -> String {
+    jose::public_key_to_jwk(self)
   }
 
-  fn double(&self) -> Self {
-    self.add(&self)
+  pub fn from_jwk(data: &str) -> Result<Self, JoseError> {
+    jose::public_key_from_jwk(data)
   }
-  /// Double-and-add algorithm
-  pub fn rmul(&self, coef: BigInt) -> Self {
-    // group order N, N cycle => point zero (infinity)
-    let coef = Euclid::rem_euclid(&coef, &N); 
-    let mut result: S256Point = Self::new(None, None, self.a.clone(), self.b.clone());
-    let mut base = self.clone();
-    let mut c = coef.clone();
-
-    while c > BigInt::zero() {
-      if c.is_odd() {
-          result = result.add(&base);
-      }
-      base = base.double();
-      c = c >> 1;
-    }
-    result
+
+  /// payload of a compact `ES256K` JWS signed by this key
+  pub fn verify_jws(&self, token: &str) -> Result<Vec<u8>, JoseError> {
+    jose::verify(self, token)
   }
 
+  /// ECDSA verification: r and s in [1, n-1] and x(uG + vP) mod n == r;
+  /// false when uG + vP is the point at infinity
   pub fn verify(&self, z: BigInt, sig: Signature) -> bool {
+    let (r, s) = (sig.r.get_num(), sig.s.get_num());
+    if r <= BigInt::zero() || r >= *N || s <= BigInt::zero() || s >= *N {
+      return false
+    }
     let s_inv = S256Field::mod_exp(&sig.s.get_num(), &(N.clone() - &BigInt::from(2u128)), &N);
     let u = Euclid::rem_euclid(&(z * (s_inv.clone())), &N.clone());
     let v = Euclid::rem_euclid(&(sig.r.get_num() * (s_inv)), &N.clone());
@@ -161,9 +87,44 @@ impl S256Point {
     let total = uG.add(&vP);
 
     match total.x {
-      Some(x_) => x_ == sig.r,
-      None => panic!("no x")
+      Some(x_) => Euclid::rem_euclid(&x_.get_num(), &N) == r,
+      None => false
     }
   }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::constants::PRIME;
+
+  fn signature(r: &BigInt, s: &BigInt) -> Signature {
+    Signature::new(S256Field::new(r.clone(), PRIME.clone()), S256Field::new(s.clone(), PRIME.clone()))
+  }
+
+  #[test]
+  fn verify_is_false_at_infinity() {
+    // P = G, r = s = 1 and z = n - 1 give uG + vP = (n - 1)G + G
+    let (one, z) = (BigInt::from(1u8), N.clone() - 1u8);
+    assert!(!Gs.verify(z, signature(&one, &one)));
+  }
+
+  #[test]
+  fn verify_compares_r_mod_n() {
+    // R with n < x(R) < p, and P = r^-1 R so that z = 0, s = 1 give uG + vP = R
+    let mut x = N.clone() + 1u8;
+    let r_point = loop {
+      if let Some(point) = S256Point::lift_x(&x) {
+        break point
+      }
+      x += 1u8;
+    };
+    let r = Euclid::rem_euclid(&x, &N);
+    let r_inv = S256Field::mod_exp(&r, &(N.clone() - 2u8), &N);
+    let public_key = r_point.rmul(r_inv);
+    let one = BigInt::from(1u8);
+    assert!(public_key.verify(BigInt::zero(), signature(&r, &one)));
+    assert!(!public_key.verify(BigInt::zero(), signature(&x, &one)));
+    assert!(!public_key.verify(BigInt::zero(), signature(&r, &BigInt::zero())));
+  }
+}
@@ -0,0 +1,332 @@
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::{Euclid, One, Zero};
+use zeroize::Zeroizing;
+
+use super::{
+  constants::{Gs, N, PRIME},
+  field_elements::FieldOperation,
+  helper::{bytes_to_int, int_to_bytes32, tagged_hash, SecretScalar},
+  private_key::PrivateKey,
+  s256_field::S256Field,
+  s256_point::S256Point,
+};
+
+/// BIP340 Schnorr signature: `bytes(R.x) || bytes(s)`
+/// https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SchnorrSignature {
+  pub r: S256Field,
+  pub s: S256Field
+}
+
+impl SchnorrSignature {
+  pub fn new(r: S256Field, s: S256Field) -> Self {
+    SchnorrSignature { r, s }
+  }
+
+  /// `None` if r >= p or s >= n
+  pub fn parse(bytes: &[u8; 64]) -> Option<Self> {
+    let r = bytes_to_int(&bytes[..32]);
+    let s = bytes_to_int(&bytes[32..]);
+    if r >= *PRIME || s >= *N {
+      return None
+    }
+    Some(Self::new(S256Field::new(r, PRIME.clone()), S256Field::new(s, PRIME.clone())))
+  }
+
+  pub fn serialize(&self) -> [u8; 64] {
+    let mut out = [0u8; 64];
+    out[..32].copy_from_slice(&int_to_bytes32(&self.r.get_num()));
+    out[32..].copy_from_slice(&int_to_bytes32(&self.s.get_num()));
+    out
+  }
+}
+
+/// x-only public key: 32 bytes of P.x
+///
+/// Panics on the point at infinity, which has no x coordinate; callers check
+/// or construct their points so that it can not occur.
+pub fn xonly(point: &S256Point) -> [u8; 32] {
+  match &point.x {
+    Some(x_) => int_to_bytes32(&x_.get_num()),
+    None => panic!("infinity has no x-only encoding")
+  }
+}
+
+/// e = int(hash_BIP0340/challenge(bytes(R) || bytes(P) || m)) mod n
+fn challenge(r: &S256Field, pubkey: &S256Point, msg: &[u8]) -> BigInt {
+  let mut data = int_to_bytes32(&r.get_num()).to_vec();
+  data.extend_from_slice(&xonly(pubkey));
+  data.extend_from_slice(msg);
+  Euclid::rem_euclid(&bytes_to_int(&tagged_hash("BIP0340/challenge", &data)), &N)
+}
+
+/// d, t, k0 and k are wiped when they go out of scope
+pub fn sign(key: &PrivateKey, msg: &[u8], aux_rand: &[u8; 32]) -> SchnorrSignature {
+  let secret = key.secret();
+  if *secret <= BigInt::zero() || *secret >= *N {
+    panic!("secret key out of range")
+  }
+  let d = if key.point.has_even_y() { secret } else { SecretScalar::new(N.clone() - &*secret) };
+
+  let aux_hash = tagged_hash("BIP0340/aux", aux_rand);
+  let mut t = d.to_bytes32();
+  for (t_, a_) in t.iter_mut().zip(aux_hash) {
+    *t_ ^= a_;
+  }
+
+  let mut nonce_data = Zeroizing::new(t.to_vec());
+  nonce_data.extend_from_slice(&xonly(&key.point));
+  nonce_data.extend_from_slice(msg);
+  let k0 = SecretScalar::new(Euclid::rem_euclid(
+    &*SecretScalar::from_bytes_be(&tagged_hash("BIP0340/nonce", &nonce_data)), &N
+  ));
+  if k0.is_zero() {
+    panic!("nonce is zero")
+  }
+
+  let r_point = Gs.rmul(k0.clone_int());
+  let k = if r_point.has_even_y() { k0 } else { SecretScalar::new(N.clone() - &*k0) };
+  let r = match r_point.x {
+    Some(x_) => x_,
+    None => panic!("no r")
+  };
+
+  let e = challenge(&r, &key.point, msg);
+  let ed = SecretScalar::new(e * &*d);
+  let s = Euclid::rem_euclid(&(&*k + &*ed), &N);
+  SchnorrSignature::new(r, S256Field::new(s, PRIME.clone()))
+}
+
+/// only `pubkey.x` is used; the key is lifted to its even-y point
+pub fn verify(pubkey: &S256Point, msg: &[u8], sig: &SchnorrSignature) -> bool {
+  let p = match pubkey.x.as_ref().and_then(|x_| S256Point::lift_x(&x_.get_num())) {
+    Some(p_) => p_,
+    None => return false
+  };
+  if sig.s.get_num() >= *N {
+    return false
+  }
+
+  // R = sG - eP
+  let e = challenge(&sig.r, &p, msg);
+  let r_point = S256Point::multi_mul(&[
+    (sig.s.get_num(), Gs.clone()),
+    (N.clone() - e, p),
+  ]);
+
+  match &r_point.x {
+    Some(x_) => r_point.has_even_y() && *x_ == sig.r,
+    None => false
+  }
+}
+
+/// Batch verification with random linear combinations:
+/// (a_1 s_1 + ... + a_u s_u) G = a_1 R_1 + a_1 e_1 P_1 + ... + a_u R_u + a_u e_u P_u
+///
+/// On failure the signatures are checked one by one and the index of the
+/// first invalid one is returned.
+pub fn verify_batch(items: &[(S256Point, &[u8], SchnorrSignature)]) -> Result<(), usize> {
+  if !batch_equation_holds(items) {
+    if let Some(i) = items.iter().position(|(pubkey, msg, sig)| !verify(pubkey, msg, sig)) {
+      return Err(i)
+    }
+  }
+  Ok(())
+}
+
+fn batch_equation_holds(items: &[(S256Point, &[u8], SchnorrSignature)]) -> bool {
+  let mut rng = rand::thread_rng();
+  let mut s_sum = BigInt::zero();
+  let mut terms: Vec<(BigInt, S256Point)> = Vec::with_capacity(2 * items.len() + 1);
+
+  for (i, (pubkey, msg, sig)) in items.iter().enumerate() {
+    let p = pubkey.x.as_ref().and_then(|x_| S256Point::lift_x(&x_.get_num()));
+    let r_point = S256Point::lift_x(&sig.r.get_num());
+    let (p, r_point) = match (p, r_point) {
+      (Some(p_), Some(r_)) if sig.s.get_num() < *N => (p_, r_),
+      _ => return false
+    };
+
+    // a_1 = 1, a_2..a_u random in [1, n-1]
+    let a = if i == 0 { BigInt::one() } else { rng.gen_bigint_range(&BigInt::one(), &N) };
+    let e = challenge(&sig.r, &p, msg);
+
+    s_sum += &a * sig.s.get_num();
+    terms.push((&a * e, p));
+    terms.push((a, r_point));
+  }
+  terms.push((N.clone() - Euclid::rem_euclid(&s_sum, &N), Gs.clone()));
+
+  S256Point::multi_mul(&terms) == S256Point::infinity()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::helper::decode_hex;
+
+  /// BIP340 test-vectors.csv: index, secret key, public key, aux_rand, message, signature, verification result
+  const VECTORS: &[(u8, &str, &str, &str, &str, &str, bool)] = &[
+    (0, "0000000000000000000000000000000000000000000000000000000000000003",
+      "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+      "0000000000000000000000000000000000000000000000000000000000000000",
+      "0000000000000000000000000000000000000000000000000000000000000000",
+      "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+      true),
+    (1, "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+      "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+      "0000000000000000000000000000000000000000000000000000000000000001",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+      true),
+    (2, "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9",
+      "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+      "C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906",
+      "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+      "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+      true),
+    // fails if msg is reduced modulo p or n
+    (3, "0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710",
+      "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+      "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+      "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+      "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+      true),
+    (4, "", "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9", "",
+      "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+      "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+      true),
+    // public key not on the curve
+    (5, "", "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+      false),
+    // has_even_y(R) is false
+    (6, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+      false),
+    // negated message
+    (7, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD",
+      false),
+    // negated s value
+    (8, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6",
+      false),
+    // sG - eP is infinite, x(inf) taken as 0
+    (9, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+      false),
+    // sG - eP is infinite, x(inf) taken as 1
+    (10, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+      false),
+    // sig[0:32] is not an x coordinate on the curve
+    (11, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+      false),
+    // sig[0:32] is equal to the field size
+    (12, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+      false),
+    // sig[32:64] is equal to the curve order
+    (13, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+      false),
+    // public key exceeds the field size
+    (14, "", "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30", "",
+      "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+      "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+      false),
+    // messages of other lengths
+    (15, "0340034003400340034003400340034003400340034003400340034003400340",
+      "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+      "0000000000000000000000000000000000000000000000000000000000000000", "",
+      "71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63",
+      true),
+    (16, "0340034003400340034003400340034003400340034003400340034003400340",
+      "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+      "0000000000000000000000000000000000000000000000000000000000000000", "11",
+      "08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF",
+      true),
+    (17, "0340034003400340034003400340034003400340034003400340034003400340",
+      "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+      "0000000000000000000000000000000000000000000000000000000000000000", "0102030405060708090A0B0C0D0E0F1011",
+      "5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5",
+      true),
+    (18, "0340034003400340034003400340034003400340034003400340034003400340",
+      "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+      "0000000000000000000000000000000000000000000000000000000000000000",
+      "99999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+      "403B12B0D8555A344175EA7EC746566303321E5DBFA8BE6F091635163ECA79A8585ED3E3170807E7C03B720FC54C7B23897FCBA0E9D0B4A06894CFD249F22367",
+      true),
+  ];
+
+  /// the public key lifted from its x coordinate, the message and the parsed signature;
+  /// None where the key or the signature does not decode
+  fn decode(pubkey: &str, msg: &str, sig: &str) -> Option<(S256Point, Vec<u8>, SchnorrSignature)> {
+    let p = S256Point::lift_x(&bytes_to_int(&decode_hex(pubkey).unwrap()))?;
+    let sig = SchnorrSignature::parse(&decode_hex(sig).unwrap().try_into().unwrap())?;
+    Some((p, decode_hex(msg).unwrap(), sig))
+  }
+
+  #[test]
+  fn bip340_vectors() {
+    for (index, secret, pubkey, aux_rand, msg, sig, valid) in VECTORS {
+      if !secret.is_empty() {
+        let key = PrivateKey::from_bytes(&decode_hex(secret).unwrap().try_into().unwrap()).unwrap();
+        assert_eq!(xonly(&key.point).to_vec(), decode_hex(pubkey).unwrap(), "index {}", index);
+        let aux_rand = decode_hex(aux_rand).unwrap().try_into().unwrap();
+        let signature = sign(&key, &decode_hex(msg).unwrap(), &aux_rand);
+        assert_eq!(signature.serialize().to_vec(), decode_hex(sig).unwrap(), "index {}", index);
+      }
+      let verified = decode(pubkey, msg, sig).is_some_and(|(p, msg, sig)| verify(&p, &msg, &sig));
+      assert_eq!(verified, *valid, "index {}", index);
+    }
+  }
+
+  /// the valid vectors, in order
+  fn valid_batch() -> Vec<(S256Point, Vec<u8>, SchnorrSignature)> {
+    VECTORS.iter()
+      .filter(|v| v.6)
+      .map(|(_, _, pubkey, _, msg, sig, _)| decode(pubkey, msg, sig).unwrap())
+      .collect()
+  }
+
+  fn as_items(batch: &[(S256Point, Vec<u8>, SchnorrSignature)]) -> Vec<(S256Point, &[u8], SchnorrSignature)> {
+    batch.iter().map(|(p, msg, sig)| (p.clone(), &msg[..], sig.clone())).collect()
+  }
+
+  #[test]
+  fn batch_verification() {
+    let batch = valid_batch();
+    assert!(batch_equation_holds(&as_items(&batch)));
+    assert_eq!(verify_batch(&as_items(&batch)), Ok(()));
+    assert_eq!(verify_batch(&[]), Ok(()));
+
+    // a signature valid under another key, in the middle and then at the end
+    for bad in [batch.len() / 2, batch.len() - 1] {
+      let mut tampered = batch.clone();
+      tampered[bad].2 = batch[0].2.clone();
+      let items = as_items(&tampered);
+      assert!(!batch_equation_holds(&items));
+      assert_eq!(items.iter().position(|(p, msg, sig)| !verify(p, msg, sig)), Some(bad));
+      assert_eq!(verify_batch(&items), Err(bad));
+    }
+
+    // a negated s, invalid alone (index 8) and so in a batch as well
+    let (p, msg, sig) = decode(VECTORS[8].2, VECTORS[8].4, VECTORS[8].5).unwrap();
+    let mut items = as_items(&batch);
+    items.insert(3, (p, &msg, sig));
+    assert_eq!(verify_batch(&items), Err(3));
+  }
+}
@@ -0,0 +1,299 @@
+//! Shamir secret sharing over the scalar field mod n
+//!
+//! A share serializes to 38 bytes:
+//! `threshold || index || value (32 bytes) || checksum (4 bytes)`, where the
+//! checksum is the first 4 bytes of SHA256(SHA256(threshold || index || value)).
+
+use std::fmt;
+
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::{Euclid, One, Zero};
+use zeroize::Zeroizing;
+
+use super::{
+  constants::N,
+  field_elements::FieldOperation,
+  helper::{sha256, SecretScalar},
+  s256_field::S256Field,
+};
+
+pub const SHARE_LENGTH: usize = 38;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ShamirError {
+  /// need 1 <= threshold <= shares
+  InvalidThreshold,
+  /// not 38 bytes
+  InvalidLength,
+  /// the checksum does not match the share contents
+  InvalidChecksum,
+  /// index 0 would be the secret itself
+  InvalidIndex,
+  /// share value is not less than n
+  InvalidValue,
+  /// two shares have this index
+  DuplicateIndex(u8),
+  /// the shares were split with different thresholds
+  ThresholdMismatch,
+  /// fewer shares than the threshold
+  NotEnoughShares,
+  /// the shares interpolate to zero, they are not from the same split
+  InvalidSecret,
+}
+
+/// f(index) of a secret split into `threshold`-of-n shares. Like a
+/// `PrivateKey`, the value is kept as 32 bytes that are wiped on drop, and
+/// `Debug` leaves it out.
+#[derive(Eq, PartialEq, Clone)]
+pub struct Share {
+  pub threshold: u8,
+  pub index: u8,
+  value: Zeroizing<[u8; 32]>,
+}
+
+impl fmt::Debug for Share {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Share")
+      .field("threshold", &self.threshold)
+      .field("index", &self.index)
+      .field("value", &"[REDACTED]")
+      .finish()
+  }
+}
+
+impl Share {
+  fn value(&self) -> SecretScalar {
+    SecretScalar::from_bytes_be(self.value.as_ref())
+  }
+
+  /// the serialized share holds the value, so the buffer is wiped on drop
+  pub fn serialize(&self) -> Zeroizing<[u8; SHARE_LENGTH]> {
+    let mut out = Zeroizing::new([0u8; SHARE_LENGTH]);
+    out[0] = self.threshold;
+    out[1] = self.index;
+    out[2..34].copy_from_slice(self.value.as_ref());
+    let sum = checksum(&out[..34]);
+    out[34..].copy_from_slice(&sum);
+    out
+  }
+
+  pub fn parse(bytes: &[u8]) -> Result<Self, ShamirError> {
+    if bytes.len() != SHARE_LENGTH {
+      return Err(ShamirError::InvalidLength)
+    }
+    if checksum(&bytes[..34]) != bytes[34..] {
+      return Err(ShamirError::InvalidChecksum)
+    }
+    let (threshold, index) = (bytes[0], bytes[1]);
+    if threshold == 0 {
+      return Err(ShamirError::InvalidThreshold)
+    }
+    if index == 0 {
+      return Err(ShamirError::InvalidIndex)
+    }
+    if *SecretScalar::from_bytes_be(&bytes[2..34]) >= *N {
+      return Err(ShamirError::InvalidValue)
+    }
+    let mut value = Zeroizing::new([0u8; 32]);
+    value.copy_from_slice(&bytes[2..34]);
+    Ok(Share { threshold, index, value })
+  }
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+  let hash = sha256(&sha256(payload));
+  [hash[0], hash[1], hash[2], hash[3]]
+}
+
+pub(crate) fn random_scalar() -> BigInt {
+  rand::thread_rng().gen_bigint_range(&BigInt::one(), &N)
+}
+
+pub(crate) fn mod_n(n: &BigInt) -> BigInt {
+  Euclid::rem_euclid(n, &N)
+}
+
+fn inverse_mod_n(n: &BigInt) -> BigInt {
+  S256Field::mod_exp(&mod_n(n), &(N.clone() - BigInt::from(2u128)), &N)
+}
+
+/// Lagrange coefficient at x = 0 for the interpolation over `participants`:
+/// lambda_i = prod_{j != i} x_j / (x_j - x_i)
+pub(crate) fn lagrange_coefficient(identifier: u16, participants: &[u16]) -> BigInt {
+  let x_i = BigInt::from(identifier);
+  let (num, den) = participants.iter()
+    .filter(|j| **j != identifier)
+    .fold((BigInt::one(), BigInt::one()), |(num, den), j| {
+      let x_j = BigInt::from(*j);
+      (mod_n(&(num * &x_j)), mod_n(&(den * (x_j - &x_i))))
+    });
+  mod_n(&(num * inverse_mod_n(&den)))
+}
+
+/// f(x) = a_0 + a_1 x + ... + a_{t-1} x^{t-1} mod n
+pub(crate) fn evaluate_polynomial(coefficients: &[SecretScalar], identifier: u16) -> BigInt {
+  let x = BigInt::from(identifier);
+  coefficients.iter().rev()
+    .fold(SecretScalar::new(BigInt::zero()), |acc, a| SecretScalar::new(mod_n(&(&*acc * &x + &**a))))
+    .clone_int()
+}
+
+/// shares f(1), ..., f(shares) of a random polynomial of degree threshold - 1 with f(0) = secret
+pub fn split(secret: &BigInt, threshold: u8, shares: u8) -> Result<Vec<Share>, ShamirError> {
+  if threshold == 0 || threshold > shares {
+    return Err(ShamirError::InvalidThreshold)
+  }
+  // the coefficients determine the secret, so they are wiped on drop
+  let mut coefficients = vec![SecretScalar::new(mod_n(secret))];
+  coefficients.extend((1..threshold).map(|_| SecretScalar::new(random_scalar())));
+
+  Ok((1..=shares)
+    .map(|index| Share {
+      threshold,
+      index,
+      value: SecretScalar::new(evaluate_polynomial(&coefficients, index as u16)).to_bytes32(),
+    })
+    .collect())
+}
+
+/// f(0) interpolated from at least `threshold` shares
+pub fn combine(shares: &[Share]) -> Result<BigInt, ShamirError> {
+  let threshold = match shares.first() {
+    Some(share) => share.threshold,
+    None => return Err(ShamirError::NotEnoughShares)
+  };
+  if shares.iter().any(|share| share.threshold != threshold) {
+    return Err(ShamirError::ThresholdMismatch)
+  }
+  let mut indices: Vec<u16> = Vec::with_capacity(shares.len());
+  for share in shares {
+    if share.index == 0 {
+      return Err(ShamirError::InvalidIndex)
+    }
+    if indices.contains(&(share.index as u16)) {
+      return Err(ShamirError::DuplicateIndex(share.index))
+    }
+    indices.push(share.index as u16);
+  }
+  if shares.len() < threshold as usize {
+    return Err(ShamirError::NotEnoughShares)
+  }
+
+  let secret = shares.iter().fold(BigInt::zero(), |acc, share| {
+    mod_n(&(acc + lagrange_coefficient(share.index as u16, &indices) * &*share.value()))
+  });
+  if secret.is_zero() {
+    return Err(ShamirError::InvalidSecret)
+  }
+  Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::helper::{decode_hex, encode_hex};
+
+  fn subsets(shares: &[Share], size: usize) -> Vec<Vec<Share>> {
+    if size == 0 {
+      return vec![vec![]]
+    }
+    (0..shares.len())
+      .flat_map(|i| {
+        subsets(&shares[i + 1..], size - 1).into_iter().map(move |mut rest| {
+          rest.insert(0, shares[i].clone());
+          rest
+        })
+      })
+      .collect()
+  }
+
+  #[test]
+  fn split_and_combine() {
+    let secret = BigInt::from(0xdead_beef_u64) << 200;
+    let shares = split(&secret, 3, 5).unwrap();
+    assert_eq!(shares.iter().map(|s| s.index).collect::<Vec<u8>>(), vec![1, 2, 3, 4, 5]);
+
+    // any 3, 4 or 5 shares recover the secret, in any order
+    for size in 3..=5 {
+      for mut subset in subsets(&shares, size) {
+        assert_eq!(combine(&subset).unwrap(), secret);
+        subset.reverse();
+        assert_eq!(combine(&subset).unwrap(), secret);
+      }
+    }
+    for subset in subsets(&shares, 2) {
+      assert_eq!(combine(&subset).unwrap_err(), ShamirError::NotEnoughShares);
+    }
+    assert_eq!(combine(&[]).unwrap_err(), ShamirError::NotEnoughShares);
+
+    // 1-of-n shares are the secret itself, n-of-n needs every share
+    assert!(split(&secret, 1, 3).unwrap().iter().all(|s| *s.value() == secret));
+    let all = split(&secret, 4, 4).unwrap();
+    assert_eq!(combine(&all).unwrap(), secret);
+    assert_eq!(combine(&all[1..]).unwrap_err(), ShamirError::NotEnoughShares);
+
+    for (threshold, count) in [(0, 3), (4, 3), (0, 0)] {
+      assert_eq!(split(&secret, threshold, count).unwrap_err(), ShamirError::InvalidThreshold);
+    }
+  }
+
+  #[test]
+  fn inconsistent_shares_are_rejected() {
+    let secret = BigInt::from(12345);
+    let shares = split(&secret, 2, 3).unwrap();
+    assert_eq!(
+      combine(&[shares[1].clone(), shares[1].clone()]).unwrap_err(),
+      ShamirError::DuplicateIndex(2)
+    );
+    let other = split(&secret, 3, 3).unwrap();
+    assert_eq!(combine(&[shares[0].clone(), other[1].clone()]).unwrap_err(), ShamirError::ThresholdMismatch);
+
+    let mut zero = shares[0].clone();
+    zero.index = 0;
+    assert_eq!(combine(&[zero, shares[1].clone()]).unwrap_err(), ShamirError::InvalidIndex);
+  }
+
+  #[test]
+  fn serialization() {
+    let share = Share::parse(&decode_hex(
+      "02030102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f205bcbc423"
+    ).unwrap()).unwrap();
+    assert_eq!((share.threshold, share.index), (2, 3));
+    assert_eq!(encode_hex(share.value.as_ref()), "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20");
+
+    for share in split(&BigInt::from(99), 2, 3).unwrap() {
+      let bytes = share.serialize();
+      assert_eq!(bytes.len(), SHARE_LENGTH);
+      assert_eq!((bytes[0], bytes[1]), (2, share.index));
+      assert_eq!(Share::parse(bytes.as_ref()).unwrap(), share);
+
+      // a change anywhere is caught by the checksum
+      for i in 0..SHARE_LENGTH {
+        let mut corrupted = *bytes;
+        corrupted[i] ^= 0x40;
+        assert_eq!(Share::parse(&corrupted).unwrap_err(), ShamirError::InvalidChecksum);
+      }
+      assert_eq!(Share::parse(&bytes[..37]).unwrap_err(), ShamirError::InvalidLength);
+    }
+
+    // well-formed checksums over bad contents
+    let with_checksum = |threshold: u8, index: u8, value: [u8; 32]| {
+      let mut bytes = vec![threshold, index];
+      bytes.extend_from_slice(&value);
+      let sum = checksum(&bytes);
+      bytes.extend_from_slice(&sum);
+      bytes
+    };
+    assert_eq!(Share::parse(&with_checksum(0, 1, [1; 32])).unwrap_err(), ShamirError::InvalidThreshold);
+    assert_eq!(Share::parse(&with_checksum(2, 0, [1; 32])).unwrap_err(), ShamirError::InvalidIndex);
+    assert_eq!(Share::parse(&with_checksum(2, 1, [0xff; 32])).unwrap_err(), ShamirError::InvalidValue);
+  }
+
+  #[test]
+  fn debug_redacts_the_value() {
+    let share = Share::parse(&decode_hex(
+      "02030102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f205bcbc423"
+    ).unwrap()).unwrap();
+    assert_eq!(format!("{:?}", share), r#"Share { threshold: 2, index: 3, value: "[REDACTED]" }"#);
+  }
+}
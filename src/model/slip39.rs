@@ -0,0 +1,688 @@
+//! SLIP-39 Shamir mnemonic shares
+//! https://github.com/satoshilabs/slips/blob/master/slip-0039.md
+//!
+//! Two levels of sharing over GF(256): the encrypted master secret is split
+//! into groups, and each group secret into member shares. A share is a
+//! mnemonic of 10-bit words: `id || ext || e || GI || Gt || g || I || t || ps || C`
+//! with an RS1024 checksum C over the words.
+
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use num_traits::Zero;
+use once_cell::sync::Lazy;
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use sha2::Sha256;
+
+use super::{
+  constants::N,
+  helper::bytes_to_int,
+  private_key::PrivateKey,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+static WORDLIST: Lazy<Vec<&'static str>> = Lazy::new(|| {
+  include_str!("wordlist/slip39.txt").lines().collect()
+});
+
+/// GF(256) with the Rijndael polynomial x^8 + x^4 + x^3 + x + 1, generator 3
+static EXP_LOG: Lazy<([u8; 255], [u8; 256])> = Lazy::new(|| {
+  let (mut exp, mut log) = ([0u8; 255], [0u8; 256]);
+  let mut poly: u16 = 1;
+  for (i, e) in exp.iter_mut().enumerate() {
+    *e = poly as u8;
+    log[poly as usize] = i as u8;
+    poly = (poly << 1) ^ poly;
+    if poly & 0x100 != 0 {
+      poly ^= 0x11b;
+    }
+  }
+  (exp, log)
+});
+
+const RADIX_BITS: usize = 10;
+const METADATA_LENGTH_WORDS: usize = 7;
+const CHECKSUM_LENGTH_WORDS: usize = 3;
+const MIN_MNEMONIC_LENGTH_WORDS: usize = 20;
+const MIN_STRENGTH_BYTES: usize = 16;
+const MAX_SHARE_COUNT: u8 = 16;
+const DIGEST_LENGTH_BYTES: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const ROUND_COUNT: u8 = 4;
+const BASE_ITERATION_COUNT: u32 = 10000;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Slip39Error {
+  /// master secrets are at least 16 bytes and of even length
+  InvalidMasterSecret,
+  /// the passphrase has characters outside printable ASCII
+  InvalidPassphrase,
+  /// iteration exponents are 4 bits
+  InvalidIterationExponent,
+  /// thresholds are 1 to 16 and at most the share count; a 1-of-n group must have n = 1
+  InvalidThreshold,
+  /// this word is not in the wordlist
+  UnknownWord(String),
+  /// fewer than 20 words or more than 8 bits of padding
+  InvalidLength,
+  /// the padding bits of the share value are not zero
+  InvalidPadding,
+  /// the RS1024 checksum does not match
+  InvalidChecksum,
+  /// group threshold above the group count, or an identifier or index
+  /// too wide for its field in the share metadata
+  InvalidGroupParameters,
+  /// the shares do not come from the same split
+  MismatchedShares,
+  /// not enough groups, or not enough members within the groups
+  InsufficientShares,
+  /// the recovered secret does not match its digest share
+  InvalidDigest,
+}
+
+/// One mnemonic share; `Share::new` and `Share::from_mnemonic` keep every
+/// field within its bit width, so a share always encodes
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Share {
+  /// 15 bit random identifier shared by every share of the split
+  identifier: u16,
+  /// the salt omits the identifier, so groups can be added to an existing split
+  extendable: bool,
+  /// PBKDF2 runs 10000 * 2^e iterations in total
+  iteration_exponent: u8,
+  group_index: u8,
+  group_threshold: u8,
+  group_count: u8,
+  member_index: u8,
+  member_threshold: u8,
+  value: Vec<u8>,
+}
+
+fn rs1024_polymod(values: &[u16]) -> u32 {
+  const GEN: [u32; 10] = [
+    0x00e0_e040, 0x01c1_c080, 0x0383_8100, 0x0707_0200, 0x0e0e_0009,
+    0x1c0c_2412, 0x3808_6c24, 0x3090_fc48, 0x21b1_f890, 0x03f3_f120,
+  ];
+  values.iter().fold(1u32, |chk, v| {
+    let b = chk >> 20;
+    let chk = ((chk & 0xfffff) << 10) ^ *v as u32;
+    GEN.iter().enumerate()
+      .filter(|(i, _)| (b >> i) & 1 == 1)
+      .fold(chk, |chk, (_, g)| chk ^ g)
+  })
+}
+
+fn customization_string(extendable: bool) -> &'static [u8] {
+  if extendable { b"shamir_extendable" } else { b"shamir" }
+}
+
+fn rs1024_create_checksum(data: &[u16], extendable: bool) -> [u16; 3] {
+  let mut values: Vec<u16> = customization_string(extendable).iter().map(|c| *c as u16).collect();
+  values.extend_from_slice(data);
+  values.extend_from_slice(&[0, 0, 0]);
+  let polymod = rs1024_polymod(&values) ^ 1;
+  [(polymod >> 20) as u16 & 1023, (polymod >> 10) as u16 & 1023, polymod as u16 & 1023]
+}
+
+fn rs1024_verify_checksum(data: &[u16], extendable: bool) -> bool {
+  let mut values: Vec<u16> = customization_string(extendable).iter().map(|c| *c as u16).collect();
+  values.extend_from_slice(data);
+  rs1024_polymod(&values) == 1
+}
+
+/// big-endian bytes as 10-bit words, left padded with zero bits
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+  let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+  let padding = word_count * RADIX_BITS - bytes.len() * 8;
+  (0..word_count)
+    .map(|i| {
+      (0..RADIX_BITS).fold(0u16, |acc, b| {
+        let bit = (i * RADIX_BITS + b).checked_sub(padding)
+          .map(|pos| (bytes[pos / 8] >> (7 - pos % 8)) & 1)
+          .unwrap_or(0);
+        acc << 1 | bit as u16
+      })
+    })
+    .collect()
+}
+
+/// f(x) for the polynomial through `shares`, byte by byte
+fn interpolate(shares: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+  if let Some((_, value)) = shares.iter().find(|(x_i, _)| *x_i == x) {
+    return value.clone()
+  }
+  let (exp, log) = &*EXP_LOG;
+  let log_prod: i32 = shares.iter().map(|(x_i, _)| log[(x_i ^ x) as usize] as i32).sum();
+
+  let mut result = vec![0u8; shares[0].1.len()];
+  for (x_i, value) in shares {
+    let log_others: i32 = shares.iter().map(|(x_j, _)| log[(x_i ^ x_j) as usize] as i32).sum();
+    let log_basis = (log_prod - log[(x_i ^ x) as usize] as i32 - log_others).rem_euclid(255);
+    for (r, v) in result.iter_mut().zip(value) {
+      if *v != 0 {
+        *r ^= exp[((log[*v as usize] as i32 + log_basis) % 255) as usize];
+      }
+    }
+  }
+  result
+}
+
+fn create_digest(random_data: &[u8], shared_secret: &[u8]) -> Vec<u8> {
+  let mut mac = HmacSha256::new_from_slice(random_data)
+    .expect("HMAC can take key of any size");
+  mac.update(shared_secret);
+  mac.finalize().into_bytes()[..DIGEST_LENGTH_BYTES].to_vec()
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+  let mut out = vec![0u8; len];
+  rand::thread_rng().fill_bytes(&mut out);
+  out
+}
+
+/// `threshold`-of-`count` split; the digest and the secret sit at x = 254 and x = 255
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> Vec<(u8, Vec<u8>)> {
+  if threshold == 1 {
+    return (0..count).map(|i| (i, secret.to_vec())).collect()
+  }
+  let random_share_count = threshold - 2;
+  let mut shares: Vec<(u8, Vec<u8>)> = (0..random_share_count)
+    .map(|i| (i, random_bytes(secret.len())))
+    .collect();
+
+  let random_part = random_bytes(secret.len() - DIGEST_LENGTH_BYTES);
+  let mut digest_share = create_digest(&random_part, secret);
+  digest_share.extend(random_part);
+
+  let mut base_shares = shares.clone();
+  base_shares.push((DIGEST_INDEX, digest_share));
+  base_shares.push((SECRET_INDEX, secret.to_vec()));
+  shares.extend((random_share_count..count).map(|i| (i, interpolate(&base_shares, i))));
+  shares
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, Slip39Error> {
+  if threshold == 1 {
+    return Ok(shares[0].1.clone())
+  }
+  let secret = interpolate(shares, SECRET_INDEX);
+  let digest_share = interpolate(shares, DIGEST_INDEX);
+  let (digest, random_part) = digest_share.split_at(DIGEST_LENGTH_BYTES);
+  if digest != create_digest(random_part, &secret) {
+    return Err(Slip39Error::InvalidDigest)
+  }
+  Ok(secret)
+}
+
+fn salt(identifier: u16, extendable: bool) -> Vec<u8> {
+  if extendable {
+    return vec![]
+  }
+  let mut salt = customization_string(false).to_vec();
+  salt.extend_from_slice(&identifier.to_be_bytes());
+  salt
+}
+
+/// F(i, R) = PBKDF2-HMAC-SHA256(i || passphrase, salt || R, 2500 * 2^e)
+fn round_function(i: u8, passphrase: &[u8], e: u8, salt: &[u8], r: &[u8]) -> Vec<u8> {
+  let mut password = vec![i];
+  password.extend_from_slice(passphrase);
+  let mut data = salt.to_vec();
+  data.extend_from_slice(r);
+  let mut out = vec![0u8; r.len()];
+  pbkdf2_hmac::<Sha256>(&password, &data, (BASE_ITERATION_COUNT << e) / ROUND_COUNT as u32, &mut out);
+  out
+}
+
+/// four round Feistel network; `rounds` runs 0..4 to encrypt and 3..=0 to decrypt
+fn feistel(
+  data: &[u8], passphrase: &[u8], e: u8, identifier: u16, extendable: bool,
+  rounds: impl Iterator<Item = u8>
+) -> Vec<u8> {
+  let salt = salt(identifier, extendable);
+  let (mut l, mut r) = (data[..data.len() / 2].to_vec(), data[data.len() / 2..].to_vec());
+  for i in rounds {
+    let f = round_function(i, passphrase, e, &salt, &r);
+    let next_r: Vec<u8> = l.iter().zip(f).map(|(a, b)| a ^ b).collect();
+    l = r;
+    r = next_r;
+  }
+  r.extend(l);
+  r
+}
+
+fn check_passphrase(passphrase: &[u8]) -> Result<(), Slip39Error> {
+  if passphrase.iter().all(|c| (32..=126).contains(c)) {
+    Ok(())
+  } else {
+    Err(Slip39Error::InvalidPassphrase)
+  }
+}
+
+impl Share {
+  /// Checks the metadata against the share format: thresholds and counts
+  /// are 1 to 16, indices 4 bits and the value at least 16 bytes
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    identifier: u16, extendable: bool, iteration_exponent: u8,
+    group_index: u8, group_threshold: u8, group_count: u8,
+    member_index: u8, member_threshold: u8, value: Vec<u8>
+  ) -> Result<Self, Slip39Error> {
+    if iteration_exponent > 15 {
+      return Err(Slip39Error::InvalidIterationExponent)
+    }
+    if !(1..=MAX_SHARE_COUNT).contains(&group_threshold) || !(1..=MAX_SHARE_COUNT).contains(&group_count)
+      || !(1..=MAX_SHARE_COUNT).contains(&member_threshold) {
+      return Err(Slip39Error::InvalidThreshold)
+    }
+    if identifier >> 15 != 0 || group_index >= MAX_SHARE_COUNT || member_index >= MAX_SHARE_COUNT
+      || group_threshold > group_count {
+      return Err(Slip39Error::InvalidGroupParameters)
+    }
+    if value.len() < MIN_STRENGTH_BYTES {
+      return Err(Slip39Error::InvalidLength)
+    }
+    Ok(Share {
+      identifier, extendable, iteration_exponent, group_index, group_threshold,
+      group_count, member_index, member_threshold, value,
+    })
+  }
+
+  pub fn identifier(&self) -> u16 {
+    self.identifier
+  }
+
+  pub fn extendable(&self) -> bool {
+    self.extendable
+  }
+
+  pub fn iteration_exponent(&self) -> u8 {
+    self.iteration_exponent
+  }
+
+  pub fn group_index(&self) -> u8 {
+    self.group_index
+  }
+
+  pub fn group_threshold(&self) -> u8 {
+    self.group_threshold
+  }
+
+  pub fn group_count(&self) -> u8 {
+    self.group_count
+  }
+
+  pub fn member_index(&self) -> u8 {
+    self.member_index
+  }
+
+  pub fn member_threshold(&self) -> u8 {
+    self.member_threshold
+  }
+
+  pub fn value(&self) -> &[u8] {
+    &self.value
+  }
+
+  pub fn words(&self) -> Vec<&'static str> {
+    let id_exp = (self.identifier as u32) << 5
+      | (self.extendable as u32) << 4
+      | self.iteration_exponent as u32;
+    let params = (self.group_index as u32) << 16
+      | ((self.group_threshold - 1) as u32) << 12
+      | ((self.group_count - 1) as u32) << 8
+      | (self.member_index as u32) << 4
+      | (self.member_threshold - 1) as u32;
+
+    let mut data: Vec<u16> = vec![
+      (id_exp >> 10) as u16, id_exp as u16 & 1023,
+      (params >> 10) as u16, params as u16 & 1023,
+    ];
+    data.extend(bytes_to_words(&self.value));
+    let checksum = rs1024_create_checksum(&data, self.extendable);
+    data.extend_from_slice(&checksum);
+    data.into_iter().map(|i| WORDLIST[i as usize]).collect()
+  }
+
+  pub fn to_mnemonic(&self) -> String {
+    self.words().join(" ")
+  }
+
+  pub fn from_mnemonic(mnemonic: &str) -> Result<Self, Slip39Error> {
+    let data = mnemonic.split_whitespace()
+      .map(|word| {
+        let word = word.to_lowercase();
+        WORDLIST.binary_search(&word.as_str())
+          .map(|i| i as u16)
+          .map_err(|_| Slip39Error::UnknownWord(word))
+      })
+      .collect::<Result<Vec<u16>, Slip39Error>>()?;
+    if data.len() < MIN_MNEMONIC_LENGTH_WORDS {
+      return Err(Slip39Error::InvalidLength)
+    }
+    let padding = (RADIX_BITS * (data.len() - METADATA_LENGTH_WORDS)) % 16;
+    if padding > 8 {
+      return Err(Slip39Error::InvalidLength)
+    }
+
+    let id_exp = (data[0] as u32) << 10 | data[1] as u32;
+    let extendable = (id_exp >> 4) & 1 == 1;
+    if !rs1024_verify_checksum(&data, extendable) {
+      return Err(Slip39Error::InvalidChecksum)
+    }
+    let params = (data[2] as u32) << 10 | data[3] as u32;
+    let nibble = |shift: u32| ((params >> shift) & 0xf) as u8;
+
+    // the value words hold `padding` zero bits and then the value bytes
+    let value_words = &data[4..data.len() - CHECKSUM_LENGTH_WORDS];
+    let value_bits = value_words.len() * RADIX_BITS - padding;
+    let mut value = vec![0u8; value_bits / 8];
+    for (i, word) in value_words.iter().enumerate() {
+      for b in 0..RADIX_BITS {
+        if (word >> (RADIX_BITS - 1 - b)) & 1 == 1 {
+          let pos = i * RADIX_BITS + b;
+          if pos < padding {
+            return Err(Slip39Error::InvalidPadding)
+          }
+          let pos = pos - padding;
+          value[pos / 8] |= 0x80 >> (pos % 8);
+        }
+      }
+    }
+
+    Share::new(
+      (id_exp >> 5) as u16, extendable, (id_exp & 0xf) as u8,
+      nibble(16), nibble(12) + 1, nibble(8) + 1, nibble(4), nibble(0) + 1, value
+    )
+  }
+}
+
+/// Splits `master_secret` into mnemonic shares: `groups` lists
+/// (member threshold, member count) and any `group_threshold` groups recover it
+pub fn generate_mnemonics(
+  group_threshold: u8, groups: &[(u8, u8)], master_secret: &[u8],
+  passphrase: &[u8], extendable: bool, iteration_exponent: u8
+) -> Result<Vec<Vec<String>>, Slip39Error> {
+  if master_secret.len() < MIN_STRENGTH_BYTES || !master_secret.len().is_multiple_of(2) {
+    return Err(Slip39Error::InvalidMasterSecret)
+  }
+  check_passphrase(passphrase)?;
+  if iteration_exponent > 15 {
+    return Err(Slip39Error::InvalidIterationExponent)
+  }
+  if group_threshold == 0 || group_threshold as usize > groups.len() || groups.len() > MAX_SHARE_COUNT as usize {
+    return Err(Slip39Error::InvalidThreshold)
+  }
+  for (member_threshold, member_count) in groups {
+    if *member_threshold == 0 || member_threshold > member_count || *member_count > MAX_SHARE_COUNT
+      || (*member_threshold == 1 && *member_count > 1) {
+      return Err(Slip39Error::InvalidThreshold)
+    }
+  }
+
+  let identifier = (rand::thread_rng().next_u32() & 0x7fff) as u16;
+  let encrypted = feistel(master_secret, passphrase, iteration_exponent, identifier, extendable, 0..ROUND_COUNT);
+  let group_shares = split_secret(group_threshold, groups.len() as u8, &encrypted);
+
+  Ok(groups.iter().zip(group_shares)
+    .map(|((member_threshold, member_count), (group_index, group_secret))| {
+      split_secret(*member_threshold, *member_count, &group_secret).into_iter()
+        .map(|(member_index, value)| Share {
+          identifier,
+          extendable,
+          iteration_exponent,
+          group_index,
+          group_threshold,
+          group_count: groups.len() as u8,
+          member_index,
+          member_threshold: *member_threshold,
+          value,
+        }.to_mnemonic())
+        .collect()
+    })
+    .collect())
+}
+
+/// Master secret from enough mnemonics of enough groups; shares of
+/// groups below their member threshold are ignored
+pub fn combine_mnemonics(mnemonics: &[&str], passphrase: &[u8]) -> Result<Vec<u8>, Slip39Error> {
+  check_passphrase(passphrase)?;
+  let shares = mnemonics.iter()
+    .map(|m| Share::from_mnemonic(m))
+    .collect::<Result<Vec<Share>, Slip39Error>>()?;
+  let first = shares.first().ok_or(Slip39Error::InsufficientShares)?;
+
+  let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+  for share in &shares {
+    if (share.identifier, share.extendable, share.iteration_exponent, share.group_threshold, share.group_count)
+      != (first.identifier, first.extendable, first.iteration_exponent, first.group_threshold, first.group_count)
+      || share.value.len() != first.value.len() {
+      return Err(Slip39Error::MismatchedShares)
+    }
+    let group = groups.entry(share.group_index).or_default();
+    if let Some(other) = group.iter().find(|s| s.member_index == share.member_index) {
+      if *other != share {
+        return Err(Slip39Error::MismatchedShares)
+      }
+      continue
+    }
+    if group.first().is_some_and(|s| s.member_threshold != share.member_threshold) {
+      return Err(Slip39Error::MismatchedShares)
+    }
+    group.push(share);
+  }
+
+  let group_shares = groups.iter()
+    .filter(|(_, members)| members.len() >= members[0].member_threshold as usize)
+    .take(first.group_threshold as usize)
+    .map(|(group_index, members)| {
+      let threshold = members[0].member_threshold;
+      let member_shares: Vec<(u8, Vec<u8>)> = members.iter()
+        .take(threshold as usize)
+        .map(|s| (s.member_index, s.value.clone()))
+        .collect();
+      recover_secret(threshold, &member_shares).map(|secret| (*group_index, secret))
+    })
+    .collect::<Result<Vec<(u8, Vec<u8>)>, Slip39Error>>()?;
+  if group_shares.len() < first.group_threshold as usize {
+    return Err(Slip39Error::InsufficientShares)
+  }
+
+  let encrypted = recover_secret(first.group_threshold, &group_shares)?;
+  Ok(feistel(
+    &encrypted, passphrase, first.iteration_exponent, first.identifier, first.extendable,
+    (0..ROUND_COUNT).rev()
+  ))
+}
+
+/// SLIP-39 backup of the 32 byte secret of `key`, extendable, iteration exponent 1
+pub fn split_private_key(
+  key: &PrivateKey, group_threshold: u8, groups: &[(u8, u8)], passphrase: &[u8]
+) -> Result<Vec<Vec<String>>, Slip39Error> {
+  generate_mnemonics(group_threshold, groups, key.to_bytes().as_ref(), passphrase, true, 1)
+}
+
+pub fn recover_private_key(mnemonics: &[&str], passphrase: &[u8]) -> Result<PrivateKey, Slip39Error> {
+  let secret = bytes_to_int(&combine_mnemonics(mnemonics, passphrase)?);
+  if secret.is_zero() || secret >= *N {
+    return Err(Slip39Error::InvalidMasterSecret)
+  }
+  Ok(PrivateKey::new(secret))
+}
+
+#[cfg(test)]
+mod tests {
+  use num_bigint::BigInt;
+
+  use super::*;
+  use crate::model::helper::{decode_hex, encode_hex};
+
+  fn combine(mnemonics: &[&str]) -> Result<String, Slip39Error> {
+    combine_mnemonics(mnemonics, b"TREZOR").map(|secret| encode_hex(&secret))
+  }
+
+  // https://github.com/trezor/python-shamir-mnemonic/blob/master/vectors.json
+  #[test]
+  fn slip39_vectors() {
+    // 1. valid mnemonic without sharing (128 bits)
+    assert_eq!(combine(&[
+      "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"
+    ]).unwrap(), "bb54aac4b89dc868ba37d9cc21b2cece");
+    // 2. mnemonic with invalid checksum (128 bits)
+    assert_eq!(combine(&[
+      "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision kidney"
+    ]).unwrap_err(), Slip39Error::InvalidChecksum);
+    // 3. mnemonic with invalid padding (128 bits)
+    assert_eq!(combine(&[
+      "duckling enlarge academic academic email result length solution fridge kidney coal piece deal husband erode duke ajar music cargo fitness"
+    ]).unwrap_err(), Slip39Error::InvalidPadding);
+
+    // 4. basic sharing 2-of-3 (128 bits)
+    let basic = [
+      "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+      "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+    ];
+    assert_eq!(combine(&basic).unwrap(), "b43ceb7e57a0ea8766221624d01b0864");
+    // 5. basic sharing 2-of-3, one share
+    assert_eq!(combine(&basic[..1]).unwrap_err(), Slip39Error::InsufficientShares);
+    // 6. mnemonics with different identifiers
+    assert_eq!(combine(&[
+      "adequate smoking academic acid debut wine petition glen cluster slow rhyme slow simple epidemic rumor junk tracks treat olympic tolerate",
+      "adequate stay academic agency agency formal party ting frequent learn upstairs remember smear leaf damage anatomy ladle market hush corner",
+    ]).unwrap_err(), Slip39Error::MismatchedShares);
+    // 7. mnemonics with different iteration exponents
+    assert_eq!(combine(&[
+      "peasant leaves academic acid desert exact olympic math alive axle trial tackle drug deny decent smear dominant desert bucket remind",
+      "peasant leader academic agency cultural blessing percent network envelope medal junk primary human pumps jacket fragment payroll ticket evoke voice",
+    ]).unwrap_err(), Slip39Error::MismatchedShares);
+
+    // group sharing, threshold 2 of 4: group 1 is 1-of-1, group 2 is 3-of-5
+    let groups = [
+      "eraser senior beard romp adorn nuclear spill corner cradle style ancient family general leader ambition exchange unusual garlic promise voice",
+      "eraser senior ceramic snake clay various huge numb argue hesitate auction category timber browser greatest hanger petition script leaf pickup",
+      "eraser senior ceramic shaft dynamic become junior wrist silver peasant force math alto coal amazing segment yelp velvet image paces",
+      "eraser senior ceramic round column hawk trust auction smug shame alive greatest sheriff living perfect corner chest sled fumes adequate",
+    ];
+    assert_eq!(combine(&groups).unwrap(), "7c3397a292a5941682d7a4ae2d898d11");
+    // one complete group is below the group threshold
+    assert_eq!(combine(&groups[1..]).unwrap_err(), Slip39Error::InsufficientShares);
+    // group 2 below its member threshold does not count
+    assert_eq!(combine(&groups[..3]).unwrap_err(), Slip39Error::InsufficientShares);
+
+    // 256 bit master secret
+    assert_eq!(combine(&[
+      "theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck"
+    ]).unwrap(), "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92");
+
+    for m in basic.iter().chain(&groups) {
+      assert_eq!(Share::from_mnemonic(m).unwrap().to_mnemonic(), *m);
+    }
+  }
+
+  #[test]
+  fn wordlist() {
+    assert_eq!(WORDLIST.len(), 1024);
+    assert_eq!((WORDLIST[0], WORDLIST[1023]), ("academic", "zero"));
+    // sorted, so binary search works, and every word is fixed by its first four letters
+    assert!(WORDLIST.windows(2).all(|w| w[0] < w[1]));
+    assert!(WORDLIST.windows(2).all(|w| w[0].get(..4) != w[1].get(..4)));
+    assert!(WORDLIST.iter().all(|w| (4..=8).contains(&w.len()) && w.bytes().all(|c| c.is_ascii_lowercase())));
+  }
+
+  #[test]
+  fn rs1024() {
+    let data: Vec<u16> = (0..17).map(|i| i * 61 % 1024).collect();
+    for extendable in [false, true] {
+      let mut words = data.clone();
+      words.extend_from_slice(&rs1024_create_checksum(&data, extendable));
+      assert!(rs1024_verify_checksum(&words, extendable));
+      // the customization string is part of the checksum
+      assert!(!rs1024_verify_checksum(&words, !extendable));
+      // any single word error is detected
+      for i in 0..words.len() {
+        let mut corrupted = words.clone();
+        corrupted[i] ^= 1 + (i as u16 % 1023);
+        assert!(!rs1024_verify_checksum(&corrupted, extendable));
+      }
+    }
+  }
+
+  #[test]
+  fn feistel_round_trip() {
+    let secret = decode_hex("bb54aac4b89dc868ba37d9cc21b2cece").unwrap();
+    for extendable in [false, true] {
+      let encrypted = feistel(&secret, b"TREZOR", 0, 7945, extendable, 0..ROUND_COUNT);
+      assert_ne!(encrypted, secret);
+      assert_eq!(feistel(&encrypted, b"TREZOR", 0, 7945, extendable, (0..ROUND_COUNT).rev()), secret);
+      assert_ne!(feistel(&encrypted, b"TREZO", 0, 7945, extendable, (0..ROUND_COUNT).rev()), secret);
+    }
+    // the identifier is only in the salt of non-extendable shares
+    assert_eq!(
+      feistel(&secret, b"", 0, 1, true, 0..ROUND_COUNT),
+      feistel(&secret, b"", 0, 2, true, 0..ROUND_COUNT)
+    );
+    assert_ne!(
+      feistel(&secret, b"", 0, 1, false, 0..ROUND_COUNT),
+      feistel(&secret, b"", 0, 2, false, 0..ROUND_COUNT)
+    );
+  }
+
+  #[test]
+  fn group_thresholds() {
+    let secret = decode_hex("0102030405060708090a0b0c0d0e0f10").unwrap();
+    let shares = generate_mnemonics(2, &[(2, 3), (1, 1), (3, 5)], &secret, b"", false, 0).unwrap();
+    let pick = |picks: &[(usize, usize)]| -> Vec<&str> {
+      picks.iter().map(|(g, m)| shares[*g][*m].as_str()).collect()
+    };
+    assert_eq!(combine_mnemonics(&pick(&[(0, 2), (0, 0), (1, 0)]), b"").unwrap(), secret);
+    assert_eq!(combine_mnemonics(&pick(&[(2, 4), (2, 1), (2, 3), (0, 1), (0, 2)]), b"").unwrap(), secret);
+    assert_eq!(combine_mnemonics(&pick(&[(2, 0), (2, 1), (2, 2), (1, 0)]), b"").unwrap(), secret);
+    // a wrong passphrase still decrypts, to a different secret
+    assert_ne!(combine_mnemonics(&pick(&[(0, 0), (0, 1), (1, 0)]), b"x").unwrap(), secret);
+    assert_eq!(
+      combine_mnemonics(&pick(&[(0, 0), (1, 0), (2, 0), (2, 1)]), b"").unwrap_err(),
+      Slip39Error::InsufficientShares
+    );
+    assert_eq!(combine_mnemonics(&pick(&[(1, 0)]), b"").unwrap_err(), Slip39Error::InsufficientShares);
+
+    for (group_threshold, groups) in [(0, vec![(1, 1)]), (2, vec![(1, 1)]), (1, vec![(0, 1)]), (1, vec![(3, 2)]), (1, vec![(1, 2)])] {
+      assert_eq!(
+        generate_mnemonics(group_threshold, &groups, &secret, b"", false, 0).unwrap_err(),
+        Slip39Error::InvalidThreshold
+      );
+    }
+
+    let key = PrivateKey::new(BigInt::from(0x5eed_u32));
+    let shares = split_private_key(&key, 1, &[(2, 3)], b"pw").unwrap();
+    let recovered = recover_private_key(&[&shares[0][2], &shares[0][0]], b"pw").unwrap();
+    assert_eq!(recovered.to_bytes().as_ref(), key.to_bytes().as_ref());
+  }
+
+  #[test]
+  fn shares_are_validated() {
+    let value = vec![0u8; 16];
+    let share = |gt, gc, mt| Share::new(1, false, 0, 0, gt, gc, 0, mt, value.clone());
+    assert!(share(1, 1, 1).is_ok());
+    assert!(share(16, 16, 16).is_ok());
+    for (gt, gc, mt) in [(0, 1, 1), (1, 0, 1), (1, 1, 0), (17, 17, 1), (1, 1, 17)] {
+      assert_eq!(share(gt, gc, mt).unwrap_err(), Slip39Error::InvalidThreshold);
+    }
+    assert_eq!(share(3, 2, 1).unwrap_err(), Slip39Error::InvalidGroupParameters);
+    assert_eq!(
+      Share::new(1 << 15, false, 0, 0, 1, 1, 0, 1, value.clone()).unwrap_err(),
+      Slip39Error::InvalidGroupParameters
+    );
+    assert_eq!(
+      Share::new(1, false, 0, 16, 1, 1, 0, 1, value.clone()).unwrap_err(),
+      Slip39Error::InvalidGroupParameters
+    );
+    assert_eq!(Share::new(1, false, 16, 0, 1, 1, 0, 1, value.clone()).unwrap_err(), Slip39Error::InvalidIterationExponent);
+    assert_eq!(Share::new(1, false, 0, 0, 1, 1, 0, 1, vec![0; 15]).unwrap_err(), Slip39Error::InvalidLength);
+
+    let share = Share::new(21219, true, 3, 2, 2, 3, 5, 4, (0..32).collect()).unwrap();
+    assert_eq!(Share::from_mnemonic(&share.to_mnemonic()).unwrap(), share);
+  }
+}
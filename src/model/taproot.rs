@@ -0,0 +1,235 @@
+use num_bigint::BigInt;
+
+use super::{
+  constants::N,
+  helper::{bytes_to_int, encode_varint, tagged_hash},
+  schnorr::xonly,
+  s256_point::S256Point,
+};
+
+/// BIP342 tapscript leaf version
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum TaprootError {
+  /// leaf versions are even, the low bit of the control block's first byte is the parity of Q
+  InvalidLeafVersion(u8),
+}
+
+/// t = int(hash_TapTweak(bytes(P) || h)), h is empty for key-path only outputs;
+/// None when t is not less than the curve order
+/// https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki
+pub fn tap_tweak_hash(internal_key: &[u8; 32], merkle_root: Option<[u8; 32]>) -> Option<BigInt> {
+  let mut data = internal_key.to_vec();
+  if let Some(root) = merkle_root {
+    data.extend_from_slice(&root);
+  }
+  let t = bytes_to_int(&tagged_hash("TapTweak", &data));
+  if t >= *N {
+    return None
+  }
+  Some(t)
+}
+
+pub fn tap_leaf_hash(version: u8, script: &[u8]) -> [u8; 32] {
+  let mut data = vec![version];
+  data.extend(encode_varint(script.len() as u64));
+  data.extend_from_slice(script);
+  tagged_hash("TapLeaf", &data)
+}
+
+/// children are sorted so a branch commits to the pair regardless of order
+pub fn tap_branch_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+  let (a, b) = if left <= right { (left, right) } else { (right, left) };
+  let mut data = a.to_vec();
+  data.extend_from_slice(b);
+  tagged_hash("TapBranch", &data)
+}
+
+/// (leaf version, script, merkle path from the leaf to the root)
+pub type TapLeafPath<'a> = (u8, &'a [u8], Vec<[u8; 32]>);
+
+/// Binary script tree committed to by a taproot output
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum TapTree {
+  Leaf { version: u8, script: Vec<u8> },
+  Branch(Box<TapTree>, Box<TapTree>),
+}
+
+impl TapTree {
+  pub fn leaf(script: Vec<u8>) -> Self {
+    TapTree::Leaf { version: TAPSCRIPT_LEAF_VERSION, script }
+  }
+
+  pub fn leaf_with_version(version: u8, script: Vec<u8>) -> Result<Self, TaprootError> {
+    if version & 1 != 0 {
+      return Err(TaprootError::InvalidLeafVersion(version))
+    }
+    Ok(TapTree::Leaf { version, script })
+  }
+
+  pub fn branch(left: TapTree, right: TapTree) -> Self {
+    TapTree::Branch(Box::new(left), Box::new(right))
+  }
+
+  /// TapLeaf hash of a leaf, TapBranch hash of a subtree; the merkle root for the whole tree
+  pub fn hash(&self) -> [u8; 32] {
+    match self {
+      TapTree::Leaf { version, script } => tap_leaf_hash(*version, script),
+      TapTree::Branch(left, right) => tap_branch_hash(&left.hash(), &right.hash()),
+    }
+  }
+
+  /// every leaf with the sibling hashes from the leaf up to the root
+  pub fn leaves(&self) -> Vec<TapLeafPath<'_>> {
+    match self {
+      TapTree::Leaf { version, script } => vec![(*version, script.as_slice(), vec![])],
+      TapTree::Branch(left, right) => {
+        let (left_hash, right_hash) = (left.hash(), right.hash());
+        let mut leaves = left.leaves();
+        for (_, _, path) in leaves.iter_mut() {
+          path.push(right_hash);
+        }
+        for (version, script, mut path) in right.leaves() {
+          path.push(left_hash);
+          leaves.push((version, script, path));
+        }
+        leaves
+      }
+    }
+  }
+
+  /// control block for spending `script` through the script path:
+  /// (leaf version | parity of Q) || internal key || merkle path;
+  /// None when the script is not a leaf or the key can not be tweaked
+  pub fn control_block(&self, internal_key: &S256Point, version: u8, script: &[u8]) -> Option<Vec<u8>> {
+    let (_, _, path) = self.leaves().into_iter()
+      .find(|(v, s, _)| *v == version && *s == script)?;
+
+    let output_key = internal_key.tap_tweak(Some(self.hash()))?;
+    let parity = if output_key.has_even_y() { 0 } else { 1 };
+
+    let mut out = vec![version | parity];
+    out.extend_from_slice(&xonly(internal_key));
+    for node in path {
+      out.extend_from_slice(&node);
+    }
+    Some(out)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    constants::Gs,
+    helper::{decode_hex, encode_hex},
+    private_key::PrivateKey,
+  };
+
+  fn hex32(s: &str) -> [u8; 32] {
+    decode_hex(s).unwrap().try_into().unwrap()
+  }
+
+  fn internal_key(s: &str) -> S256Point {
+    S256Point::lift_x(&bytes_to_int(&hex32(s))).unwrap()
+  }
+
+  /// BIP341 wallet test vectors, scriptPubKey section
+  #[test]
+  fn bip341_output_keys() {
+    let p = internal_key("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d");
+    let q = p.tap_tweak(None).unwrap();
+    assert_eq!(xonly(&q), hex32("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343"));
+
+    let p = internal_key("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27");
+    let script = decode_hex("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac").unwrap();
+    let tree = TapTree::leaf(script.clone());
+    assert_eq!(tree.hash(), hex32("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"));
+    let q = p.tap_tweak(Some(tree.hash())).unwrap();
+    assert_eq!(xonly(&q), hex32("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3"));
+    assert_eq!(
+      tree.control_block(&p, TAPSCRIPT_LEAF_VERSION, &script).unwrap(),
+      decode_hex("c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27").unwrap()
+    );
+
+    let p = internal_key("ee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592");
+    let script = decode_hex("06424950333431").unwrap();
+    let tree = TapTree::branch(
+      TapTree::leaf(decode_hex("20387671353e273264c495656e27e39ba899ea8fee3bb69fb2a680e22093447d48ac").unwrap()),
+      TapTree::leaf_with_version(250, script.clone()).unwrap(),
+    );
+    assert_eq!(tree.hash(), hex32("6c2dc106ab816b73f9d07e3cd1ef2c8c1256f519748e0813e4edd2405d277bef"));
+    let q = p.tap_tweak(Some(tree.hash())).unwrap();
+    assert_eq!(xonly(&q), hex32("712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5"));
+    assert_eq!(
+      tree.control_block(&p, 250, &script).unwrap(),
+      decode_hex(
+        "faee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf37865928ad69ec7cf41c2a4001fd1f738bf1e505ce2277acdcaa63fe4765192497f47a7"
+      ).unwrap()
+    );
+  }
+
+  /// BIP341 wallet test vectors, keyPathSpending section
+  #[test]
+  fn bip341_tweaked_private_keys() {
+    // (internalPrivkey, merkleRoot, tweakedPrivkey)
+    let cases = [
+      (
+        "6b973d88838f27366ed61c9ad6367663045cb456e28335c109e30717ae0c6baa",
+        None,
+        "2405b971772ad26915c8dcdf10f238753a9b837e5f8e6a86fd7c0cce5b7296d9",
+      ),
+      (
+        "1e4da49f6aaf4e5cd175fe08a32bb5cb4863d963921255f33d3bc31e1343907f",
+        Some("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"),
+        "ea260c3b10e60f6de018455cd0278f2f5b7e454be1999572789e6a9565d26080",
+      ),
+      (
+        "d3c7af07da2d54f7a7735d3d0fc4f0a73164db638b2f2f7c43f711f6d4aa7e64",
+        Some("c525714a7f49c28aedbbba78c005931a81c234b2f6c99a73e4d06082adc8bf2b"),
+        "97323385e57015b75b0339a549c56a948eb961555973f0951f555ae6039ef00d",
+      ),
+      (
+        "f36bb07a11e469ce941d16b63b11b9b9120a84d9d87cff2c84a8d4affb438f4e",
+        Some("ccbd66c6f7e8fdab47b3a486f59d28262be857f30d4773f2d5ea47f7761ce0e2"),
+        "a8e7aa924f0d58854185a490e6c41f6efb7b675c0f3331b7f14b549400b4d501",
+      ),
+      (
+        "415cfe9c15d9cea27d8104d5517c06e9de48e2f986b695e4f5ffebf230e725d8",
+        Some("2f6b2c5397b6d68ca18e09a3f05161668ffe93a988582d55c6f07bd5b3329def"),
+        "241c14f2639d0d7139282aa6abde28dd8a067baa9d633e4e7230287ec2d02901",
+      ),
+      (
+        "c7b0e81f0a9a0b0499e112279d718cca98e79a12e2f137c72ae5b213aad0d103",
+        Some("6c2dc106ab816b73f9d07e3cd1ef2c8c1256f519748e0813e4edd2405d277bef"),
+        "65b6000cd2bfa6b7cf736767a8955760e62b6649058cbc970b7c0871d786346b",
+      ),
+      (
+        "77863416be0d0665e517e1c375fd6f75839544eca553675ef7fdf4949518ebaa",
+        Some("ab179431c28d3b68fb798957faf5497d69c883c6fb1e1cd9f81483d87bac90cc"),
+        "ec18ce6af99f43815db543f47b8af5ff5df3b2cb7315c955aa4a86e8143d2bf5",
+      ),
+    ];
+    for (secret, root, tweaked) in cases {
+      let key = PrivateKey::from_bytes(&hex32(secret)).unwrap();
+      let root = root.map(hex32);
+      let tweaked_key = key.tap_tweak(root).unwrap();
+      assert_eq!(encode_hex(tweaked_key.to_bytes().as_ref()), tweaked);
+      assert_eq!(tweaked_key.point, key.point.tap_tweak(root).unwrap());
+    }
+  }
+
+  #[test]
+  fn odd_leaf_versions_are_rejected() {
+    assert_eq!(TapTree::leaf_with_version(0xc1, vec![0x51]).unwrap_err(), TaprootError::InvalidLeafVersion(0xc1));
+    assert_eq!(TapTree::leaf_with_version(TAPSCRIPT_LEAF_VERSION, vec![0x51]).unwrap(), TapTree::leaf(vec![0x51]));
+  }
+
+  #[test]
+  fn tweak_of_infinity_is_rejected() {
+    assert_eq!(S256Point::infinity().tap_tweak(None), None);
+    let tree = TapTree::leaf(vec![0x51]);
+    assert_eq!(tree.control_block(&Gs.rmul(N.clone()), TAPSCRIPT_LEAF_VERSION, &[0x51]), None);
+  }
+}